@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{file, VaultError};
+
+const ADDRESS_BOOK_FILE_NAME: &str = "address-book.yaml";
+
+/// Public keys of teammates by name. Keeps no secrets, so it lives next to the vault as plain YAML
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub contacts: BTreeMap<String, String>,
+}
+
+impl AddressBook {
+    pub fn get_file_name() -> String {
+        file::compile_full_filename(ADDRESS_BOOK_FILE_NAME)
+    }
+
+    /// A missing file is an empty address book
+    pub fn load() -> Result<Self, VaultError> {
+        let content = match std::fs::read_to_string(Self::get_file_name()) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        serde_yaml::from_str(&content).map_err(|err| VaultError::InvalidData(err.to_string()))
+    }
+
+    pub fn save(&self) -> Result<(), VaultError> {
+        let content =
+            serde_yaml::to_string(self).map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+        std::fs::write(Self::get_file_name(), content)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, name: &str, public_key: &str) -> Result<(), VaultError> {
+        crate::sharing::parse_recipient(public_key)?;
+
+        self.contacts
+            .insert(name.to_string(), public_key.trim().to_string());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.contacts.remove(name).is_some()
+    }
+
+    /// Public key of the contact or the value itself if there is no such contact
+    pub fn resolve<'s>(&'s self, name_or_key: &'s str) -> &'s str {
+        match self.contacts.get(name_or_key) {
+            Some(public_key) => public_key.as_str(),
+            None => name_or_key,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{container, import::ImportedEntry, sharing, TypeContent, Vault, VaultError};
+
+pub const BUNDLE_EXTENSION: &str = "mysecrets";
+
+/// Copies the selected entries into a standalone file encrypted with its own password.
+/// Paths are `category` for a whole category or `category/subcategory` for a single entry
+pub fn create_bundle(
+    vault: &Vault,
+    paths: &[String],
+    password: &str,
+) -> Result<Vec<u8>, VaultError> {
+    let mut json = serialize_entries(vault, paths)?;
+    let result = container::seal(password, &json);
+    zeroize::Zeroize::zeroize(&mut json);
+
+    result
+}
+
+pub fn read_bundle(data: &[u8], password: &str) -> Result<Vec<ImportedEntry>, VaultError> {
+    let mut json = container::open(password, data)?;
+    let result = deserialize_entries(&json);
+    zeroize::Zeroize::zeroize(&mut json);
+
+    result
+}
+
+/// JSON of the selected entries in the same shape as the vault content
+pub(crate) fn serialize_entries(vault: &Vault, paths: &[String]) -> Result<Vec<u8>, VaultError> {
+    let mut selected = TypeContent::new();
+
+    for path in paths {
+        let category = path
+            .split_once('/')
+            .map_or(path.as_str(), |(category, _)| category);
+        sharing::check_not_identity(category)?;
+
+        match path.split_once('/') {
+            Some((category, sub_category)) => {
+                let text = vault.get(category, sub_category)?;
+                selected
+                    .entry(category.to_string())
+                    .or_default()
+                    .insert(sub_category.to_string(), text.to_string());
+            }
+            None => {
+                let sub_categories = vault
+                    .content()?
+                    .get(path)
+                    .ok_or_else(|| VaultError::CategoryNotFound(path.to_string()))?;
+
+                selected
+                    .entry(path.to_string())
+                    .or_default()
+                    .extend(sub_categories.clone());
+            }
+        }
+    }
+
+    if selected.is_empty() {
+        return Err(VaultError::InvalidData("Nothing to export".to_string()));
+    }
+
+    let result = serde_json::to_vec(&selected).unwrap();
+    crate::wipe_content(selected);
+
+    Ok(result)
+}
+
+pub(crate) fn deserialize_entries(json: &[u8]) -> Result<Vec<ImportedEntry>, VaultError> {
+    let mut content: TypeContent =
+        serde_json::from_slice(json).map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+    let mut result = Vec::new();
+
+    while let Some((category, mut sub_categories)) = content.pop_first() {
+        while let Some((sub_category, text)) = sub_categories.pop_first() {
+            result.push(ImportedEntry {
+                category: category.clone(),
+                sub_category,
+                text,
+            });
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,330 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use zeroize::Zeroize;
+
+use crate::VaultError;
+
+/// Header of the encrypted files:
+/// magic | version: u8 | kdf: u8 | m_cost: u32 | t_cost: u32 | p_cost: u32 | salt: [u8; 16] | nonce: [u8; 24]
+/// followed by XChaCha20-Poly1305 ciphertext. The whole header is authenticated as associated data
+const MAGIC: &[u8; 8] = b"MYSECRET";
+const VERSION: u8 = 1;
+const KDF_ARGON2ID: u8 = 1;
+
+const SALT_SIZE: usize = 16;
+pub const NONCE_SIZE: usize = 24;
+pub const KEY_SIZE: usize = 32;
+const HEADER_SIZE: usize = MAGIC.len() + 2 + 12 + SALT_SIZE + NONCE_SIZE;
+
+const M_COST_KIB: u32 = 64 * 1024;
+const T_COST: u32 = 3;
+const P_COST: u32 = 1;
+
+const MAX_M_COST_KIB: u32 = 1024 * 1024;
+const MAX_T_COST: u32 = 16;
+
+pub fn is_container(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypts the data with the key derived from the password
+pub fn seal(password: &str, data: &[u8]) -> Result<Vec<u8>, VaultError> {
+    ContainerKey::derive(password)?.seal(data)
+}
+
+/// Decrypts the data sealed with [`seal`]. Wrong password and modified data are not distinguishable
+pub fn open(password: &str, data: &[u8]) -> Result<Vec<u8>, VaultError> {
+    Ok(ContainerKey::open(password, data)?.1)
+}
+
+/// Key derived from the password together with its KDF parameters.
+/// Kept by the owner to seal the data again without running the KDF with the password
+pub struct ContainerKey {
+    key: [u8; KEY_SIZE],
+    salt: [u8; SALT_SIZE],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl ContainerKey {
+    /// New key with a random salt
+    pub fn derive(password: &str) -> Result<Self, VaultError> {
+        if password.is_empty() {
+            return Err(VaultError::EmptyPassword);
+        }
+
+        let mut salt = [0u8; SALT_SIZE];
+        fill_random(&mut salt)?;
+
+        Ok(Self {
+            key: derive_key(password, &salt, M_COST_KIB, T_COST, P_COST)?,
+            salt,
+            m_cost: M_COST_KIB,
+            t_cost: T_COST,
+            p_cost: P_COST,
+        })
+    }
+
+    /// Decrypts the container returning the key it was sealed with
+    pub fn open(password: &str, data: &[u8]) -> Result<(Self, Vec<u8>), VaultError> {
+        let container = parse_container(data)?;
+
+        let key = Self {
+            key: derive_key(
+                password,
+                &container.salt,
+                container.m_cost,
+                container.t_cost,
+                container.p_cost,
+            )?,
+            salt: container.salt,
+            m_cost: container.m_cost,
+            t_cost: container.t_cost,
+            p_cost: container.p_cost,
+        };
+
+        let result = open_with_key(
+            &key.key,
+            container.nonce,
+            container.header,
+            container.encrypted,
+        )?;
+
+        Ok((key, result))
+    }
+
+    /// Decrypts another container sealed with this key without the password
+    pub fn open_sealed(&self, data: &[u8]) -> Result<Vec<u8>, VaultError> {
+        let container = parse_container(data)?;
+
+        if container.salt != self.salt
+            || container.m_cost != self.m_cost
+            || container.t_cost != self.t_cost
+            || container.p_cost != self.p_cost
+        {
+            return Err(VaultError::InvalidPassword);
+        }
+
+        open_with_key(
+            &self.key,
+            container.nonce,
+            container.header,
+            container.encrypted,
+        )
+    }
+
+    /// Encrypts with a new nonce each time
+    pub fn seal(&self, data: &[u8]) -> Result<Vec<u8>, VaultError> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        fill_random(&mut nonce)?;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        header.push(KDF_ARGON2ID);
+        header.extend_from_slice(&self.m_cost.to_le_bytes());
+        header.extend_from_slice(&self.t_cost.to_le_bytes());
+        header.extend_from_slice(&self.p_cost.to_le_bytes());
+        header.extend_from_slice(&self.salt);
+        header.extend_from_slice(&nonce);
+
+        let encrypted = seal_with_key(&self.key, &nonce, &header, data)?;
+
+        let mut result = header;
+        result.extend_from_slice(&encrypted);
+        Ok(result)
+    }
+}
+
+impl Drop for ContainerKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+struct Container<'s> {
+    salt: [u8; SALT_SIZE],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    nonce: &'s [u8],
+    header: &'s [u8],
+    encrypted: &'s [u8],
+}
+
+fn parse_container(data: &[u8]) -> Result<Container<'_>, VaultError> {
+    if data.len() < HEADER_SIZE || !is_container(data) {
+        return Err(VaultError::InvalidData("Unknown file format".to_string()));
+    }
+
+    let (header, encrypted) = data.split_at(HEADER_SIZE);
+
+    let version = header[MAGIC.len()];
+    let kdf = header[MAGIC.len() + 1];
+
+    if version != VERSION || kdf != KDF_ARGON2ID {
+        return Err(VaultError::InvalidData(format!(
+            "Unsupported file version {}",
+            version
+        )));
+    }
+
+    let mut pos = MAGIC.len() + 2;
+    let mut read_u32 = || {
+        let result = u32::from_le_bytes(header[pos..pos + 4].try_into().unwrap());
+        pos += 4;
+        result
+    };
+
+    let m_cost = read_u32();
+    let t_cost = read_u32();
+    let p_cost = read_u32();
+
+    // Parameters come from the file, do not let it allocate unlimited memory
+    if m_cost > MAX_M_COST_KIB || t_cost > MAX_T_COST {
+        return Err(VaultError::InvalidData(
+            "Unsupported key derivation parameters".to_string(),
+        ));
+    }
+
+    let mut salt = [0u8; SALT_SIZE];
+    salt.copy_from_slice(&header[pos..pos + SALT_SIZE]);
+
+    Ok(Container {
+        salt,
+        m_cost,
+        t_cost,
+        p_cost,
+        nonce: &header[pos + SALT_SIZE..],
+        header,
+        encrypted,
+    })
+}
+
+/// Encrypts with a ready key. Used where the key is not derived from a password
+pub fn seal_with_key(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8],
+    associated_data: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, VaultError> {
+    XChaCha20Poly1305::new(key.into())
+        .encrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: data,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| VaultError::InvalidData("Can not encrypt data".to_string()))
+}
+
+pub fn open_with_key(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8],
+    associated_data: &[u8],
+    encrypted: &[u8],
+) -> Result<Vec<u8>, VaultError> {
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: encrypted,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| VaultError::InvalidPassword)
+}
+
+pub fn fill_random(buffer: &mut [u8]) -> Result<(), VaultError> {
+    getrandom::fill(buffer)
+        .map_err(|err| VaultError::InvalidData(format!("Can not generate random data: {}", err)))
+}
+
+fn derive_key(
+    password: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<[u8; KEY_SIZE], VaultError> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_SIZE))
+        .map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+    let mut result = [0u8; KEY_SIZE];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut result)
+        .map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_sealed_data() {
+        let sealed = seal("password", b"secret data").unwrap();
+
+        assert!(is_container(&sealed));
+        assert_eq!(open("password", &sealed).unwrap(), b"secret data");
+        assert!(matches!(
+            open("wrong", &sealed),
+            Err(VaultError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn key_opens_containers_sealed_again() {
+        let sealed = seal("password", b"first").unwrap();
+        let (key, _) = ContainerKey::open("password", &sealed).unwrap();
+
+        let sealed_again = key.seal(b"second").unwrap();
+
+        assert_ne!(sealed[..HEADER_SIZE], sealed_again[..HEADER_SIZE]);
+        assert_eq!(key.open_sealed(&sealed_again).unwrap(), b"second");
+        assert_eq!(open("password", &sealed_again).unwrap(), b"second");
+    }
+
+    #[test]
+    fn detects_modified_data() {
+        let key = ContainerKey::derive("password").unwrap();
+        let sealed = key.seal(b"secret data").unwrap();
+
+        let mut modified_body = sealed.clone();
+        *modified_body.last_mut().unwrap() ^= 1;
+        assert!(key.open_sealed(&modified_body).is_err());
+
+        // The nonce is the end of the header
+        let mut modified_header = sealed;
+        modified_header[HEADER_SIZE - 1] ^= 1;
+        assert!(key.open_sealed(&modified_header).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_data() {
+        assert!(matches!(
+            open("password", b"not a container"),
+            Err(VaultError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_too_expensive_parameters() {
+        let key = ContainerKey::derive("password").unwrap();
+        let mut sealed = key.seal(b"secret data").unwrap();
+
+        let m_cost_pos = MAGIC.len() + 2;
+        sealed[m_cost_pos..m_cost_pos + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            open("password", &sealed),
+            Err(VaultError::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{container, sealed_category, TypeContent, VaultCommand, VaultError};
+
+/// The encrypted part of files written before entries had IDs is the bare content.
+/// The document starts with this text, which no content starts with, since a category value is an object
+const DOCUMENT_PREFIX: &[u8] = b"{\"format\":\"my-secrets-vault\"";
+const DOCUMENT_FORMAT: &str = "my-secrets-vault";
+const DOCUMENT_VERSION: u32 = 1;
+
+const ID_SIZE: usize = 16;
+
+/// Stable IDs of the entries by category and subcategory. Stored in the file next to the content,
+/// so an entry renamed or moved on one copy of the vault is still the same entry when the copies are merged.
+/// Entries of sealed categories have no IDs, their names are not stored outside the sealed container
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct EntryIds(BTreeMap<String, BTreeMap<String, String>>);
+
+impl EntryIds {
+    /// IDs of a file written before entries had them. Every copy of such file gets the same IDs
+    pub fn from_paths(content: &TypeContent) -> Self {
+        let mut result = Self::default();
+        let mut used = BTreeSet::new();
+
+        for (category, sub_categories) in content {
+            if sealed_category::is_sealed(sub_categories) {
+                continue;
+            }
+
+            for sub_category in sub_categories.keys() {
+                let id = derive_id(category, sub_category, |id| used.contains(id));
+                used.insert(id.clone());
+                result.set(category, sub_category, id);
+            }
+        }
+
+        result
+    }
+
+    pub fn get(&self, category: &str, sub_category: &str) -> Option<&str> {
+        self.0
+            .get(category)
+            .and_then(|ids| ids.get(sub_category))
+            .map(|id| id.as_str())
+    }
+
+    pub(crate) fn set(&mut self, category: &str, sub_category: &str, id: String) {
+        self.0
+            .entry(category.to_string())
+            .or_default()
+            .insert(sub_category.to_string(), id);
+    }
+
+    /// Moves the IDs along with renamed and moved entries.
+    /// IDs of deleted entries are kept until the next save, so undoing the delete gets them back
+    pub(crate) fn apply(&mut self, command: &VaultCommand) {
+        match command {
+            VaultCommand::RenameCategory { from, to } => {
+                if let Some(ids) = self.0.remove(from) {
+                    self.0.insert(to.clone(), ids);
+                }
+            }
+            VaultCommand::RenameSubCategory { category, from, to } => {
+                if let Some(id) = self.remove(category, from) {
+                    self.set(category, to, id);
+                }
+            }
+            VaultCommand::MoveSubCategory {
+                name,
+                from_category,
+                to_category,
+            } => {
+                if let Some(id) = self.remove(from_category, name) {
+                    self.set(to_category, name, id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Drops IDs of the entries which are gone and gives new entries random IDs.
+    /// Categories for which `is_sealed` is true keep no IDs
+    pub(crate) fn sync(
+        &mut self,
+        content: &TypeContent,
+        is_sealed: impl Fn(&str) -> bool,
+    ) -> Result<(), VaultError> {
+        let mut result = BTreeMap::new();
+
+        for (category, sub_categories) in content {
+            if is_sealed(category) || sealed_category::is_sealed(sub_categories) {
+                continue;
+            }
+
+            let mut ids = BTreeMap::new();
+
+            for sub_category in sub_categories.keys() {
+                let id = match self.get(category, sub_category) {
+                    Some(id) => id.to_string(),
+                    None => generate_id()?,
+                };
+
+                ids.insert(sub_category.clone(), id);
+            }
+
+            result.insert(category.clone(), ids);
+        }
+
+        self.0 = result;
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, category: &str, sub_category: &str) -> Option<String> {
+        self.0
+            .get_mut(category)
+            .and_then(|ids| ids.remove(sub_category))
+    }
+}
+
+/// ID of an entry without a stored one: its path, made unique within the version
+pub(crate) fn derive_id(
+    category: &str,
+    sub_category: &str,
+    is_used: impl Fn(&str) -> bool,
+) -> String {
+    let id = format!("{}/{}", category, sub_category);
+
+    if !is_used(&id) {
+        return id;
+    }
+
+    (2..)
+        .map(|index| format!("{}#{}", id, index))
+        .find(|id| !is_used(id))
+        .unwrap()
+}
+
+fn generate_id() -> Result<String, VaultError> {
+    let mut id = [0u8; ID_SIZE];
+    container::fill_random(&mut id)?;
+
+    Ok(id.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[derive(Serialize)]
+struct Document<'s> {
+    format: &'s str,
+    version: u32,
+    content: &'s TypeContent,
+    ids: &'s EntryIds,
+}
+
+#[derive(Deserialize)]
+struct StoredDocument {
+    version: u32,
+    content: TypeContent,
+    ids: EntryIds,
+}
+
+/// JSON encrypted in the vault file
+pub(crate) fn to_document(content: &TypeContent, ids: &EntryIds) -> Vec<u8> {
+    serde_json::to_vec(&Document {
+        format: DOCUMENT_FORMAT,
+        version: DOCUMENT_VERSION,
+        content,
+        ids,
+    })
+    .unwrap()
+}
+
+/// Reads the document or the bare content of older files, giving their entries IDs from the paths
+pub(crate) fn from_document(json: &[u8]) -> Result<(TypeContent, EntryIds), VaultError> {
+    if !json.starts_with(DOCUMENT_PREFIX) {
+        let content: TypeContent =
+            serde_json::from_slice(json).map_err(|err| VaultError::InvalidData(err.to_string()))?;
+        let ids = EntryIds::from_paths(&content);
+        return Ok((content, ids));
+    }
+
+    let document: StoredDocument =
+        serde_json::from_slice(json).map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+    if document.version != DOCUMENT_VERSION {
+        let version = document.version;
+        crate::wipe_content(document.content);
+        return Err(VaultError::InvalidData(format!(
+            "Unsupported vault version {}",
+            version
+        )));
+    }
+
+    Ok((document.content, document.ids))
+}
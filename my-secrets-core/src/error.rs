@@ -0,0 +1,124 @@
+#[derive(Debug)]
+pub enum VaultError {
+    Locked,
+    EmptyPassword,
+    InvalidPassword,
+    CategoryNotFound(String),
+    CategoryAlreadyExists(String),
+    CategoryLocked(String),
+    SubCategoryNotFound {
+        category: String,
+        sub_category: String,
+    },
+    SubCategoryAlreadyExists {
+        category: String,
+        sub_category: String,
+    },
+    FieldNotFound {
+        category: String,
+        sub_category: String,
+        field: String,
+    },
+    InvalidData(String),
+    Template(String),
+    Import(String),
+    Gpg(String),
+    Sharing(String),
+    Team(String),
+    Git(String),
+    Server(String),
+    Storage(String),
+    /// The remote storage of the vault can not be reached
+    Offline(String),
+    /// The unlock agent is not running or can not give the key
+    Agent(String),
+    /// Another program wrote the vault file since it was loaded
+    FileChanged,
+    /// Another process has the vault open. It can only be read
+    FileLocked(crate::file_lock::LockOwner),
+    Io(std::io::Error),
+}
+
+impl VaultError {
+    pub fn sub_category_not_found(category: &str, sub_category: &str) -> Self {
+        VaultError::SubCategoryNotFound {
+            category: category.to_string(),
+            sub_category: sub_category.to_string(),
+        }
+    }
+
+    pub fn sub_category_already_exists(category: &str, sub_category: &str) -> Self {
+        VaultError::SubCategoryAlreadyExists {
+            category: category.to_string(),
+            sub_category: sub_category.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for VaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VaultError::Locked => write!(f, "Vault is locked"),
+            VaultError::EmptyPassword => write!(f, "Password can not be empty"),
+            VaultError::InvalidPassword => write!(f, "Invalid password"),
+            VaultError::CategoryNotFound(category) => {
+                write!(f, "Category {} not found", category)
+            }
+            VaultError::CategoryAlreadyExists(category) => {
+                write!(f, "Category {} already exists", category)
+            }
+            VaultError::CategoryLocked(category) => {
+                write!(f, "Category {} is locked", category)
+            }
+            VaultError::SubCategoryNotFound {
+                category,
+                sub_category,
+            } => write!(f, "Subcategory {}/{} not found", category, sub_category),
+            VaultError::SubCategoryAlreadyExists {
+                category,
+                sub_category,
+            } => write!(
+                f,
+                "Subcategory {}/{} already exists",
+                category, sub_category
+            ),
+            VaultError::FieldNotFound {
+                category,
+                sub_category,
+                field,
+            } => write!(
+                f,
+                "Field {} not found in {}/{}",
+                field, category, sub_category
+            ),
+            VaultError::InvalidData(err) => write!(f, "Invalid data: {}", err),
+            VaultError::Template(err) => write!(f, "Can not render template:\n{}", err),
+            VaultError::Import(err) => write!(f, "Can not import: {}", err),
+            VaultError::Gpg(err) => write!(f, "GPG error: {}", err),
+            VaultError::Sharing(err) => write!(f, "Can not share: {}", err),
+            VaultError::Team(err) => write!(f, "Team vault: {}", err),
+            VaultError::Git(err) => write!(f, "Git error: {}", err),
+            VaultError::Server(err) => write!(f, "Sync server: {}", err),
+            VaultError::Storage(err) => write!(f, "Storage error: {}", err),
+            VaultError::Offline(err) => write!(f, "{}", err),
+            VaultError::Agent(err) => write!(f, "Unlock agent: {}", err),
+            VaultError::FileChanged => {
+                write!(f, "The vault file was changed by another program")
+            }
+            VaultError::FileLocked(owner) => write!(
+                f,
+                "The vault is open in another process (pid {} on {})",
+                owner.pid, owner.host
+            ),
+            VaultError::Io(err) => write!(f, "IO error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+impl From<std::io::Error> for VaultError {
+    fn from(err: std::io::Error) -> Self {
+        VaultError::Io(err)
+    }
+}
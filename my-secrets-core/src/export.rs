@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{fields, template::TemplateFormat, Vault, VaultError};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyCase {
+    /// `db-password` -> `DB_PASSWORD`
+    #[default]
+    UpperSnake,
+    /// `DB Password` -> `db_password`
+    LowerSnake,
+    /// `DB Password` -> `db-password`
+    Kebab,
+    /// Name of the entry is used as is
+    AsIs,
+}
+
+/// How entries of a category become keys of the exported secret
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct KeyNamingRules {
+    #[serde(default)]
+    pub case: KeyCase,
+    #[serde(default)]
+    pub prefix: String,
+    /// Export `name: value` lines of every entry as separate `<entry>_<name>` keys
+    #[serde(default)]
+    pub fields: bool,
+}
+
+impl KeyNamingRules {
+    pub fn compile_key(&self, name: &str) -> String {
+        let name = format!("{}{}", self.prefix, name);
+
+        match self.case {
+            KeyCase::UpperSnake => fields::to_env_name(&name),
+            KeyCase::LowerSnake => fields::to_env_name(&name).to_lowercase(),
+            KeyCase::Kebab => fields::to_env_name(&name).to_lowercase().replace('_', "-"),
+            KeyCase::AsIs => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Kubernetes,
+    Env,
+    DockerCompose,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "k8s" | "kubernetes" => Some(Self::Kubernetes),
+            "env" | "dotenv" => Some(Self::Env),
+            "compose" | "docker-compose" => Some(Self::DockerCompose),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Kubernetes => "kubernetes",
+            Self::Env => "env",
+            Self::DockerCompose => "docker-compose",
+        }
+    }
+}
+
+pub struct ExportItem {
+    pub key: String,
+    pub value: String,
+}
+
+impl Drop for ExportItem {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.value);
+    }
+}
+
+/// Result of the export. Docker compose layout consists of the compose file and a file per secret
+pub struct ExportedFile {
+    pub file_name: String,
+    pub content: String,
+}
+
+impl Drop for ExportedFile {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.content);
+    }
+}
+
+/// Collects the keys of the category. If entries are given only they are exported
+pub fn collect_items(
+    vault: &Vault,
+    category: &str,
+    entries: Option<&[String]>,
+    rules: &KeyNamingRules,
+) -> Result<Vec<ExportItem>, VaultError> {
+    crate::sharing::check_not_identity(category)?;
+
+    let sub_categories: Vec<&str> = match entries {
+        Some(entries) => entries.iter().map(|entry| entry.as_str()).collect(),
+        None => vault.list_sub_categories(category)?,
+    };
+
+    let mut result: Vec<ExportItem> = Vec::new();
+
+    for sub_category in sub_categories {
+        let text = vault.get(category, sub_category)?;
+
+        if rules.fields {
+            for (field, value) in fields::parse_fields(text) {
+                result.push(ExportItem {
+                    key: rules.compile_key(&format!("{}_{}", sub_category, field)),
+                    value: value.to_string(),
+                });
+            }
+        } else {
+            result.push(ExportItem {
+                key: rules.compile_key(sub_category),
+                value: text.to_string(),
+            });
+        }
+    }
+
+    for (index, item) in result.iter().enumerate() {
+        if result[..index].iter().any(|other| other.key == item.key) {
+            return Err(VaultError::InvalidData(format!(
+                "Several entries of category {} are exported as the same key {}",
+                category, item.key
+            )));
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KubernetesSecret<'s> {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: KubernetesMetadata<'s>,
+    #[serde(rename = "type")]
+    secret_type: &'static str,
+    data: BTreeMap<&'s str, String>,
+}
+
+#[derive(Serialize)]
+struct KubernetesMetadata<'s> {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<&'s str>,
+}
+
+/// `kind: Secret` manifest with base64 encoded data
+pub fn to_kubernetes_secret(
+    name: &str,
+    namespace: Option<&str>,
+    items: &[ExportItem],
+) -> Result<String, VaultError> {
+    let mut data = BTreeMap::new();
+
+    for item in items {
+        if !is_valid_key(&item.key) {
+            return Err(VaultError::InvalidData(format!(
+                "{} is not a valid Kubernetes secret key",
+                item.key
+            )));
+        }
+
+        data.insert(
+            item.key.as_str(),
+            base64::engine::general_purpose::STANDARD.encode(item.value.as_bytes()),
+        );
+    }
+
+    let secret = KubernetesSecret {
+        api_version: "v1",
+        kind: "Secret",
+        metadata: KubernetesMetadata {
+            name: to_kubernetes_name(name)?,
+            namespace,
+        },
+        secret_type: "Opaque",
+        data,
+    };
+
+    serde_yaml::to_string(&secret).map_err(|err| VaultError::InvalidData(err.to_string()))
+}
+
+/// Letters, digits, `-`, `_` and `.` as Kubernetes allows. Names kept as is can have anything else
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+pub fn to_env_file(items: &[ExportItem]) -> Result<String, VaultError> {
+    let mut result = String::new();
+
+    for item in items {
+        if !is_valid_key(&item.key) {
+            return Err(VaultError::InvalidData(format!(
+                "{} is not a valid .env key",
+                item.key
+            )));
+        }
+
+        result.push_str(&item.key);
+        result.push('=');
+        result.push_str(&TemplateFormat::Env.escape(&item.value));
+        result.push('\n');
+    }
+
+    Ok(result)
+}
+
+/// Compose file with the top level `secrets:` section and a file with the value for every secret
+pub fn to_docker_compose(
+    items: &[ExportItem],
+    secrets_dir: &str,
+) -> Result<Vec<ExportedFile>, VaultError> {
+    let mut compose = String::from("secrets:\n");
+    let mut result = Vec::new();
+
+    for item in items {
+        // The key is the name of the file in the secrets directory
+        let is_valid_key =
+            !matches!(item.key.as_str(), "" | "." | "..") && !item.key.contains(['/', '\\', '\0']);
+
+        if !is_valid_key {
+            return Err(VaultError::InvalidData(format!(
+                "{} can not be a file name of a Docker secret",
+                item.key
+            )));
+        }
+
+        let file_name = format!("{}/{}", secrets_dir, item.key);
+        compose.push_str(&format!(
+            "  {}:\n    file: {}\n",
+            serde_json::to_string(&item.key).unwrap(),
+            serde_json::to_string(&format!("./{}", file_name)).unwrap()
+        ));
+
+        result.push(ExportedFile {
+            file_name,
+            content: item.value.clone(),
+        });
+    }
+
+    result.insert(
+        0,
+        ExportedFile {
+            file_name: "docker-compose.secrets.yml".to_string(),
+            content: compose,
+        },
+    );
+
+    Ok(result)
+}
+
+/// Kubernetes object names are lowercase letters, digits and dashes
+fn to_kubernetes_name(name: &str) -> Result<String, VaultError> {
+    let result = fields::to_env_name(name).to_lowercase().replace('_', "-");
+    let result = result.trim_matches('-');
+
+    if result.is_empty() {
+        return Err(VaultError::InvalidData(format!(
+            "{} has no letters or digits for a Kubernetes name. Please specify the name",
+            name
+        )));
+    }
+
+    Ok(result.to_string())
+}
+
+pub struct ExportRequest<'s> {
+    pub category: &'s str,
+    /// Export only these entries of the category
+    pub entries: Option<&'s [String]>,
+    pub rules: &'s KeyNamingRules,
+    pub format: ExportFormat,
+    /// Name of the Kubernetes secret. Category name is used if not set
+    pub name: Option<&'s str>,
+    pub namespace: Option<&'s str>,
+}
+
+pub fn export_category(
+    vault: &Vault,
+    request: &ExportRequest,
+) -> Result<Vec<ExportedFile>, VaultError> {
+    let items = collect_items(vault, request.category, request.entries, request.rules)?;
+
+    let result = match request.format {
+        ExportFormat::Kubernetes => {
+            let name = request.name.unwrap_or(request.category);
+            vec![ExportedFile {
+                file_name: format!("{}.yaml", to_kubernetes_name(name)?),
+                content: to_kubernetes_secret(name, request.namespace, &items)?,
+            }]
+        }
+        ExportFormat::Env => vec![ExportedFile {
+            file_name: ".env".to_string(),
+            content: to_env_file(&items)?,
+        }],
+        ExportFormat::DockerCompose => to_docker_compose(&items, "secrets")?,
+    };
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(key: &str) -> ExportItem {
+        ExportItem {
+            key: key.to_string(),
+            value: "value".to_string(),
+        }
+    }
+
+    #[test]
+    fn writes_docker_compose_secrets() {
+        let files = to_docker_compose(&[item("DB_PASSWORD"), item("api: key")], "secrets").unwrap();
+
+        assert_eq!(
+            files[0].content,
+            "secrets:\n  \"DB_PASSWORD\":\n    file: \"./secrets/DB_PASSWORD\"\n  \"api: key\":\n    file: \"./secrets/api: key\"\n"
+        );
+        assert_eq!(files[1].file_name, "secrets/DB_PASSWORD");
+        assert_eq!(files[1].content, "value");
+    }
+
+    #[test]
+    fn refuses_to_export_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = Vault::open(dir.path().join("vault").to_string_lossy(), [0; 16]);
+        vault.unlock("password").unwrap();
+        vault
+            .put(
+                crate::sharing::IDENTITY_CATEGORY,
+                crate::sharing::IDENTITY_SUB_CATEGORY,
+                crate::sharing::generate_identity_text(),
+            )
+            .unwrap();
+
+        let rules = KeyNamingRules::default();
+
+        assert!(collect_items(&vault, crate::sharing::IDENTITY_CATEGORY, None, &rules).is_err());
+        assert!(crate::bundle::create_bundle(
+            &vault,
+            &[crate::sharing::IDENTITY_CATEGORY.to_string()],
+            "password"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_docker_secret_keys_leaving_directory() {
+        for key in ["../etc/passwd", "a/b", "a\\b", "..", ".", "", "a\0b"] {
+            assert!(
+                to_docker_compose(&[item(key)], "secrets").is_err(),
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_docker_secret_keys_with_dots() {
+        let files = to_docker_compose(&[item("a..b"), item(".hidden")], "secrets").unwrap();
+
+        assert_eq!(files[1].file_name, "secrets/a..b");
+        assert_eq!(files[2].file_name, "secrets/.hidden");
+    }
+
+    #[test]
+    fn rejects_env_keys_kept_as_is() {
+        let rules = KeyNamingRules {
+            case: KeyCase::AsIs,
+            ..KeyNamingRules::default()
+        };
+
+        for name in ["db password", "a=b", "a\nB=c", ""] {
+            let items = [ExportItem {
+                key: rules.compile_key(name),
+                value: "value".to_string(),
+            }];
+            assert!(to_env_file(&items).is_err(), "{}", name);
+        }
+
+        assert_eq!(
+            to_env_file(&[item("db.password")]).unwrap(),
+            "db.password=\"value\"\n"
+        );
+    }
+
+    #[test]
+    fn compiles_kubernetes_name() {
+        assert_eq!(
+            to_kubernetes_name("My App_Secrets").unwrap(),
+            "my-app-secrets"
+        );
+        assert!(to_kubernetes_name("---").is_err());
+        assert!(to_kubernetes_name("").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+/// Secrets are free text, but lines like `password: qwerty` or `USER=admin`
+/// are treated as named fields of the entry. The colon has to be followed by a whitespace,
+/// so `https://example.com` stays a text line.
+pub fn parse_fields(text: &str) -> Vec<(&str, &str)> {
+    let mut result = Vec::new();
+
+    for line in text.lines() {
+        if let Some(field) = parse_field_line(line) {
+            result.push(field);
+        }
+    }
+
+    result
+}
+
+pub fn get_field<'s>(text: &'s str, field_name: &str) -> Option<&'s str> {
+    parse_fields(text)
+        .into_iter()
+        .find(|(name, _)| *name == field_name)
+        .map(|(_, value)| value)
+}
+
+fn parse_field_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let separator_pos = line.find([':', '='])?;
+
+    let name = line[..separator_pos].trim();
+    let value = &line[separator_pos + 1..];
+
+    if line[separator_pos..].starts_with(':')
+        && !value.is_empty()
+        && !value.starts_with(char::is_whitespace)
+    {
+        return None;
+    }
+
+    if !is_field_name(name) {
+        return None;
+    }
+
+    Some((name, value.trim()))
+}
+
+/// Letters, digits, `_`, `-` and `.`: `password`, `db-password`, `api.key`
+fn is_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// `db-password` -> `DB_PASSWORD`
+pub fn to_env_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            result.push(c.to_ascii_uppercase());
+        } else {
+            result.push('_');
+        }
+    }
+
+    if result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_field_lines() {
+        let text = "login: admin\nPASSWORD = qwerty\n\nfree text line\n# comment: skipped";
+
+        assert_eq!(
+            parse_fields(text),
+            vec![("login", "admin"), ("PASSWORD", "qwerty")]
+        );
+    }
+
+    #[test]
+    fn keeps_separators_inside_value() {
+        assert_eq!(
+            get_field("connection: host=db port=5432", "connection"),
+            Some("host=db port=5432")
+        );
+    }
+
+    #[test]
+    fn skips_names_with_whitespaces() {
+        assert_eq!(parse_fields("my password: qwerty"), vec![]);
+    }
+
+    #[test]
+    fn skips_urls_and_invalid_names() {
+        let text =
+            "https://example.com/login?user=admin\n$(rm -rf): x\nnote:\nurl=https://example.com";
+
+        assert_eq!(
+            parse_fields(text),
+            vec![("note", ""), ("url", "https://example.com")]
+        );
+    }
+
+    #[test]
+    fn missing_field() {
+        assert_eq!(get_field("login: admin", "password"), None);
+    }
+
+    #[test]
+    fn converts_to_env_name() {
+        assert_eq!(to_env_name("db-password"), "DB_PASSWORD");
+        assert_eq!(to_env_name("api.key"), "API_KEY");
+        assert_eq!(to_env_name("2fa"), "_2FA");
+    }
+}
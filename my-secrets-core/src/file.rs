@@ -0,0 +1,170 @@
+use std::fs;
+
+use encryption::aes::AesKey;
+use zeroize::Zeroize;
+
+use crate::{
+    entry_ids::{self, EntryIds},
+    storage::{Storage, WriteCondition, Written},
+    TypeContent, VaultError,
+};
+
+pub const DEFAULT_FILE_NAME: &str = ".my-secrets-data";
+
+pub fn compile_full_filename(file_name: &str) -> String {
+    format!(
+        "{}/.my-secrets/{}",
+        std::env::var("HOME").unwrap(),
+        file_name
+    )
+}
+
+/// Reads and decrypts the vault file. A missing file is an empty vault
+pub fn load_file(
+    storage: &dyn Storage,
+    aes_key: &AesKey,
+) -> Result<(TypeContent, EntryIds), VaultError> {
+    match storage.load()? {
+        Some(stored) => decrypt_content(aes_key, &stored.data),
+        None => Ok(Default::default()),
+    }
+}
+
+pub fn save_to_file(
+    storage: &dyn Storage,
+    aes_key: &AesKey,
+    content: &TypeContent,
+    ids: &EntryIds,
+) -> Result<Written, VaultError> {
+    let encrypted = encrypt_content(aes_key, content, ids);
+    save_data_to_file(storage, &encrypted, WriteCondition::Any)
+}
+
+/// Writes already encrypted data keeping the previous file as a backup
+pub fn save_data_to_file(
+    storage: &dyn Storage,
+    data: &[u8],
+    condition: WriteCondition,
+) -> Result<Written, VaultError> {
+    // A file changed by another program is kept as it is, there is nothing to back up
+    if !matches!(condition, WriteCondition::Any)
+        && !condition.holds(storage.get_version()?.as_deref())
+    {
+        return Err(VaultError::FileChanged);
+    }
+
+    save_current_as_backup(storage);
+    storage.write(data, condition)
+}
+
+pub fn decrypt_content(
+    aes_key: &AesKey,
+    encrypted: &[u8],
+) -> Result<(TypeContent, EntryIds), VaultError> {
+    let mut json = match aes_key.decrypt(encrypted) {
+        Ok(result) => result,
+        Err(_) => return Err(VaultError::InvalidPassword),
+    };
+
+    // A wrong key may decrypt to garbage instead of failing
+    let result = entry_ids::from_document(&json).map_err(|_| VaultError::InvalidPassword);
+    json.zeroize();
+
+    result
+}
+
+pub fn encrypt_content(aes_key: &AesKey, content: &TypeContent, ids: &EntryIds) -> Vec<u8> {
+    let mut json = entry_ids::to_document(content, ids);
+
+    let encrypted = aes_key.encrypt(&json);
+    json.zeroize();
+
+    encrypted
+}
+
+/// Creates or overwrites the file making it readable only by the user
+pub fn write_private_file(file_name: &str, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut open_options = fs::OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(0o600);
+    }
+
+    let mut file = open_options.open(file_name)?;
+
+    // The mode applies only when the file is created
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(content)
+}
+
+pub fn save_current_as_backup(storage: &dyn Storage) {
+    if let Err(err) = storage.backup() {
+        eprintln!(
+            "Can not back up file: {}. Err: {}",
+            storage.get_location(),
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stored version that never changes
+    struct FixedStorage {
+        backed_up: std::cell::Cell<bool>,
+    }
+
+    impl Storage for FixedStorage {
+        fn read(&self) -> Result<Option<crate::storage::StoredFile>, VaultError> {
+            Ok(None)
+        }
+
+        fn get_version(&self) -> Result<Option<String>, VaultError> {
+            Ok(Some("1".to_string()))
+        }
+
+        fn write(&self, _data: &[u8], condition: WriteCondition) -> Result<Written, VaultError> {
+            match condition.holds(Some("1")) {
+                true => Ok(Written::Stored("2".to_string())),
+                false => Err(VaultError::FileChanged),
+            }
+        }
+
+        fn backup(&self) -> Result<(), VaultError> {
+            self.backed_up.set(true);
+            Ok(())
+        }
+
+        fn get_location(&self) -> String {
+            "fixed".to_string()
+        }
+    }
+
+    #[test]
+    fn backs_up_only_file_to_replace() {
+        let storage = FixedStorage {
+            backed_up: std::cell::Cell::new(false),
+        };
+
+        assert!(matches!(
+            save_data_to_file(&storage, b"data", WriteCondition::Version("0")),
+            Err(VaultError::FileChanged)
+        ));
+        assert!(!storage.backed_up.get());
+
+        save_data_to_file(&storage, b"data", WriteCondition::Version("1")).unwrap();
+        assert!(storage.backed_up.get());
+    }
+}
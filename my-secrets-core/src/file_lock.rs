@@ -0,0 +1,284 @@
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::VaultError;
+
+/// Liveness of a process on another host can not be checked. Its lock is stale
+/// once it is not refreshed for this long
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Process holding the lock
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: get_host_name(),
+        }
+    }
+}
+
+/// Advisory `<vault>.lock` file with the owner process. Another process finding it opens the vault read-only.
+/// The file is removed when the lock is dropped
+pub struct FileLock {
+    file_name: String,
+    owner: LockOwner,
+    refreshed_at: Instant,
+}
+
+impl FileLock {
+    /// Locks of dead processes on this host and locks of other hosts not refreshed for a while are taken over
+    pub fn acquire(vault_file_name: &str) -> Result<Self, VaultError> {
+        let lock = Self {
+            file_name: format!("{}.lock", vault_file_name),
+            owner: LockOwner::current(),
+            refreshed_at: Instant::now(),
+        };
+
+        for _ in 0..2 {
+            match lock.create() {
+                Ok(()) => return Ok(lock),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            let data = lock.read().unwrap_or_default();
+
+            match parse_owner(&data) {
+                Some(owner) if !lock.is_stale(&owner) => {
+                    return Err(VaultError::FileLocked(owner));
+                }
+                // Another process may have taken the stale lock meanwhile
+                _ => lock.remove_if_unchanged(&data)?,
+            }
+        }
+
+        Err(VaultError::InvalidData(format!(
+            "Can not create the lock file {}",
+            lock.file_name
+        )))
+    }
+
+    /// Takes the lock from the process holding it. Only when the user knows it is not running
+    pub fn take_over(vault_file_name: &str) -> Result<Self, VaultError> {
+        let lock = Self {
+            file_name: format!("{}.lock", vault_file_name),
+            owner: LockOwner::current(),
+            refreshed_at: Instant::now(),
+        };
+
+        lock.remove()?;
+        lock.create()?;
+
+        Ok(lock)
+    }
+
+    /// Fails with [`VaultError::FileLocked`] if another process took the lock over.
+    /// A removed lock file is created again
+    pub fn check_owner(&self) -> Result<(), VaultError> {
+        match self.read_owner() {
+            Some(owner) if owner == self.owner => Ok(()),
+            Some(owner) => Err(VaultError::FileLocked(owner)),
+            None => match self.create() {
+                Ok(()) => Ok(()),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    match self.read_owner() {
+                        Some(owner) if owner == self.owner => Ok(()),
+                        Some(owner) => Err(VaultError::FileLocked(owner)),
+                        None => Err(err.into()),
+                    }
+                }
+                Err(err) => Err(err.into()),
+            },
+        }
+    }
+
+    /// Keeps the lock of a long running process from becoming stale for other hosts
+    pub fn refresh_if_needed(&mut self) {
+        if self.refreshed_at.elapsed() < REFRESH_INTERVAL {
+            return;
+        }
+
+        self.refreshed_at = Instant::now();
+
+        if self.read_owner().as_ref() != Some(&self.owner) {
+            return;
+        }
+
+        if let Err(err) = crate::file::write_private_file(&self.file_name, &self.to_json()) {
+            eprintln!(
+                "Can not refresh the lock file: {}. Err: {}",
+                self.file_name, err
+            );
+        }
+    }
+
+    fn create(&self) -> std::io::Result<()> {
+        let mut open_options = fs::OpenOptions::new();
+        open_options.write(true).create_new(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            open_options.mode(0o600);
+        }
+
+        open_options
+            .open(&self.file_name)?
+            .write_all(&self.to_json())
+    }
+
+    fn read(&self) -> Option<Vec<u8>> {
+        fs::read(&self.file_name).ok()
+    }
+
+    fn read_owner(&self) -> Option<LockOwner> {
+        parse_owner(&self.read()?)
+    }
+
+    fn is_stale(&self, owner: &LockOwner) -> bool {
+        if owner.host == self.owner.host {
+            return owner.pid == self.owner.pid || !is_process_alive(owner.pid);
+        }
+
+        fs::metadata(&self.file_name)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age > STALE_AFTER)
+            .unwrap_or(true)
+    }
+
+    fn remove(&self) -> Result<(), VaultError> {
+        match fs::remove_file(&self.file_name) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn remove_if_unchanged(&self, data: &[u8]) -> Result<(), VaultError> {
+        match self.read() {
+            Some(current) if current != data => Ok(()),
+            _ => self.remove(),
+        }
+    }
+
+    fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.owner).unwrap()
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Taken over by another process meanwhile
+        if self.read_owner().as_ref() != Some(&self.owner) {
+            return;
+        }
+
+        if let Err(err) = self.remove() {
+            eprintln!(
+                "Can not remove the lock file: {}. Err: {}",
+                self.file_name, err
+            );
+        }
+    }
+}
+
+fn parse_owner(data: &[u8]) -> Option<LockOwner> {
+    serde_json::from_slice(data).ok()
+}
+
+/// A process of another user is alive too, signalling it fails with EPERM
+#[cfg(unix)]
+fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return true;
+    };
+
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+
+    std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(not(unix))]
+fn is_process_alive(_pid: u32) -> bool {
+    true
+}
+
+fn get_host_name() -> String {
+    let host_name = Command::new("hostname")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|host_name| !host_name.is_empty());
+
+    host_name
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_other_owner(file_name: &str) -> LockOwner {
+        let owner = LockOwner {
+            pid: 1,
+            host: "other-host".to_string(),
+        };
+        fs::write(file_name, serde_json::to_vec(&owner).unwrap()).unwrap();
+        owner
+    }
+
+    #[test]
+    fn keeps_fresh_lock_of_other_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_file_name = dir.path().join("vault").to_string_lossy().to_string();
+        let owner = write_other_owner(&format!("{}.lock", vault_file_name));
+
+        assert!(matches!(
+            FileLock::acquire(&vault_file_name),
+            Err(VaultError::FileLocked(locked_by)) if locked_by == owner
+        ));
+    }
+
+    #[test]
+    fn notices_lock_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_file_name = dir.path().join("vault").to_string_lossy().to_string();
+
+        let lock = FileLock::acquire(&vault_file_name).unwrap();
+        lock.check_owner().unwrap();
+
+        write_other_owner(&lock.file_name);
+        assert!(matches!(lock.check_owner(), Err(VaultError::FileLocked(_))));
+
+        fs::remove_file(&lock.file_name).unwrap();
+        lock.check_owner().unwrap();
+        assert_eq!(lock.read_owner(), Some(lock.owner.clone()));
+    }
+
+    #[test]
+    fn process_of_other_user_is_alive() {
+        assert!(is_process_alive(std::process::id()));
+        assert!(is_process_alive(1));
+    }
+}
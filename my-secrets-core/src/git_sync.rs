@@ -0,0 +1,421 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use crate::VaultError;
+
+const DEFAULT_COMMIT_NAME: &str = "my-secrets";
+const DEFAULT_COMMIT_EMAIL: &str = "my-secrets@localhost";
+
+/// The directory of the vault file is a git repository. Every save commits the encrypted file,
+/// pull and push go to the remote which is a URL or a path of a bare repository
+pub struct GitSync {
+    pub binary: String,
+    pub remote: Option<String>,
+    pub branch: String,
+    dir: PathBuf,
+    file_name: String,
+}
+
+pub enum PullResult {
+    UpToDate,
+    FastForwarded,
+    /// Both sides have commits. Contains the vault file of the common ancestor, if it had one,
+    /// and of the remote side to merge with the current content
+    Diverged {
+        base: Option<Vec<u8>>,
+        theirs: Vec<u8>,
+    },
+}
+
+impl GitSync {
+    pub fn new(vault_file_name: &str, remote: Option<String>, branch: String) -> Self {
+        let path = Path::new(vault_file_name);
+
+        Self {
+            binary: "git".to_string(),
+            remote,
+            branch,
+            dir: path
+                .parent()
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_default(),
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Commits the vault file if it changed. Creates the repository on the first call
+    pub fn commit(&self, message: &str) -> Result<(), VaultError> {
+        self.init_if_needed()?;
+
+        if !self.dir.join(&self.file_name).exists() {
+            return Ok(());
+        }
+
+        self.run(&["add", "--", self.file_name.as_str()])?;
+
+        let changed = !self
+            .run(&["status", "--porcelain", "--", self.file_name.as_str()])?
+            .is_empty();
+
+        if !changed && !self.is_merging() {
+            return Ok(());
+        }
+
+        let mut args = self.get_identity_args();
+
+        args.extend([
+            "commit".to_string(),
+            "--quiet".to_string(),
+            "-m".to_string(),
+            message.to_string(),
+        ]);
+
+        self.run(&args)?;
+        Ok(())
+    }
+
+    /// Commits the current file, fetches the remote branch and fast-forwards if possible.
+    /// The vault has to be reloaded after [`PullResult::FastForwarded`]
+    pub fn pull(&self) -> Result<PullResult, VaultError> {
+        let remote = self.get_remote()?;
+        self.commit("Update vault")?;
+
+        let remote_branch = format!("refs/heads/{}", self.branch);
+        if self
+            .run(&["ls-remote", remote, remote_branch.as_str()])?
+            .is_empty()
+        {
+            return Ok(PullResult::UpToDate);
+        }
+
+        self.run(&["fetch", "--quiet", remote, remote_branch.as_str()])?;
+
+        if !self.has_commits() {
+            self.run(&["reset", "--quiet", "--hard", "FETCH_HEAD"])?;
+            return Ok(PullResult::FastForwarded);
+        }
+
+        if self.is_ancestor("FETCH_HEAD", "HEAD")? {
+            return Ok(PullResult::UpToDate);
+        }
+
+        if self.is_ancestor("HEAD", "FETCH_HEAD")? {
+            self.run(&["merge", "--quiet", "--ff-only", "FETCH_HEAD"])?;
+            return Ok(PullResult::FastForwarded);
+        }
+
+        let base = match self.run(&["merge-base", "HEAD", "FETCH_HEAD"]) {
+            Ok(base) => self
+                .read_file_at(String::from_utf8_lossy(&base).trim())
+                .ok(),
+            Err(_) => None,
+        };
+
+        Ok(PullResult::Diverged {
+            base,
+            theirs: self.read_file_at("FETCH_HEAD")?,
+        })
+    }
+
+    /// Records the fetched commit as merged. The next save writes the merged content
+    /// and commits it as the merge commit
+    pub fn begin_merge(&self) -> Result<(), VaultError> {
+        let mut args = self.get_identity_args();
+        args.extend(
+            [
+                "merge",
+                "--quiet",
+                "--no-commit",
+                "--no-ff",
+                "--allow-unrelated-histories",
+                "-s",
+                "ours",
+                "FETCH_HEAD",
+            ]
+            .map(|arg| arg.to_string()),
+        );
+
+        self.run(&args)?;
+        Ok(())
+    }
+
+    /// Forgets the merge started with [`GitSync::begin_merge`] if the merged vault could not be saved
+    pub fn abort_merge(&self) -> Result<(), VaultError> {
+        if self.is_merging() {
+            self.run(&["merge", "--abort"])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn push(&self) -> Result<(), VaultError> {
+        let remote = self.get_remote()?;
+        self.commit("Update vault")?;
+
+        if !self.has_commits() {
+            return Ok(());
+        }
+
+        self.run(&[
+            "push",
+            "--quiet",
+            remote,
+            format!("HEAD:refs/heads/{}", self.branch).as_str(),
+        ])
+        .map_err(|err| VaultError::Git(format!("{}. Pull the remote changes first", err)))?;
+
+        Ok(())
+    }
+
+    /// Commits need an author. Repositories without one configured commit as my-secrets
+    fn get_identity_args(&self) -> Vec<String> {
+        if self.run(&["config", "--get", "user.email"]).is_ok() {
+            return Vec::new();
+        }
+
+        vec![
+            "-c".to_string(),
+            format!("user.name={}", DEFAULT_COMMIT_NAME),
+            "-c".to_string(),
+            format!("user.email={}", DEFAULT_COMMIT_EMAIL),
+        ]
+    }
+
+    fn get_remote(&self) -> Result<&str, VaultError> {
+        self.remote
+            .as_deref()
+            .ok_or_else(|| VaultError::Git("Remote is not configured".to_string()))
+    }
+
+    fn init_if_needed(&self) -> Result<(), VaultError> {
+        if self.dir.join(".git").exists() {
+            return Ok(());
+        }
+
+        self.run(&["init", "--quiet"])?;
+        self.run(&[
+            "symbolic-ref",
+            "HEAD",
+            format!("refs/heads/{}", self.branch).as_str(),
+        ])?;
+
+        Ok(())
+    }
+
+    fn has_commits(&self) -> bool {
+        self.run(&["rev-parse", "--quiet", "--verify", "HEAD"])
+            .is_ok()
+    }
+
+    fn is_merging(&self) -> bool {
+        self.run(&["rev-parse", "--quiet", "--verify", "MERGE_HEAD"])
+            .is_ok()
+    }
+
+    fn is_ancestor(&self, ancestor: &str, commit: &str) -> Result<bool, VaultError> {
+        let output = self.output(&["merge-base", "--is-ancestor", ancestor, commit])?;
+
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(to_error(&output)),
+        }
+    }
+
+    fn read_file_at(&self, commit: &str) -> Result<Vec<u8>, VaultError> {
+        self.run(&["show", format!("{}:{}", commit, self.file_name).as_str()])
+    }
+
+    fn run<S: AsRef<std::ffi::OsStr>>(&self, args: &[S]) -> Result<Vec<u8>, VaultError> {
+        let output = self.output(args)?;
+
+        if !output.status.success() {
+            return Err(to_error(&output));
+        }
+
+        Ok(output.stdout)
+    }
+
+    fn output<S: AsRef<std::ffi::OsStr>>(&self, args: &[S]) -> Result<Output, VaultError> {
+        Command::new(&self.binary)
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| VaultError::Git(format!("Can not start {}. Err: {}", self.binary, err)))
+    }
+}
+
+fn to_error(output: &Output) -> VaultError {
+    VaultError::Git(String::from_utf8_lossy(&output.stderr).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    struct WorkingCopy {
+        git_sync: GitSync,
+        file_name: PathBuf,
+    }
+
+    impl WorkingCopy {
+        fn new(dir: &tempfile::TempDir, name: &str, remote: &str) -> Self {
+            let clone_dir = dir.path().join(name);
+            fs::create_dir(&clone_dir).unwrap();
+
+            let file_name = clone_dir.join("vault");
+            let git_sync = GitSync::new(
+                &file_name.to_string_lossy(),
+                Some(remote.to_string()),
+                "main".to_string(),
+            );
+
+            Self {
+                git_sync,
+                file_name,
+            }
+        }
+
+        fn write(&self, data: &str) {
+            fs::write(&self.file_name, data).unwrap();
+        }
+
+        fn read(&self) -> String {
+            fs::read_to_string(&self.file_name).unwrap()
+        }
+
+        fn count_commits(&self) -> usize {
+            let output = self.git_sync.run(&["rev-list", "--count", "HEAD"]).unwrap();
+            String::from_utf8(output).unwrap().trim().parse().unwrap()
+        }
+    }
+
+    fn create_remote(dir: &tempfile::TempDir) -> String {
+        let remote = dir.path().join("remote.git");
+
+        let status = Command::new("git")
+            .args(["init", "--quiet", "--bare"])
+            .arg(&remote)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        remote.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn commits_only_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let clone = WorkingCopy::new(&dir, "a", &create_remote(&dir));
+
+        clone.write("v1");
+        clone.git_sync.commit("First").unwrap();
+        clone.git_sync.commit("Nothing changed").unwrap();
+
+        assert_eq!(clone.count_commits(), 1);
+
+        clone.write("v2");
+        clone.git_sync.commit("Second").unwrap();
+
+        assert_eq!(clone.count_commits(), 2);
+    }
+
+    #[test]
+    fn pulls_pushed_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = create_remote(&dir);
+        let a = WorkingCopy::new(&dir, "a", &remote);
+        let b = WorkingCopy::new(&dir, "b", &remote);
+
+        assert!(matches!(b.git_sync.pull().unwrap(), PullResult::UpToDate));
+
+        a.write("v1");
+        a.git_sync.push().unwrap();
+
+        assert!(matches!(
+            b.git_sync.pull().unwrap(),
+            PullResult::FastForwarded
+        ));
+        assert_eq!(b.read(), "v1");
+
+        a.write("v2");
+        a.git_sync.push().unwrap();
+
+        assert!(matches!(
+            b.git_sync.pull().unwrap(),
+            PullResult::FastForwarded
+        ));
+        assert_eq!(b.read(), "v2");
+        assert!(matches!(b.git_sync.pull().unwrap(), PullResult::UpToDate));
+    }
+
+    #[test]
+    fn merges_diverged_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = create_remote(&dir);
+        let a = WorkingCopy::new(&dir, "a", &remote);
+        let b = WorkingCopy::new(&dir, "b", &remote);
+
+        a.write("v1");
+        a.git_sync.push().unwrap();
+        b.git_sync.pull().unwrap();
+
+        a.write("v2 a");
+        a.git_sync.push().unwrap();
+
+        b.write("v2 b");
+        assert!(b.git_sync.push().is_err());
+
+        match b.git_sync.pull().unwrap() {
+            PullResult::Diverged { base, theirs } => {
+                assert_eq!(base.as_deref(), Some(b"v1".as_slice()));
+                assert_eq!(theirs, b"v2 a");
+            }
+            _ => panic!("Changes of both sides are not detected"),
+        }
+
+        b.git_sync.begin_merge().unwrap();
+        b.write("v3 merged");
+        b.git_sync.commit("Merge").unwrap();
+        b.git_sync.push().unwrap();
+
+        assert!(matches!(
+            a.git_sync.pull().unwrap(),
+            PullResult::FastForwarded
+        ));
+        assert_eq!(a.read(), "v3 merged");
+    }
+
+    #[test]
+    fn aborts_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = create_remote(&dir);
+        let a = WorkingCopy::new(&dir, "a", &remote);
+        let b = WorkingCopy::new(&dir, "b", &remote);
+
+        a.write("v1 a");
+        a.git_sync.push().unwrap();
+
+        b.write("v1 b");
+        assert!(matches!(
+            b.git_sync.pull().unwrap(),
+            PullResult::Diverged { base: None, .. }
+        ));
+
+        b.git_sync.begin_merge().unwrap();
+        b.git_sync.abort_merge().unwrap();
+
+        assert!(!b.git_sync.is_merging());
+        assert_eq!(b.read(), "v1 b");
+    }
+}
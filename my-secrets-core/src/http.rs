@@ -0,0 +1,365 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use crate::VaultError;
+
+const MAX_HEAD_SIZE: u64 = 16 * 1024;
+/// Vault files are far smaller. Anything bigger is not a vault
+const MAX_BODY_SIZE: u64 = 64 * 1024 * 1024;
+pub(crate) const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Just enough HTTP/1.1 for the sync server and its client: bodies with Content-Length,
+/// one request per connection
+pub(crate) struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        get_header(&self.headers, name)
+    }
+}
+
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        get_header(&self.headers, name)
+    }
+}
+
+pub(crate) fn read_request(reader: &mut impl BufRead) -> Result<Request, VaultError> {
+    let (start_line, headers) = read_head(reader)?;
+
+    let mut parts = start_line.split(' ');
+    let (Some(method), Some(path), Some(_)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(to_error(format!("Invalid request line: {}", start_line)));
+    };
+
+    let body = read_body(reader, &headers, false)?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body,
+    })
+}
+
+pub(crate) fn write_response(stream: &mut impl Write, response: &Response) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        get_reason(response.status),
+        response.body.len()
+    );
+
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+/// Sends the request to an `http://host[:port]/path` URL over a new connection
+pub(crate) fn send(
+    url: &str,
+    method: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> Result<Response, VaultError> {
+    let (host, path) = parse_url(url)?;
+
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+
+    let mut stream = TcpStream::connect(&address)
+        .map_err(|err| to_error(format!("Can not connect to {}. Err: {}", host, err)))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        method,
+        path,
+        host,
+        body.len()
+    );
+
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let (status_line, headers) = read_head(&mut reader)?;
+    let status = parse_status(&status_line)?;
+
+    let body = read_body(&mut reader, &headers, method != "HEAD")?;
+
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Response printed by `curl --include`. Interim 1xx heads are skipped,
+/// the body is already decoded by curl
+pub(crate) fn parse_response(data: &[u8]) -> Result<Response, VaultError> {
+    let mut reader = data;
+
+    loop {
+        let (status_line, headers) = read_head(&mut reader)?;
+        let status = parse_status(&status_line)?;
+
+        if (100..200).contains(&status) {
+            continue;
+        }
+
+        return Ok(Response {
+            status,
+            headers,
+            body: reader.to_vec(),
+        });
+    }
+}
+
+fn parse_status(status_line: &str) -> Result<u16, VaultError> {
+    status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| to_error(format!("Invalid status line: {}", status_line)))
+}
+
+/// Splits the URL into `host[:port]` and the path
+fn parse_url(url: &str) -> Result<(&str, &str), VaultError> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(to_error(format!(
+            "Only http:// URLs are supported: {}. Put a TLS proxy in front of the server for https",
+            url
+        )));
+    };
+
+    match rest.find('/') {
+        Some(index) => Ok((&rest[..index], &rest[index..])),
+        None => Ok((rest, "/")),
+    }
+}
+
+fn read_head(reader: &mut impl BufRead) -> Result<(String, Vec<(String, String)>), VaultError> {
+    let mut limited = reader.take(MAX_HEAD_SIZE);
+
+    let mut start_line = None;
+    let mut headers = Vec::new();
+
+    loop {
+        let mut line = String::new();
+
+        if limited.read_line(&mut line)? == 0 {
+            return Err(to_error("Connection closed in the middle of the head"));
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if line.is_empty() {
+            break;
+        }
+
+        if start_line.is_none() {
+            start_line = Some(line.to_string());
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(to_error(format!("Invalid header: {}", line)));
+        };
+
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    match start_line {
+        Some(start_line) => Ok((start_line, headers)),
+        None => Err(to_error("Empty head")),
+    }
+}
+
+/// Without Content-Length a request has no body, a response lasts until the connection is closed
+fn read_body(
+    reader: &mut impl BufRead,
+    headers: &[(String, String)],
+    read_to_end: bool,
+) -> Result<Vec<u8>, VaultError> {
+    if get_header(headers, "Transfer-Encoding").is_some() {
+        return Err(to_error("Transfer-Encoding is not supported"));
+    }
+
+    let mut body = Vec::new();
+
+    match get_header(headers, "Content-Length") {
+        Some(length) => {
+            let length: u64 = length
+                .parse()
+                .map_err(|_| to_error(format!("Invalid Content-Length: {}", length)))?;
+
+            if length > MAX_BODY_SIZE {
+                return Err(to_error(format!("Body of {} bytes is too big", length)));
+            }
+
+            reader.take(length).read_to_end(&mut body)?;
+
+            if body.len() as u64 != length {
+                return Err(to_error("Connection closed in the middle of the body"));
+            }
+        }
+        None if read_to_end => {
+            reader.take(MAX_BODY_SIZE).read_to_end(&mut body)?;
+        }
+        None => {}
+    }
+
+    Ok(body)
+}
+
+fn get_header<'s>(headers: &'s [(String, String)], name: &str) -> Option<&'s str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn get_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        412 => "Precondition Failed",
+        428 => "Precondition Required",
+        _ => "Internal Server Error",
+    }
+}
+
+fn to_error(message: impl Into<String>) -> VaultError {
+    VaultError::Server(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_request_with_body() {
+        let mut data: &[u8] =
+            b"PUT /vault HTTP/1.1\r\nHost: localhost\r\nif-match: \"1\"\r\nContent-Length: 4\r\n\r\ndata";
+
+        let request = read_request(&mut data).unwrap();
+
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/vault");
+        assert_eq!(request.get_header("If-Match"), Some("\"1\""));
+        assert_eq!(request.body, b"data");
+    }
+
+    #[test]
+    fn request_without_content_length_has_no_body() {
+        let mut data: &[u8] = b"GET /vault HTTP/1.1\r\n\r\nignored";
+
+        assert!(read_request(&mut data).unwrap().body.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        let requests: [&[u8]; 4] = [
+            b"GET /vault\r\n\r\n",
+            b"PUT /vault HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort",
+            b"PUT /vault HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n",
+            b"GET /vault HTTP/1.1\r\nNoColon\r\n\r\n",
+        ];
+
+        for mut request in requests {
+            assert!(read_request(&mut request).is_err());
+        }
+    }
+
+    #[test]
+    fn writes_response() {
+        let response = Response::new(412)
+            .with_header("ETag", "\"1\"")
+            .with_body(b"body".to_vec());
+
+        let mut data = Vec::new();
+        write_response(&mut data, &response).unwrap();
+
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "HTTP/1.1 412 Precondition Failed\r\nContent-Length: 4\r\nConnection: close\r\nETag: \"1\"\r\n\r\nbody"
+        );
+    }
+
+    #[test]
+    fn parses_response_skipping_interim_heads() {
+        let response = parse_response(
+            b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nETag: \"1\"\r\n\r\nbody",
+        )
+        .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.get_header("etag"), Some("\"1\""));
+        assert_eq!(response.body, b"body");
+    }
+
+    #[test]
+    fn parses_url() {
+        assert_eq!(
+            parse_url("http://localhost:7878/vault").unwrap(),
+            ("localhost:7878", "/vault")
+        );
+        assert_eq!(parse_url("http://server").unwrap(), ("server", "/"));
+        assert!(parse_url("https://server/vault").is_err());
+    }
+}
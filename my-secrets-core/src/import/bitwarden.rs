@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use super::{compose_entry_text, ImportedEntry};
+use crate::VaultError;
+
+const DEFAULT_CATEGORY: &str = "Bitwarden";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    /// Organization exports have collections instead of folders
+    #[serde(default)]
+    collections: Vec<BitwardenFolder>,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(default)]
+    name: String,
+    folder_id: Option<String>,
+    #[serde(default)]
+    collection_ids: Vec<String>,
+    notes: Option<String>,
+    login: Option<BitwardenLogin>,
+    card: Option<BTreeMap<String, serde_json::Value>>,
+    identity: Option<BTreeMap<String, serde_json::Value>>,
+    #[serde(default)]
+    fields: Vec<BitwardenField>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<BitwardenUri>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    name: Option<String>,
+    value: Option<String>,
+}
+
+/// Reads unencrypted JSON export of Bitwarden. Folders or collections become categories
+pub fn read_bitwarden(file_name: &str) -> Result<Vec<ImportedEntry>, VaultError> {
+    let mut content = std::fs::read_to_string(file_name)?;
+    let export: Result<BitwardenExport, _> = serde_json::from_str(&content);
+    zeroize::Zeroize::zeroize(&mut content);
+
+    let export = export.map_err(|err| VaultError::Import(err.to_string()))?;
+
+    if export.encrypted {
+        return Err(VaultError::Import(
+            "Encrypted Bitwarden export is not supported. Please export as unencrypted JSON"
+                .to_string(),
+        ));
+    }
+
+    let folders: BTreeMap<&str, &str> = export
+        .folders
+        .iter()
+        .chain(export.collections.iter())
+        .map(|folder| (folder.id.as_str(), folder.name.as_str()))
+        .collect();
+
+    let result = export
+        .items
+        .iter()
+        .map(|item| {
+            let folder_id = item
+                .folder_id
+                .as_deref()
+                .or_else(|| item.collection_ids.first().map(|id| id.as_str()));
+
+            let category = folder_id
+                .and_then(|id| folders.get(id))
+                .copied()
+                .unwrap_or(DEFAULT_CATEGORY);
+
+            ImportedEntry {
+                category: category.to_string(),
+                sub_category: get_item_name(item),
+                text: compile_item_text(item),
+            }
+        })
+        .collect();
+
+    Ok(result)
+}
+
+fn get_item_name(item: &BitwardenItem) -> String {
+    if item.name.trim().is_empty() {
+        "Untitled".to_string()
+    } else {
+        item.name.trim().to_string()
+    }
+}
+
+fn compile_item_text(item: &BitwardenItem) -> String {
+    let mut fields = Vec::new();
+
+    if let Some(login) = &item.login {
+        fields.push(("username", login.username.as_deref().unwrap_or_default()));
+        fields.push(("password", login.password.as_deref().unwrap_or_default()));
+        fields.push(("totp", login.totp.as_deref().unwrap_or_default()));
+
+        for uri in &login.uris {
+            fields.push(("url", uri.uri.as_deref().unwrap_or_default()));
+        }
+    }
+
+    for values in [&item.card, &item.identity].into_iter().flatten() {
+        for (name, value) in values {
+            if let Some(value) = value.as_str() {
+                fields.push((name.as_str(), value));
+            }
+        }
+    }
+
+    for field in &item.fields {
+        if let Some(name) = field.name.as_deref() {
+            fields.push((name, field.value.as_deref().unwrap_or_default()));
+        }
+    }
+
+    compose_entry_text(&fields, item.notes.as_deref().unwrap_or_default())
+}
@@ -0,0 +1,252 @@
+use keepass::{
+    db::{Entry, Group, Node},
+    Database, DatabaseKey,
+};
+
+use super::{compose_entry_text, ImportedEntry};
+use crate::VaultError;
+
+const NESTED_GROUP_SEPARATOR: &str = ".";
+
+const STANDARD_FIELDS: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+/// How KeePass groups deeper than the first level become categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupMapping {
+    /// Entries of the nested groups go to the category of their top level group
+    #[default]
+    Flatten,
+    /// Every group becomes a category named by its path: `Internet.Email`
+    Nested,
+}
+
+/// Reads KDBX 3.1/4 database. At least one of the password and the key file is required
+pub fn read_keepass(
+    file_name: &str,
+    password: Option<&str>,
+    key_file: Option<&str>,
+    mapping: GroupMapping,
+) -> Result<Vec<ImportedEntry>, VaultError> {
+    let mut key = DatabaseKey::new();
+
+    if let Some(password) = password {
+        if !password.is_empty() {
+            key = key.with_password(password);
+        }
+    }
+
+    if let Some(key_file) = key_file {
+        let mut key_file = std::fs::File::open(key_file)?;
+        key = key.with_keyfile(&mut key_file)?;
+    }
+
+    let mut file = std::fs::File::open(file_name)?;
+    let db = Database::open(&mut file, key).map_err(|err| VaultError::Import(err.to_string()))?;
+
+    // The recycle bin is named in the language of KeePass, so it is found by its UUID
+    let recycle_bin = db.meta.recyclebin_uuid;
+    let is_recycle_bin = |group: &Group| Some(group.uuid) == recycle_bin;
+
+    let mut result = Vec::new();
+    let mut path = Vec::new();
+    collect_group(
+        &db.root,
+        &db.root.name,
+        &mut path,
+        mapping,
+        &is_recycle_bin,
+        &mut result,
+    );
+
+    Ok(result)
+}
+
+fn collect_group<'s>(
+    group: &'s Group,
+    root_name: &str,
+    path: &mut Vec<&'s str>,
+    mapping: GroupMapping,
+    is_recycle_bin: &dyn Fn(&Group) -> bool,
+    result: &mut Vec<ImportedEntry>,
+) {
+    for node in &group.children {
+        match node {
+            Node::Group(child) => {
+                if is_recycle_bin(child) {
+                    continue;
+                }
+
+                path.push(child.name.as_str());
+                collect_group(child, root_name, path, mapping, is_recycle_bin, result);
+                path.pop();
+            }
+            Node::Entry(entry) => {
+                let category = match (path.first(), mapping) {
+                    (None, _) => root_name.to_string(),
+                    (Some(top_level), GroupMapping::Flatten) => top_level.to_string(),
+                    (Some(_), GroupMapping::Nested) => path.join(NESTED_GROUP_SEPARATOR),
+                };
+
+                result.push(ImportedEntry {
+                    category,
+                    sub_category: get_entry_title(entry),
+                    text: compile_entry_text(entry),
+                });
+            }
+        }
+    }
+}
+
+fn get_entry_title(entry: &Entry) -> String {
+    match entry.get_title() {
+        Some(title) if !title.trim().is_empty() => title.trim().to_string(),
+        _ => "Untitled".to_string(),
+    }
+}
+
+fn compile_entry_text(entry: &Entry) -> String {
+    let mut fields = vec![
+        ("username", entry.get_username().unwrap_or_default()),
+        ("password", entry.get_password().unwrap_or_default()),
+        ("url", entry.get_url().unwrap_or_default()),
+    ];
+
+    let mut custom_fields: Vec<&str> = entry
+        .fields
+        .keys()
+        .map(|name| name.as_str())
+        .filter(|name| !STANDARD_FIELDS.contains(name))
+        .collect();
+    custom_fields.sort();
+
+    for name in custom_fields {
+        fields.push((name, entry.get(name).unwrap_or_default()));
+    }
+
+    compose_entry_text(&fields, entry.get("Notes").unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use keepass::db::Value;
+
+    use super::*;
+
+    fn entry(title: &str, fields: &[(&str, &str)]) -> Node {
+        let mut entry = Entry::new();
+
+        for (name, value) in [("Title", title)].iter().chain(fields) {
+            entry
+                .fields
+                .insert(name.to_string(), Value::Unprotected(value.to_string()));
+        }
+
+        Node::Entry(entry)
+    }
+
+    fn group(name: &str, children: Vec<Node>) -> Group {
+        let mut group = Group::new(name);
+        group.children = children;
+        group
+    }
+
+    /// The root and the recycle bin in it
+    fn create_root() -> (Group, Group) {
+        let recycle_bin = group("Papierkorb", vec![entry("Deleted", &[("Password", "3")])]);
+
+        let root = group(
+            "Root",
+            vec![
+                entry("Top", &[("Password", "1")]),
+                Node::Group(group(
+                    "Internet",
+                    vec![Node::Group(group(
+                        "Email",
+                        vec![entry("Mail", &[("Password", "2")])],
+                    ))],
+                )),
+                Node::Group(recycle_bin.clone()),
+                Node::Group(group(
+                    "Recycle Bin",
+                    vec![entry("Kept", &[("Password", "4")])],
+                )),
+            ],
+        );
+
+        (root, recycle_bin)
+    }
+
+    fn collect(mapping: GroupMapping) -> Vec<(String, String)> {
+        let (root, recycle_bin) = create_root();
+        let is_recycle_bin = |group: &Group| group.uuid == recycle_bin.uuid;
+
+        let mut result = Vec::new();
+        collect_group(
+            &root,
+            &root.name,
+            &mut Vec::new(),
+            mapping,
+            &is_recycle_bin,
+            &mut result,
+        );
+
+        result
+            .iter()
+            .map(|entry| (entry.category.clone(), entry.sub_category.clone()))
+            .collect()
+    }
+
+    fn to_paths(paths: &[(&str, &str)]) -> Vec<(String, String)> {
+        paths
+            .iter()
+            .map(|(category, sub_category)| (category.to_string(), sub_category.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn flattens_nested_groups() {
+        assert_eq!(
+            collect(GroupMapping::Flatten),
+            to_paths(&[
+                ("Root", "Top"),
+                ("Internet", "Mail"),
+                ("Recycle Bin", "Kept")
+            ])
+        );
+    }
+
+    #[test]
+    fn names_categories_by_group_path() {
+        assert_eq!(
+            collect(GroupMapping::Nested),
+            to_paths(&[
+                ("Root", "Top"),
+                ("Internet.Email", "Mail"),
+                ("Recycle Bin", "Kept")
+            ])
+        );
+    }
+
+    #[test]
+    fn composes_entry_text() {
+        let Node::Entry(keepass_entry) = entry(
+            "  ",
+            &[
+                ("UserName", "admin"),
+                ("Password", "secret"),
+                ("URL", "https://example.com"),
+                ("Notes", "my notes"),
+                ("pin", "1234"),
+                ("Api Key", "abc"),
+            ],
+        ) else {
+            unreachable!()
+        };
+
+        assert_eq!(get_entry_title(&keepass_entry), "Untitled");
+        assert_eq!(
+            compile_entry_text(&keepass_entry),
+            "username: admin\npassword: secret\nurl: https://example.com\nApi_Key: abc\npin: 1234\n\nmy notes\n"
+        );
+    }
+}
@@ -0,0 +1,424 @@
+mod bitwarden;
+mod keepass;
+mod one_password;
+mod password_csv;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+pub use bitwarden::*;
+pub use keepass::*;
+pub use one_password::*;
+pub use password_csv::*;
+
+use crate::{TypeContent, Vault, VaultCommand, VaultError};
+
+/// Entry read from another password manager
+pub struct ImportedEntry {
+    pub category: String,
+    pub sub_category: String,
+    pub text: String,
+}
+
+impl Drop for ImportedEntry {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.text);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    KeePass,
+    Bitwarden,
+    OnePassword,
+    Chrome,
+    Firefox,
+    /// Encrypted bundle made by [`crate::bundle::create_bundle`]
+    Bundle,
+    /// Entries shared with [`crate::sharing::share_entries`] to the identity of the vault
+    Shared,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 7] = [
+        ImportFormat::KeePass,
+        ImportFormat::Bitwarden,
+        ImportFormat::OnePassword,
+        ImportFormat::Chrome,
+        ImportFormat::Firefox,
+        ImportFormat::Bundle,
+        ImportFormat::Shared,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keepass" | "kdbx" => Some(Self::KeePass),
+            "bitwarden" => Some(Self::Bitwarden),
+            "1password" | "1pux" => Some(Self::OnePassword),
+            "chrome" | "csv" => Some(Self::Chrome),
+            "firefox" => Some(Self::Firefox),
+            "bundle" => Some(Self::Bundle),
+            "shared" | "age" => Some(Self::Shared),
+            _ => None,
+        }
+    }
+
+    /// Guess by the extension. CSV files are treated as Chrome export which has the most common columns
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let file_name = file_name.to_lowercase();
+
+        if file_name.ends_with(".kdbx") {
+            Some(Self::KeePass)
+        } else if file_name.ends_with(".json") {
+            Some(Self::Bitwarden)
+        } else if file_name.ends_with(".1pux") {
+            Some(Self::OnePassword)
+        } else if file_name.ends_with(".csv") {
+            Some(Self::Chrome)
+        } else if file_name.ends_with(&format!(".{}", crate::bundle::BUNDLE_EXTENSION)) {
+            Some(Self::Bundle)
+        } else if file_name.ends_with(&format!(".{}", crate::sharing::SHARED_EXTENSION)) {
+            Some(Self::Shared)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::KeePass => "keepass",
+            Self::Bitwarden => "bitwarden",
+            Self::OnePassword => "1password",
+            Self::Chrome => "chrome",
+            Self::Firefox => "firefox",
+            Self::Bundle => "bundle",
+            Self::Shared => "shared",
+        }
+    }
+
+    pub fn requires_password(&self) -> bool {
+        *self == Self::KeePass || *self == Self::Bundle
+    }
+}
+
+/// Password and key file are used by the formats which are encrypted
+#[derive(Default)]
+pub struct ImportOptions<'s> {
+    pub password: Option<&'s str>,
+    pub key_file: Option<&'s str>,
+    pub mapping: GroupMapping,
+    /// Content of the vault with the identity the shared entries are encrypted to
+    pub vault_content: Option<&'s TypeContent>,
+}
+
+pub fn read_entries(
+    format: ImportFormat,
+    file_name: &str,
+    options: &ImportOptions,
+) -> Result<Vec<ImportedEntry>, VaultError> {
+    match format {
+        ImportFormat::KeePass => read_keepass(
+            file_name,
+            options.password,
+            options.key_file,
+            options.mapping,
+        ),
+        ImportFormat::Bitwarden => read_bitwarden(file_name),
+        ImportFormat::OnePassword => read_one_password(file_name),
+        ImportFormat::Chrome => read_password_csv(file_name, "Chrome"),
+        ImportFormat::Firefox => read_password_csv(file_name, "Firefox"),
+        ImportFormat::Bundle => {
+            let data = std::fs::read(file_name)?;
+            crate::bundle::read_bundle(&data, options.password.unwrap_or_default())
+        }
+        ImportFormat::Shared => {
+            let content = options.vault_content.ok_or(VaultError::Locked)?;
+            let data = std::fs::read(file_name)?;
+            crate::sharing::read_shared(content, &data)
+        }
+    }
+}
+
+/// What to do if the vault already has a subcategory with the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    #[default]
+    Skip,
+    Overwrite,
+    KeepBoth,
+}
+
+impl ConflictResolution {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "skip" => Some(Self::Skip),
+            "overwrite" => Some(Self::Overwrite),
+            "keep-both" => Some(Self::KeepBoth),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Overwrite => "overwrite",
+            Self::KeepBoth => "keep-both",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportAction {
+    Create,
+    /// The same subcategory with the same text already exists
+    Unchanged,
+    Skip,
+    Overwrite,
+    /// Created next to the existing one with a suffix
+    KeepBoth,
+}
+
+pub struct ImportPlanItem {
+    pub entry: ImportedEntry,
+    pub action: ImportAction,
+    /// Name of the subcategory in the vault. Differs from the imported one when both are kept
+    pub target_sub_category: String,
+    pub include: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub kept_both: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} to create, {} to skip, {} to merge ({} overwritten, {} kept both)",
+            self.created,
+            self.skipped,
+            self.overwritten + self.kept_both,
+            self.overwritten,
+            self.kept_both
+        )
+    }
+}
+
+/// Preview of the import: what happens with every entry, before anything is written to the vault
+pub struct ImportPlan {
+    pub items: Vec<ImportPlanItem>,
+    pub resolution: ConflictResolution,
+}
+
+impl ImportPlan {
+    pub fn new(
+        content: &TypeContent,
+        entries: Vec<ImportedEntry>,
+        resolution: ConflictResolution,
+    ) -> Self {
+        let mut result = Self {
+            items: entries
+                .into_iter()
+                .map(|entry| ImportPlanItem {
+                    target_sub_category: entry.sub_category.clone(),
+                    entry,
+                    action: ImportAction::Create,
+                    include: true,
+                })
+                .collect(),
+            resolution,
+        };
+
+        result.rebuild(content, resolution);
+        result
+    }
+
+    /// Recalculates actions with another conflict resolution keeping the include flags
+    pub fn rebuild(&mut self, content: &TypeContent, resolution: ConflictResolution) {
+        self.resolution = resolution;
+
+        let mut planned: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+        for item in self.items.iter_mut() {
+            let entry = &item.entry;
+            let existing = content
+                .get(&entry.category)
+                .and_then(|sub_categories| sub_categories.get(&entry.sub_category));
+
+            let planned_names = planned.entry(entry.category.clone()).or_default();
+
+            let is_taken = |name: &str| {
+                planned_names.contains(name)
+                    || content
+                        .get(&entry.category)
+                        .map(|sub_categories| sub_categories.contains_key(name))
+                        .unwrap_or(false)
+            };
+
+            let (action, target) = if planned_names.contains(&entry.sub_category) {
+                // Two imported entries with the same name are always kept both
+                (
+                    ImportAction::KeepBoth,
+                    compile_free_name(&entry.sub_category, is_taken),
+                )
+            } else {
+                match existing {
+                    None => (ImportAction::Create, entry.sub_category.clone()),
+                    Some(text) if text == &entry.text => {
+                        (ImportAction::Unchanged, entry.sub_category.clone())
+                    }
+                    Some(_) => match resolution {
+                        ConflictResolution::Skip => {
+                            (ImportAction::Skip, entry.sub_category.clone())
+                        }
+                        ConflictResolution::Overwrite => {
+                            (ImportAction::Overwrite, entry.sub_category.clone())
+                        }
+                        ConflictResolution::KeepBoth => (
+                            ImportAction::KeepBoth,
+                            compile_free_name(&entry.sub_category, is_taken),
+                        ),
+                    },
+                }
+            };
+
+            if item.include && action != ImportAction::Skip && action != ImportAction::Unchanged {
+                planned_names.insert(target.clone());
+            }
+
+            item.action = action;
+            item.target_sub_category = target;
+        }
+    }
+
+    pub fn summary(&self) -> ImportSummary {
+        let mut result = ImportSummary::default();
+
+        for item in &self.items {
+            if !item.include {
+                result.skipped += 1;
+                continue;
+            }
+
+            match item.action {
+                ImportAction::Create => result.created += 1,
+                ImportAction::Unchanged | ImportAction::Skip => result.skipped += 1,
+                ImportAction::Overwrite => result.overwritten += 1,
+                ImportAction::KeepBoth => result.kept_both += 1,
+            }
+        }
+
+        result
+    }
+
+    /// Commands which perform the import. New categories are created with all their entries at once
+    pub fn compile_commands(&self, content: &TypeContent) -> Vec<VaultCommand> {
+        let mut new_categories: BTreeMap<&str, BTreeMap<String, String>> = BTreeMap::new();
+        let mut result = Vec::new();
+
+        for item in &self.items {
+            if !item.include {
+                continue;
+            }
+
+            let entry = &item.entry;
+
+            match item.action {
+                ImportAction::Unchanged | ImportAction::Skip => {}
+                ImportAction::Create | ImportAction::KeepBoth => {
+                    if content.contains_key(&entry.category) {
+                        result.push(VaultCommand::CreateSubCategory {
+                            category: entry.category.clone(),
+                            name: item.target_sub_category.clone(),
+                            text: entry.text.clone(),
+                        });
+                    } else {
+                        new_categories
+                            .entry(entry.category.as_str())
+                            .or_default()
+                            .insert(item.target_sub_category.clone(), entry.text.clone());
+                    }
+                }
+                ImportAction::Overwrite => {
+                    let before = content
+                        .get(&entry.category)
+                        .and_then(|sub_categories| sub_categories.get(&entry.sub_category))
+                        .cloned()
+                        .unwrap_or_default();
+
+                    result.push(VaultCommand::EditText {
+                        category: entry.category.clone(),
+                        sub_category: item.target_sub_category.clone(),
+                        before,
+                        after: entry.text.clone(),
+                    });
+                }
+            }
+        }
+
+        for (name, sub_categories) in new_categories {
+            result.push(VaultCommand::CreateCategory {
+                name: name.to_string(),
+                sub_categories,
+            });
+        }
+
+        result
+    }
+
+    pub fn apply(&self, vault: &mut Vault) -> Result<ImportSummary, VaultError> {
+        let commands = self.compile_commands(vault.content()?);
+
+        for command in &commands {
+            vault.apply(command)?;
+        }
+
+        Ok(self.summary())
+    }
+}
+
+/// `name (2)`, `name (3)`... whichever is free first
+fn compile_free_name(name: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    let mut index = 2;
+    loop {
+        let result = format!("{} ({})", name, index);
+        if !is_taken(&result) {
+            return result;
+        }
+        index += 1;
+    }
+}
+
+/// Builds the text of the subcategory: known fields as `name: value` lines and notes below them
+pub fn compose_entry_text(fields: &[(&str, &str)], notes: &str) -> String {
+    let mut result = String::new();
+
+    for (name, value) in fields {
+        if value.is_empty() {
+            continue;
+        }
+
+        // Field names can not contain whitespaces, see [`crate::fields::parse_fields`]
+        let name: Vec<&str> = name.split_whitespace().collect();
+        result.push_str(&name.join("_"));
+        result.push_str(": ");
+        result.push_str(value);
+        result.push('\n');
+    }
+
+    // Whitespaces of the notes can be a part of a key or a formatted text
+    let notes = notes.trim_end_matches(['\r', '\n']);
+
+    if !notes.trim_start().is_empty() {
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result.push_str(notes);
+        result.push('\n');
+    }
+
+    result
+}
@@ -0,0 +1,193 @@
+use std::io::Read;
+
+use serde_json::Value;
+
+use super::{compose_entry_text, read_password_csv, ImportedEntry};
+use crate::VaultError;
+
+const DEFAULT_CATEGORY: &str = "1Password";
+const EXPORT_DATA_FILE: &str = "export.data";
+
+/// Reads 1PUX archive or CSV export of 1Password. Vaults become categories
+pub fn read_one_password(file_name: &str) -> Result<Vec<ImportedEntry>, VaultError> {
+    if !file_name.to_lowercase().ends_with(".1pux") {
+        return read_password_csv(file_name, DEFAULT_CATEGORY);
+    }
+
+    let file = std::fs::File::open(file_name)?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|err| VaultError::Import(err.to_string()))?;
+
+    let mut content = String::new();
+    archive
+        .by_name(EXPORT_DATA_FILE)
+        .map_err(|err| VaultError::Import(err.to_string()))?
+        .read_to_string(&mut content)?;
+
+    let export: Result<Value, _> = serde_json::from_str(&content);
+    zeroize::Zeroize::zeroize(&mut content);
+
+    let export = export.map_err(|err| VaultError::Import(err.to_string()))?;
+
+    let mut result = Vec::new();
+
+    for account in get_array(&export, "accounts") {
+        for vault in get_array(account, "vaults") {
+            let category = match get_str(&vault["attrs"], "name") {
+                "" => DEFAULT_CATEGORY,
+                name => name,
+            };
+
+            for item in get_array(vault, "items") {
+                if get_str(item, "state") == "archived" {
+                    continue;
+                }
+
+                let sub_category = match get_str(&item["overview"], "title").trim() {
+                    "" => "Untitled",
+                    title => title,
+                };
+
+                result.push(ImportedEntry {
+                    category: category.to_string(),
+                    sub_category: sub_category.to_string(),
+                    text: compile_item_text(item),
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn compile_item_text(item: &Value) -> String {
+    let details = &item["details"];
+    let mut fields = Vec::new();
+
+    for login_field in get_array(details, "loginFields") {
+        let name = match get_str(login_field, "designation") {
+            "" => get_str(login_field, "name"),
+            designation => designation,
+        };
+
+        fields.push((name, get_str(login_field, "value")));
+    }
+
+    fields.push(("password", get_str(details, "password")));
+    fields.push(("url", get_str(&item["overview"], "url")));
+
+    for section in get_array(details, "sections") {
+        for field in get_array(section, "fields") {
+            let name = match get_str(field, "title") {
+                "" => get_str(field, "id"),
+                title => title,
+            };
+
+            if let Some(value) = get_field_value(&field["value"]) {
+                fields.push((name, value));
+            }
+        }
+    }
+
+    compose_entry_text(&fields, get_str(details, "notesPlain"))
+}
+
+/// Section field values are typed: `{"concealed": "..."}`, `{"string": "..."}`, `{"email": {"email_address": "..."}}`
+fn get_field_value(value: &Value) -> Option<&str> {
+    let (_, value) = value.as_object()?.iter().next()?;
+
+    match value {
+        Value::String(value) => Some(value.as_str()),
+        Value::Object(value) => value.values().find_map(|value| value.as_str()),
+        _ => None,
+    }
+}
+
+fn get_array<'s>(value: &'s Value, name: &str) -> &'s [Value] {
+    value[name]
+        .as_array()
+        .map(|items| items.as_slice())
+        .unwrap_or_default()
+}
+
+fn get_str<'s>(value: &'s Value, name: &str) -> &'s str {
+    value[name].as_str().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const EXPORT_DATA: &str = r#"{
+        "accounts": [{
+            "vaults": [{
+                "attrs": { "name": "Work" },
+                "items": [
+                    {
+                        "state": "active",
+                        "overview": { "title": "GitHub", "url": "https://github.com" },
+                        "details": {
+                            "loginFields": [
+                                { "designation": "username", "name": "login", "value": "octo" },
+                                { "designation": "", "name": "pin", "value": "1234" }
+                            ],
+                            "notesPlain": "my notes",
+                            "sections": [{
+                                "fields": [
+                                    { "title": "api key", "id": "k1", "value": { "concealed": "abc" } },
+                                    { "title": "", "id": "email", "value": { "email": { "email_address": "me@mail.com" } } }
+                                ]
+                            }]
+                        }
+                    },
+                    {
+                        "state": "archived",
+                        "overview": { "title": "Old" },
+                        "details": {}
+                    }
+                ]
+            }]
+        }]
+    }"#;
+
+    fn write_1pux(dir: &tempfile::TempDir) -> String {
+        let file_name = dir.path().join("export.1pux");
+
+        let mut archive = zip::ZipWriter::new(std::fs::File::create(&file_name).unwrap());
+        archive
+            .start_file(EXPORT_DATA_FILE, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        archive.write_all(EXPORT_DATA.as_bytes()).unwrap();
+        archive.finish().unwrap();
+
+        file_name.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn reads_1pux_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = read_one_password(&write_1pux(&dir)).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].category, "Work");
+        assert_eq!(entries[0].sub_category, "GitHub");
+        assert_eq!(
+            entries[0].text,
+            "username: octo\npin: 1234\nurl: https://github.com\napi_key: abc\nemail: me@mail.com\n\nmy notes\n"
+        );
+    }
+
+    #[test]
+    fn reads_csv_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_name = dir.path().join("export.csv");
+        std::fs::write(&file_name, "title,username,password\nGitHub,octo,secret\n").unwrap();
+
+        let entries = read_one_password(file_name.to_str().unwrap()).unwrap();
+
+        assert_eq!(entries[0].category, DEFAULT_CATEGORY);
+        assert_eq!(entries[0].text, "username: octo\npassword: secret\n");
+    }
+}
@@ -0,0 +1,174 @@
+use super::{compose_entry_text, ImportedEntry};
+use crate::VaultError;
+
+const TITLE_COLUMNS: [&str; 2] = ["title", "name"];
+const URL_COLUMNS: [&str; 4] = ["url", "login_uri", "website", "urls"];
+const USERNAME_COLUMNS: [&str; 3] = ["username", "login_username", "user name"];
+const PASSWORD_COLUMNS: [&str; 2] = ["password", "login_password"];
+const OTP_COLUMNS: [&str; 3] = ["otpauth", "login_totp", "totp"];
+const NOTES_COLUMNS: [&str; 3] = ["notes", "note", "notesplain"];
+const CATEGORY_COLUMNS: [&str; 2] = ["vault", "folder"];
+
+/// Reads password CSV exported by Chrome, Firefox, 1Password or Bitwarden.
+/// Columns are found by their names. Rows without a vault or folder column go to the default category
+pub fn read_password_csv(
+    file_name: &str,
+    default_category: &str,
+) -> Result<Vec<ImportedEntry>, VaultError> {
+    let mut reader =
+        csv::Reader::from_path(file_name).map_err(|err| VaultError::Import(err.to_string()))?;
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|err| VaultError::Import(err.to_string()))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+
+    let find_column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.as_str()))
+    };
+
+    let title = find_column(&TITLE_COLUMNS);
+    let url = find_column(&URL_COLUMNS);
+    let username = find_column(&USERNAME_COLUMNS);
+    let otp = find_column(&OTP_COLUMNS);
+    let notes = find_column(&NOTES_COLUMNS);
+    let category = find_column(&CATEGORY_COLUMNS);
+    let password = find_column(&PASSWORD_COLUMNS)
+        .ok_or_else(|| VaultError::Import("CSV file has no password column".to_string()))?;
+
+    let mut result = Vec::new();
+
+    for record in reader.records() {
+        let record = record.map_err(|err| VaultError::Import(err.to_string()))?;
+
+        // Whitespaces can be a part of a secret. Only names and URLs are trimmed
+        let get_secret = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .unwrap_or_default()
+        };
+        let get = |index: Option<usize>| get_secret(index).trim();
+
+        let sub_category = match get(title) {
+            "" => match get_host(get(url)) {
+                "" => "Untitled",
+                host => host,
+            },
+            title => title,
+        };
+
+        let category = match get(category) {
+            "" => default_category,
+            category => category,
+        };
+
+        let fields = [
+            ("username", get(username)),
+            ("password", get_secret(Some(password))),
+            ("url", get(url)),
+            ("totp", get_secret(otp)),
+        ];
+
+        result.push(ImportedEntry {
+            category: category.to_string(),
+            sub_category: sub_category.to_string(),
+            text: compose_entry_text(&fields, get_secret(notes)),
+        });
+    }
+
+    Ok(result)
+}
+
+/// Firefox export has no names, so `https://example.com:443/login` becomes `example.com`
+fn get_host(url: &str) -> &str {
+    let url = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None => url,
+    };
+
+    url.split(['/', ':', '?', '#']).next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_csv(content: &str) -> Result<Vec<ImportedEntry>, VaultError> {
+        let dir = tempfile::tempdir().unwrap();
+        let file_name = dir.path().join("passwords.csv");
+        std::fs::write(&file_name, content).unwrap();
+
+        read_password_csv(file_name.to_str().unwrap(), "Imported")
+    }
+
+    #[test]
+    fn reads_chrome_export() {
+        let entries = read_csv(
+            "name,url,username,password,note\nGitHub,https://github.com/login,octo,secret,my notes\n",
+        )
+        .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].category, "Imported");
+        assert_eq!(entries[0].sub_category, "GitHub");
+        assert_eq!(
+            entries[0].text,
+            "username: octo\npassword: secret\nurl: https://github.com/login\n\nmy notes\n"
+        );
+    }
+
+    #[test]
+    fn names_firefox_entries_by_host() {
+        let entries = read_csv(
+            "url,username,password,httpRealm,formActionOrigin,guid,timeCreated,timeLastUsed,timePasswordChanged\n\
+             https://example.com:443/login,user,pass,,,{1},1,1,1\n",
+        )
+        .unwrap();
+
+        assert_eq!(entries[0].sub_category, "example.com");
+        assert_eq!(
+            entries[0].text,
+            "username: user\npassword: pass\nurl: https://example.com:443/login\n"
+        );
+    }
+
+    #[test]
+    fn takes_category_from_folder() {
+        let entries = read_csv(
+            "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+             Banks,,login,Bank,,,0,https://bank.com,me,pass,otpauth://totp/bank\n\
+             ,,login,Mail,,,0,,me,pass,\n",
+        )
+        .unwrap();
+
+        assert_eq!(entries[0].category, "Banks");
+        assert!(entries[0].text.contains("totp: otpauth://totp/bank\n"));
+        assert_eq!(entries[1].category, "Imported");
+    }
+
+    #[test]
+    fn keeps_whitespaces_of_secrets() {
+        let entries = read_csv(
+            " Name , Password ,TOTP,Notes\n GitHub , pass word ,otpauth://totp/a ,  indented\n",
+        )
+        .unwrap();
+
+        assert_eq!(entries[0].sub_category, "GitHub");
+        assert_eq!(
+            entries[0].text,
+            "password:  pass word \ntotp: otpauth://totp/a \n\n  indented\n"
+        );
+    }
+
+    #[test]
+    fn requires_password_column() {
+        assert!(matches!(
+            read_csv("name,url\nGitHub,https://github.com\n"),
+            Err(VaultError::Import(_))
+        ));
+    }
+}
@@ -0,0 +1,37 @@
+pub mod address_book;
+pub mod bundle;
+pub mod container;
+pub mod entry_ids;
+mod error;
+pub mod export;
+pub mod fields;
+pub mod file;
+pub mod file_lock;
+pub mod git_sync;
+mod http;
+pub mod import;
+pub mod local_api;
+pub mod merge;
+pub mod pass_store;
+pub mod password_utils;
+pub mod remote_storage;
+pub mod sealed_category;
+mod secrets;
+pub mod secure_memory;
+pub mod server_sync;
+pub mod sharing;
+pub mod storage;
+pub mod sync_server;
+pub mod team;
+pub mod template;
+mod unix_socket;
+pub mod unlock_agent;
+mod vault;
+mod vault_command;
+
+pub use error::*;
+pub use secrets::*;
+pub use vault::*;
+pub use vault_command::*;
+
+pub use encryption::aes::AesKey;
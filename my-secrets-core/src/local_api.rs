@@ -0,0 +1,681 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    sync::{mpsc, Arc},
+    time::Duration,
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zeroize::Zeroize;
+
+use crate::{sharing::IDENTITY_CATEGORY, Vault, VaultError};
+
+const SOCKET_FILE_NAME: &str = "api/api.sock";
+const CLIENTS_FILE_NAME: &str = "api/clients.json";
+const ACCESS_LOG_FILE_NAME: &str = "api/access.log";
+
+const MAX_REQUEST_SIZE: u64 = 64 * 1024;
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Registering waits for the user to answer the approval prompt
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Tokens are random, so the hash only has to be one way, not slow
+const TOKEN_HASH_SALT: &[u8] = b"my-secrets-api-token";
+
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+pub const UNKNOWN_CLIENT: i32 = -32001;
+pub const NOT_PERMITTED: i32 = -32002;
+pub const VAULT_LOCKED: i32 = -32003;
+pub const NOT_FOUND: i32 = -32004;
+pub const DENIED: i32 = -32005;
+pub const NO_ANSWER: i32 = -32006;
+
+pub fn get_socket_path() -> String {
+    crate::file::compile_full_filename(SOCKET_FILE_NAME)
+}
+
+pub fn get_clients_file_name() -> String {
+    crate::file::compile_full_filename(CLIENTS_FILE_NAME)
+}
+
+pub fn get_access_log_file_name() -> String {
+    crate::file::compile_full_filename(ACCESS_LOG_FILE_NAME)
+}
+
+/// Error of a JSON-RPC call
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<VaultError> for ApiError {
+    fn from(err: VaultError) -> Self {
+        let code = match &err {
+            VaultError::Locked | VaultError::CategoryLocked(_) => VAULT_LOCKED,
+            VaultError::CategoryNotFound(_) | VaultError::SubCategoryNotFound { .. } => NOT_FOUND,
+            _ => INVALID_REQUEST,
+        };
+
+        Self::new(code, err.to_string())
+    }
+}
+
+pub enum ApiCall {
+    /// Asks the user to approve the client for the categories. Returns the token of the client
+    Register {
+        name: String,
+        categories: Vec<String>,
+    },
+    /// Categories the client may read, or subcategories of one of them
+    List { category: Option<String> },
+    Get {
+        category: String,
+        sub_category: String,
+    },
+    /// Subcategories with the query in the name. Secrets are not searched
+    Search { query: String },
+}
+
+impl ApiCall {
+    pub fn get_method(&self) -> &'static str {
+        match self {
+            ApiCall::Register { .. } => "register",
+            ApiCall::List { .. } => "list",
+            ApiCall::Get { .. } => "get",
+            ApiCall::Search { .. } => "search",
+        }
+    }
+
+    /// What the call accesses, for the access log
+    pub fn get_target(&self) -> String {
+        match self {
+            ApiCall::Register { name, categories } => {
+                format!("{} [{}]", name, categories.join(", "))
+            }
+            ApiCall::List { category } => category.clone().unwrap_or_else(|| "-".to_string()),
+            ApiCall::Get {
+                category,
+                sub_category,
+            } => format!("{}/{}", category, sub_category),
+            ApiCall::Search { query } => format!("{:?}", query),
+        }
+    }
+}
+
+/// Process which connected to the API, so the user knows who asks to register
+#[derive(Debug, Clone)]
+pub struct PeerProcess {
+    pub pid: i32,
+    /// None if the OS does not tell it
+    pub executable: Option<String>,
+}
+
+impl PeerProcess {
+    fn from_stream(stream: &UnixStream) -> Option<Self> {
+        let pid = crate::unix_socket::get_peer_pid(stream)?;
+
+        Some(Self {
+            pid,
+            executable: crate::unix_socket::get_executable(pid),
+        })
+    }
+}
+
+impl std::fmt::Display for PeerProcess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.executable {
+            Some(executable) => write!(f, "{} (pid {})", executable, self.pid),
+            None => write!(f, "pid {}", self.pid),
+        }
+    }
+}
+
+/// Call of a client waiting for the answer of the vault
+pub struct ApiRequest {
+    pub token: Option<String>,
+    pub call: ApiCall,
+    /// None if the OS does not tell it
+    pub peer: Option<PeerProcess>,
+    reply: mpsc::Sender<Result<Value, ApiError>>,
+}
+
+impl ApiRequest {
+    pub fn respond(mut self, result: Result<Value, ApiError>) {
+        if let Some(token) = &mut self.token {
+            token.zeroize();
+        }
+
+        // The client may have given up waiting
+        let _ = self.reply.send(result);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApprovedClient {
+    pub name: String,
+    token_hash: String,
+    /// Categories the client may read
+    pub categories: Vec<String>,
+}
+
+impl ApprovedClient {
+    pub fn can_read(&self, category: &str) -> bool {
+        category != IDENTITY_CATEGORY && self.categories.iter().any(|allowed| allowed == category)
+    }
+}
+
+/// Clients the user approved. Only hashes of their tokens are kept
+pub struct ApiClients {
+    file_name: String,
+    clients: Vec<ApprovedClient>,
+}
+
+impl ApiClients {
+    pub fn load(file_name: String) -> Result<Self, VaultError> {
+        let clients = match fs::read(&file_name) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|err| VaultError::InvalidData(err.to_string()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self { file_name, clients })
+    }
+
+    pub fn get_all(&self) -> &[ApprovedClient] {
+        &self.clients
+    }
+
+    pub fn is_approved(&self, name: &str) -> bool {
+        self.clients.iter().any(|client| client.name == name)
+    }
+
+    pub fn find(&self, token: &str) -> Option<&ApprovedClient> {
+        let token_hash = hash_token(token);
+
+        self.clients
+            .iter()
+            .find(|client| is_same_hash(&client.token_hash, &token_hash))
+    }
+
+    /// Returns the token of the new client. A client of the same name has to be revoked first,
+    /// so another program can not take its place by registering with its name
+    pub fn approve(&mut self, name: &str, categories: Vec<String>) -> Result<String, VaultError> {
+        if self.is_approved(name) {
+            return Err(VaultError::InvalidData(format!(
+                "Client {} is approved already",
+                name
+            )));
+        }
+
+        let mut random = [0u8; 32];
+        crate::container::fill_random(&mut random)?;
+
+        let token: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+        random.zeroize();
+
+        self.clients.push(ApprovedClient {
+            name: name.to_string(),
+            token_hash: hash_token(&token),
+            categories,
+        });
+
+        self.save()?;
+        Ok(token)
+    }
+
+    /// False if there is no such client
+    pub fn revoke(&mut self, name: &str) -> Result<bool, VaultError> {
+        let count = self.clients.len();
+        self.clients.retain(|client| client.name != name);
+
+        if self.clients.len() == count {
+            return Ok(false);
+        }
+
+        self.save()?;
+        Ok(true)
+    }
+
+    fn save(&self) -> Result<(), VaultError> {
+        if let Some(dir) = std::path::Path::new(&self.file_name).parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        crate::file::write_private_file(
+            &self.file_name,
+            &serde_json::to_vec_pretty(&self.clients).unwrap(),
+        )?;
+        Ok(())
+    }
+}
+
+/// Every call with the client, the accessed entry and the outcome. Secrets are never written
+pub struct AccessLog {
+    file_name: String,
+}
+
+impl AccessLog {
+    pub fn new(file_name: String) -> Self {
+        Self { file_name }
+    }
+
+    pub fn write(&self, client: Option<&str>, call: &ApiCall, result: &Result<Value, ApiError>) {
+        let outcome = match result {
+            Ok(_) => "ok".to_string(),
+            Err(err) => format!("error {}: {}", err.code, err.message),
+        };
+
+        let line = format!(
+            "{} {} {} {} {}\n",
+            &DateTimeAsMicroseconds::now().to_rfc3339()[..19],
+            client.unwrap_or("-"),
+            call.get_method(),
+            call.get_target(),
+            outcome
+        );
+
+        if let Err(err) = self.append(&line) {
+            eprintln!(
+                "Can not write the access log: {}. Err: {}",
+                self.file_name, err
+            );
+        }
+    }
+
+    fn append(&self, line: &str) -> std::io::Result<()> {
+        let mut open_options = fs::OpenOptions::new();
+        open_options.append(true).create(true);
+
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(0o600);
+
+        open_options
+            .open(&self.file_name)?
+            .write_all(line.as_bytes())
+    }
+}
+
+/// Listens on the socket in a thread and handles every connection on its own thread, so a client
+/// waiting for the approval does not block the others. Every call is given to `on_request`,
+/// which has to answer it with [`ApiRequest::respond`] from wherever the vault is
+pub fn start_server(
+    socket_path: &str,
+    on_request: impl Fn(ApiRequest) + Send + Sync + 'static,
+) -> Result<(), VaultError> {
+    let listener = crate::unix_socket::bind_private(socket_path)?;
+    let on_request = Arc::new(on_request);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Can not handle the API connection. Err: {}", err);
+                    continue;
+                }
+            };
+
+            let on_request = on_request.clone();
+
+            std::thread::spawn(move || {
+                if let Err(err) = handle_connection(stream, on_request.as_ref()) {
+                    eprintln!("Can not handle the API connection. Err: {}", err);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Runs a call of an approved client against the unlocked vault
+pub fn execute(vault: &Vault, client: &ApprovedClient, call: &ApiCall) -> Result<Value, ApiError> {
+    match call {
+        ApiCall::Register { .. } => Err(ApiError::new(
+            INVALID_REQUEST,
+            "The client is registered already",
+        )),
+        ApiCall::List { category: None } => {
+            let categories: Vec<&str> = vault
+                .list()?
+                .into_iter()
+                .filter(|category| client.can_read(category))
+                .collect();
+
+            Ok(json!(categories))
+        }
+        ApiCall::List {
+            category: Some(category),
+        } => {
+            check_can_read(client, category)?;
+            Ok(json!(vault.list_sub_categories(category)?))
+        }
+        ApiCall::Get {
+            category,
+            sub_category,
+        } => {
+            check_can_read(client, category)?;
+            Ok(json!(vault.get(category, sub_category)?))
+        }
+        ApiCall::Search { query } => {
+            let query = query.to_lowercase();
+            let mut found = Vec::new();
+
+            for category in vault.list()? {
+                if !client.can_read(category) || vault.is_category_locked(category) {
+                    continue;
+                }
+
+                for sub_category in vault.list_sub_categories(category)? {
+                    if sub_category.to_lowercase().contains(&query) {
+                        found.push(format!("{}/{}", category, sub_category));
+                    }
+                }
+            }
+
+            Ok(json!(found))
+        }
+    }
+}
+
+fn check_can_read(client: &ApprovedClient, category: &str) -> Result<(), ApiError> {
+    if client.can_read(category) {
+        return Ok(());
+    }
+
+    Err(ApiError::new(
+        NOT_PERMITTED,
+        format!("{} may not read category {}", client.name, category),
+    ))
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: RpcParams,
+}
+
+#[derive(Deserialize, Default)]
+struct RpcParams {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    categories: Option<Vec<String>>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    sub_category: Option<String>,
+    #[serde(default)]
+    query: Option<String>,
+}
+
+/// One JSON-RPC 2.0 request per line and connection
+fn handle_connection(
+    stream: UnixStream,
+    on_request: &impl Fn(ApiRequest),
+) -> Result<(), VaultError> {
+    if !crate::unix_socket::is_peer_current_user(&stream) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "Refused the connection of another user",
+        )
+        .into());
+    }
+
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut line = String::new();
+    let read = BufReader::new(&stream)
+        .take(MAX_REQUEST_SIZE)
+        .read_line(&mut line)?;
+
+    // Closed without a request
+    if read == 0 {
+        return Ok(());
+    }
+
+    let request = serde_json::from_str::<RpcRequest>(&line);
+    line.zeroize();
+
+    let (id, result) = match request {
+        Ok(request) => {
+            let id = request.id.clone();
+            let peer = PeerProcess::from_stream(&stream);
+            (id, call(request, peer, on_request))
+        }
+        Err(err) => (
+            Value::Null,
+            Err(ApiError::new(
+                PARSE_ERROR,
+                format!("Invalid request. Err: {}", err),
+            )),
+        ),
+    };
+
+    let mut response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": err.code, "message": err.message },
+        }),
+    }
+    .to_string();
+    response.push('\n');
+
+    let write_result = (&stream).write_all(response.as_bytes());
+    response.zeroize();
+
+    Ok(write_result?)
+}
+
+fn call(
+    request: RpcRequest,
+    peer: Option<PeerProcess>,
+    on_request: &impl Fn(ApiRequest),
+) -> Result<Value, ApiError> {
+    if request.jsonrpc != "2.0" {
+        return Err(ApiError::new(INVALID_REQUEST, "jsonrpc must be 2.0"));
+    }
+
+    let params = request.params;
+
+    let api_call = match request.method.as_str() {
+        "register" => ApiCall::Register {
+            name: require(params.name, "name")?,
+            categories: match params.categories {
+                Some(categories) if !categories.is_empty() => check_categories(categories)?,
+                _ => return Err(ApiError::new(INVALID_PARAMS, "categories are required")),
+            },
+        },
+        "list" => ApiCall::List {
+            category: params.category,
+        },
+        "get" => ApiCall::Get {
+            category: require(params.category, "category")?,
+            sub_category: require(params.sub_category, "sub_category")?,
+        },
+        "search" => ApiCall::Search {
+            query: require(params.query, "query")?,
+        },
+        method => {
+            return Err(ApiError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method {}", method),
+            ))
+        }
+    };
+
+    let (reply, answer) = mpsc::channel();
+
+    on_request(ApiRequest {
+        token: params.token,
+        call: api_call,
+        peer,
+        reply,
+    });
+
+    answer
+        .recv_timeout(ANSWER_TIMEOUT)
+        .unwrap_or_else(|_| Err(ApiError::new(NO_ANSWER, "The vault did not answer")))
+}
+
+fn check_categories(categories: Vec<String>) -> Result<Vec<String>, ApiError> {
+    if categories
+        .iter()
+        .any(|category| category == IDENTITY_CATEGORY)
+    {
+        return Err(ApiError::new(
+            INVALID_PARAMS,
+            format!(
+                "{} category can not be shared with clients",
+                IDENTITY_CATEGORY
+            ),
+        ));
+    }
+
+    Ok(categories)
+}
+
+fn require(value: Option<String>, name: &str) -> Result<String, ApiError> {
+    value.ok_or_else(|| ApiError::new(INVALID_PARAMS, format!("{} is required", name)))
+}
+
+fn hash_token(token: &str) -> String {
+    let params = Params::new(Params::MIN_M_COST, 1, 1, Some(32)).unwrap();
+
+    let mut hash = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(token.as_bytes(), TOKEN_HASH_SALT, &mut hash)
+        .unwrap();
+
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Takes the same time wherever the hashes differ
+fn is_same_hash(left: &str, right: &str) -> bool {
+    left.len() == right.len()
+        && left
+            .bytes()
+            .zip(right.bytes())
+            .fold(0u8, |result, (left, right)| result | (left ^ right))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(socket_path: &str, request: &str) -> Value {
+        let stream = UnixStream::connect(socket_path).unwrap();
+        (&stream)
+            .write_all(format!("{}\n", request).as_bytes())
+            .unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn serves_clients_while_one_waits_for_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("api.sock").to_string_lossy().to_string();
+
+        let (sender, requests) = mpsc::channel();
+        start_server(&socket_path, move |request| sender.send(request).unwrap()).unwrap();
+
+        let register = {
+            let socket_path = socket_path.clone();
+            std::thread::spawn(move || {
+                send(
+                    &socket_path,
+                    r#"{"jsonrpc":"2.0","id":1,"method":"register","params":{"name":"deploy","categories":["Servers"]}}"#,
+                )
+            })
+        };
+
+        let waiting = requests.recv_timeout(CLIENT_TIMEOUT).unwrap();
+        assert!(matches!(waiting.call, ApiCall::Register { .. }));
+        assert_eq!(
+            waiting.peer.as_ref().map(|peer| peer.pid),
+            Some(std::process::id() as i32)
+        );
+
+        let list = {
+            let socket_path = socket_path.clone();
+            std::thread::spawn(move || {
+                send(
+                    &socket_path,
+                    r#"{"jsonrpc":"2.0","id":2,"method":"list","params":{"token":"t"}}"#,
+                )
+            })
+        };
+
+        requests
+            .recv_timeout(CLIENT_TIMEOUT)
+            .unwrap()
+            .respond(Ok(json!(["Servers"])));
+        assert_eq!(list.join().unwrap()["result"], json!(["Servers"]));
+
+        waiting.respond(Err(ApiError::new(DENIED, "Denied")));
+        assert_eq!(register.join().unwrap()["error"]["code"], json!(DENIED));
+    }
+
+    #[test]
+    fn refuses_to_approve_name_twice() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_name = dir
+            .path()
+            .join("clients.json")
+            .to_string_lossy()
+            .to_string();
+
+        let mut clients = ApiClients::load(file_name.clone()).unwrap();
+        let token = clients
+            .approve("deploy", vec!["Servers".to_string()])
+            .unwrap();
+
+        assert!(clients.approve("deploy", vec!["Bank".to_string()]).is_err());
+
+        let clients = ApiClients::load(file_name).unwrap();
+        assert_eq!(clients.find(&token).unwrap().categories, vec!["Servers"]);
+    }
+
+    #[test]
+    fn never_reads_identity() {
+        let client = ApprovedClient {
+            name: "deploy".to_string(),
+            token_hash: String::new(),
+            categories: vec![IDENTITY_CATEGORY.to_string()],
+        };
+
+        assert!(!client.can_read(IDENTITY_CATEGORY));
+    }
+}
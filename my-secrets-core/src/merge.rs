@@ -0,0 +1,529 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use zeroize::Zeroize;
+
+use crate::{
+    entry_ids::{self, EntryIds},
+    TypeContent,
+};
+
+/// One version of the vault to merge
+pub struct MergeInput<'s> {
+    pub content: &'s TypeContent,
+    pub ids: &'s EntryIds,
+}
+
+/// Entry as it is on one side of a conflict
+#[derive(Clone)]
+pub struct MergeEntry {
+    pub category: String,
+    pub sub_category: String,
+    pub text: String,
+}
+
+impl Drop for MergeEntry {
+    fn drop(&mut self) {
+        self.category.zeroize();
+        self.sub_category.zeroize();
+        self.text.zeroize();
+    }
+}
+
+/// Entry changed differently on both sides: edited, renamed or moved.
+/// None means the entry does not exist on that side
+pub struct MergeConflict {
+    pub id: String,
+    pub base: Option<MergeEntry>,
+    pub ours: Option<MergeEntry>,
+    pub theirs: Option<MergeEntry>,
+}
+
+impl MergeConflict {
+    /// Name of the entry to show. Ours if we have it
+    pub fn get_path(&self) -> String {
+        match self.ours.as_ref().or(self.theirs.as_ref()) {
+            Some(entry) => format!("{}/{}", entry.category, entry.sub_category),
+            None => self.id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeChoice {
+    #[default]
+    Ours,
+    Theirs,
+}
+
+/// Merged content with our side taken for every conflict until they are resolved
+#[derive(Default)]
+pub struct MergeResult {
+    pub content: TypeContent,
+    pub ids: EntryIds,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// Content and IDs with the chosen side of each conflict. Missing choices keep our side
+    pub fn resolve(&mut self, choices: &[MergeChoice]) -> (TypeContent, EntryIds) {
+        let mut content = std::mem::take(&mut self.content);
+        let mut ids = std::mem::take(&mut self.ids);
+
+        for (conflict, choice) in self.conflicts.iter().zip(choices) {
+            if *choice == MergeChoice::Ours {
+                continue;
+            }
+
+            if let Some(ours) = &conflict.ours {
+                remove_entry(&mut content, &ours.category, &ours.sub_category);
+                ids.remove(&ours.category, &ours.sub_category);
+            }
+
+            if let Some(theirs) = &conflict.theirs {
+                // Another entry may have the name by now
+                let sub_category =
+                    get_free_sub_category(&content, &theirs.category, &theirs.sub_category);
+
+                set_entry(
+                    &mut content,
+                    &theirs.category,
+                    &sub_category,
+                    theirs.text.clone(),
+                );
+                ids.set(&theirs.category, &sub_category, conflict.id.clone());
+            }
+        }
+
+        (content, ids)
+    }
+}
+
+impl Drop for MergeResult {
+    fn drop(&mut self) {
+        crate::wipe_content(std::mem::take(&mut self.content));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Entry<'s> {
+    category: &'s str,
+    sub_category: &'s str,
+    text: &'s str,
+}
+
+impl<'s> Entry<'s> {
+    fn get_location(&self) -> (&'s str, &'s str) {
+        (self.category, self.sub_category)
+    }
+
+    fn to_merge_entry(self) -> MergeEntry {
+        MergeEntry {
+            category: self.category.to_string(),
+            sub_category: self.sub_category.to_string(),
+            text: self.text.to_string(),
+        }
+    }
+}
+
+/// Three-way merge of entries matched by their IDs. The name and the text of an entry are merged
+/// separately, so an entry renamed on one side and edited on the other gets both changes.
+/// Entries changed on both sides to different results become conflicts.
+/// Without a base every entry existing on both sides in different states is a conflict
+pub fn merge_content(base: &MergeInput, ours: &MergeInput, theirs: &MergeInput) -> MergeResult {
+    let mut result = MergeResult::default();
+
+    let base_entries = collect_entries(base);
+    let our_entries = collect_entries(ours);
+    let their_entries = collect_entries(theirs);
+
+    let ids: BTreeSet<&String> = base_entries
+        .keys()
+        .chain(our_entries.keys())
+        .chain(their_entries.keys())
+        .collect();
+
+    // Entries staying where they are here go first, so a name taken on both sides is kept by ours
+    // and the entry coming from theirs gets a free name
+    let mut in_place = Vec::new();
+    let mut moved = Vec::new();
+
+    for id in ids {
+        let base_entry = base_entries.get(id).copied();
+        let our_entry = our_entries.get(id).copied();
+        let their_entry = their_entries.get(id).copied();
+
+        let merged = match (our_entry, their_entry) {
+            (Some(our_entry), Some(their_entry)) => {
+                let location = merge_value(
+                    base_entry.map(|entry| entry.get_location()),
+                    Some(our_entry.get_location()),
+                    Some(their_entry.get_location()),
+                );
+                let text = merge_value(
+                    base_entry.map(|entry| entry.text),
+                    Some(our_entry.text),
+                    Some(their_entry.text),
+                );
+
+                match (location, text) {
+                    (Some(Some((category, sub_category))), Some(Some(text))) => Some(Some(Entry {
+                        category,
+                        sub_category,
+                        text,
+                    })),
+                    _ => None,
+                }
+            }
+            _ => merge_value(base_entry, our_entry, their_entry),
+        };
+
+        let entry = match merged {
+            Some(entry) => entry,
+            None => {
+                result.conflicts.push(MergeConflict {
+                    id: id.to_string(),
+                    base: base_entry.map(Entry::to_merge_entry),
+                    ours: our_entry.map(Entry::to_merge_entry),
+                    theirs: their_entry.map(Entry::to_merge_entry),
+                });
+
+                our_entry
+            }
+        };
+
+        if let Some(entry) = entry {
+            if our_entry.map(|our_entry| our_entry.get_location()) == Some(entry.get_location()) {
+                in_place.push((id, entry));
+            } else {
+                moved.push((id, entry));
+            }
+        }
+    }
+
+    for (id, entry) in in_place.into_iter().chain(moved) {
+        let sub_category =
+            get_free_sub_category(&result.content, entry.category, entry.sub_category);
+
+        set_entry(
+            &mut result.content,
+            entry.category,
+            &sub_category,
+            entry.text.to_string(),
+        );
+        result
+            .ids
+            .set(entry.category, &sub_category, id.to_string());
+    }
+
+    // Empty categories
+    let categories: BTreeSet<&String> = base
+        .content
+        .keys()
+        .chain(ours.content.keys())
+        .chain(theirs.content.keys())
+        .collect();
+
+    for category in categories {
+        let in_ours = ours.content.contains_key(category);
+
+        let keep = merge_value(
+            base.content.contains_key(category),
+            in_ours,
+            theirs.content.contains_key(category),
+        )
+        .unwrap_or(in_ours);
+
+        if keep {
+            result.content.entry(category.to_string()).or_default();
+        }
+    }
+
+    result
+}
+
+/// Entries of the version by ID. Entries without a stored ID get one from their path
+fn collect_entries<'s>(input: &MergeInput<'s>) -> BTreeMap<String, Entry<'s>> {
+    let mut result = BTreeMap::new();
+
+    for (category, sub_categories) in input.content {
+        for (sub_category, text) in sub_categories {
+            let id = match input.ids.get(category, sub_category) {
+                Some(id) if !result.contains_key(id) => id.to_string(),
+                _ => entry_ids::derive_id(category, sub_category, |id| result.contains_key(id)),
+            };
+
+            result.insert(
+                id,
+                Entry {
+                    category,
+                    sub_category,
+                    text,
+                },
+            );
+        }
+    }
+
+    result
+}
+
+/// Value of the side which changed it, None if both sides changed it differently
+fn merge_value<T: PartialEq>(base: T, ours: T, theirs: T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+/// The name if no entry has it, otherwise `name (theirs)`, `name (theirs 2)`...
+fn get_free_sub_category(content: &TypeContent, category: &str, sub_category: &str) -> String {
+    let is_taken = |name: &str| {
+        content
+            .get(category)
+            .map(|sub_categories| sub_categories.contains_key(name))
+            .unwrap_or(false)
+    };
+
+    if !is_taken(sub_category) {
+        return sub_category.to_string();
+    }
+
+    let mut index = 1;
+
+    loop {
+        let name = match index {
+            1 => format!("{} (theirs)", sub_category),
+            _ => format!("{} (theirs {})", sub_category, index),
+        };
+
+        if !is_taken(&name) {
+            return name;
+        }
+
+        index += 1;
+    }
+}
+
+fn set_entry(content: &mut TypeContent, category: &str, sub_category: &str, text: String) {
+    let prev = content
+        .entry(category.to_string())
+        .or_default()
+        .insert(sub_category.to_string(), text);
+
+    if let Some(mut prev) = prev {
+        prev.zeroize();
+    }
+}
+
+fn remove_entry(content: &mut TypeContent, category: &str, sub_category: &str) {
+    if let Some(mut prev) = content
+        .get_mut(category)
+        .and_then(|entries| entries.remove(sub_category))
+    {
+        prev.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const ID_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    const ID_C: &str = "cccccccccccccccccccccccccccccccc";
+
+    struct Version {
+        content: TypeContent,
+        ids: EntryIds,
+    }
+
+    impl Version {
+        /// Entries as `(id, category, sub_category, text)`
+        fn new(entries: &[(&str, &str, &str, &str)]) -> Self {
+            let mut content = TypeContent::new();
+            let mut ids = EntryIds::default();
+
+            for (id, category, sub_category, text) in entries {
+                set_entry(&mut content, category, sub_category, text.to_string());
+                ids.set(category, sub_category, id.to_string());
+            }
+
+            Self { content, ids }
+        }
+
+        fn input(&self) -> MergeInput<'_> {
+            MergeInput {
+                content: &self.content,
+                ids: &self.ids,
+            }
+        }
+    }
+
+    fn merge(base: &Version, ours: &Version, theirs: &Version) -> MergeResult {
+        merge_content(&base.input(), &ours.input(), &theirs.input())
+    }
+
+    fn get<'s>(content: &'s TypeContent, category: &str, sub_category: &str) -> Option<&'s str> {
+        content
+            .get(category)
+            .and_then(|sub_categories| sub_categories.get(sub_category))
+            .map(|text| text.as_str())
+    }
+
+    #[test]
+    fn takes_changes_of_both_sides() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1"), (ID_B, "Mail", "home", "2")]);
+        let ours = Version::new(&[
+            (ID_A, "Mail", "work", "1 ours"),
+            (ID_B, "Mail", "home", "2"),
+        ]);
+        let theirs = Version::new(&[
+            (ID_A, "Mail", "work", "1"),
+            (ID_B, "Mail", "home", "2 theirs"),
+        ]);
+
+        let result = merge(&base, &ours, &theirs);
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(get(&result.content, "Mail", "work"), Some("1 ours"));
+        assert_eq!(get(&result.content, "Mail", "home"), Some("2 theirs"));
+    }
+
+    #[test]
+    fn merges_rename_with_edit() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1")]);
+        let ours = Version::new(&[(ID_A, "Banks", "office", "1")]);
+        let theirs = Version::new(&[(ID_A, "Mail", "work", "1 theirs")]);
+
+        let result = merge(&base, &ours, &theirs);
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(get(&result.content, "Banks", "office"), Some("1 theirs"));
+        assert_eq!(get(&result.content, "Mail", "work"), None);
+        assert_eq!(result.ids.get("Banks", "office"), Some(ID_A));
+    }
+
+    #[test]
+    fn takes_delete_of_unchanged_entry() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1"), (ID_B, "Mail", "home", "2")]);
+        let ours = Version::new(&[(ID_A, "Mail", "work", "1"), (ID_B, "Mail", "home", "2")]);
+        let theirs = Version::new(&[(ID_B, "Mail", "home", "2")]);
+
+        let result = merge(&base, &ours, &theirs);
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(get(&result.content, "Mail", "work"), None);
+        assert_eq!(get(&result.content, "Mail", "home"), Some("2"));
+    }
+
+    #[test]
+    fn different_edits_are_conflict() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1")]);
+        let ours = Version::new(&[(ID_A, "Mail", "work", "1 ours")]);
+        let theirs = Version::new(&[(ID_A, "Mail", "work", "1 theirs")]);
+
+        let mut result = merge(&base, &ours, &theirs);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].id, ID_A);
+        assert_eq!(result.conflicts[0].get_path(), "Mail/work");
+        assert_eq!(get(&result.content, "Mail", "work"), Some("1 ours"));
+
+        let (content, ids) = result.resolve(&[MergeChoice::Theirs]);
+
+        assert_eq!(get(&content, "Mail", "work"), Some("1 theirs"));
+        assert_eq!(ids.get("Mail", "work"), Some(ID_A));
+    }
+
+    #[test]
+    fn edit_of_deleted_entry_is_conflict() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1")]);
+        let ours = Version::new(&[]);
+        let theirs = Version::new(&[(ID_A, "Mail", "work", "1 theirs")]);
+
+        let mut result = merge(&base, &ours, &theirs);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert!(result.conflicts[0].ours.is_none());
+        assert_eq!(get(&result.content, "Mail", "work"), None);
+
+        let (content, _) = result.resolve(&[MergeChoice::Theirs]);
+        assert_eq!(get(&content, "Mail", "work"), Some("1 theirs"));
+    }
+
+    #[test]
+    fn without_base_different_entries_are_conflicts() {
+        let base = Version::new(&[]);
+        let ours = Version::new(&[
+            (ID_A, "Mail", "work", "1 ours"),
+            (ID_B, "Mail", "home", "2"),
+        ]);
+        let theirs = Version::new(&[
+            (ID_A, "Mail", "work", "1 theirs"),
+            (ID_B, "Mail", "home", "2"),
+        ]);
+
+        let result = merge(&base, &ours, &theirs);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].id, ID_A);
+        assert_eq!(get(&result.content, "Mail", "home"), Some("2"));
+    }
+
+    #[test]
+    fn keeps_both_entries_getting_the_same_name() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1"), (ID_B, "Mail", "home", "2")]);
+        let ours = Version::new(&[
+            (ID_A, "Mail", "work", "1"),
+            (ID_B, "Mail", "home", "2"),
+            (ID_C, "Mail", "office", "3"),
+        ]);
+        let theirs = Version::new(&[(ID_A, "Mail", "work", "1"), (ID_B, "Mail", "office", "2")]);
+
+        let result = merge(&base, &ours, &theirs);
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(get(&result.content, "Mail", "office"), Some("3"));
+        assert_eq!(get(&result.content, "Mail", "office (theirs)"), Some("2"));
+        assert_eq!(get(&result.content, "Mail", "home"), None);
+        assert_eq!(result.ids.get("Mail", "office"), Some(ID_C));
+        assert_eq!(result.ids.get("Mail", "office (theirs)"), Some(ID_B));
+    }
+
+    #[test]
+    fn resolving_to_theirs_keeps_entry_having_the_name() {
+        let base = Version::new(&[(ID_A, "Mail", "work", "1"), (ID_B, "Mail", "home", "2")]);
+        let ours = Version::new(&[
+            (ID_A, "Mail", "office", "1"),
+            (ID_B, "Mail", "home", "2"),
+            (ID_C, "Mail", "old", "3"),
+        ]);
+        let theirs = Version::new(&[(ID_A, "Mail", "old", "1"), (ID_B, "Mail", "home", "2")]);
+
+        let mut result = merge(&base, &ours, &theirs);
+
+        assert_eq!(result.conflicts.len(), 1);
+
+        let (content, ids) = result.resolve(&[MergeChoice::Theirs]);
+
+        assert_eq!(get(&content, "Mail", "old"), Some("3"));
+        assert_eq!(get(&content, "Mail", "old (theirs)"), Some("1"));
+        assert_eq!(get(&content, "Mail", "office"), None);
+        assert_eq!(ids.get("Mail", "old"), Some(ID_C));
+        assert_eq!(ids.get("Mail", "old (theirs)"), Some(ID_A));
+    }
+
+    #[test]
+    fn keeps_empty_category_added_by_them() {
+        let base = Version::new(&[]);
+        let ours = Version::new(&[]);
+        let mut theirs = Version::new(&[]);
+        theirs.content.insert("Banks".to_string(), BTreeMap::new());
+
+        let result = merge(&base, &ours, &theirs);
+
+        assert!(result.content.contains_key("Banks"));
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use zeroize::Zeroize;
+
+use crate::{
+    fields,
+    import::{compose_entry_text, ImportedEntry},
+    Vault, VaultError,
+};
+
+const DEFAULT_STORE_DIR: &str = ".password-store";
+const GPG_ID_FILE: &str = ".gpg-id";
+const GPG_EXTENSION: &str = "gpg";
+const PASSWORD_FIELD: &str = "password";
+
+/// Entries in the root of the store have no directory to become a category
+const ROOT_CATEGORY: &str = "pass";
+
+/// $PASSWORD_STORE_DIR or ~/.password-store the same way `pass` finds it
+pub fn get_default_store_dir() -> String {
+    match std::env::var("PASSWORD_STORE_DIR") {
+        Ok(dir) => dir,
+        Err(_) => format!(
+            "{}/{}",
+            std::env::var("HOME").unwrap_or_default(),
+            DEFAULT_STORE_DIR
+        ),
+    }
+}
+
+/// Local gpg binary. Passphrases of the secret keys are asked by gpg-agent
+pub struct Gpg {
+    pub binary: String,
+    /// Secret key to try first if files are encrypted to hidden recipients
+    pub secret_key: Option<String>,
+}
+
+impl Default for Gpg {
+    fn default() -> Self {
+        Self {
+            binary: "gpg".to_string(),
+            secret_key: None,
+        }
+    }
+}
+
+impl Gpg {
+    fn decrypt(&self, file_name: &Path) -> Result<String, VaultError> {
+        let mut command = Command::new(&self.binary);
+        command.arg("--quiet").arg("--decrypt");
+
+        if let Some(secret_key) = &self.secret_key {
+            command.arg("--try-secret-key").arg(secret_key);
+        }
+
+        let output = command
+            .arg(file_name)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| {
+                VaultError::Gpg(format!("Can not start {}. Err: {}", self.binary, err))
+            })?;
+
+        if !output.status.success() {
+            return Err(VaultError::Gpg(format!(
+                "Can not decrypt {}. {}",
+                file_name.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        String::from_utf8(output.stdout).map_err(|err| {
+            let mut bytes = err.into_bytes();
+            bytes.zeroize();
+            VaultError::Gpg(format!("{} is not a text file", file_name.display()))
+        })
+    }
+
+    fn encrypt(
+        &self,
+        recipients: &[String],
+        content: &str,
+        file_name: &Path,
+    ) -> Result<(), VaultError> {
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("--quiet")
+            .arg("--batch")
+            .arg("--yes")
+            .arg("--encrypt");
+
+        for recipient in recipients {
+            command.arg("--recipient").arg(recipient);
+        }
+
+        let mut child = command
+            .arg("--output")
+            .arg(file_name)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                VaultError::Gpg(format!("Can not start {}. Err: {}", self.binary, err))
+            })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            return Err(VaultError::Gpg(format!(
+                "Can not encrypt {}. {}",
+                file_name.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads `<dir>/<category>/<subcategory>.gpg` files. Deeper directories stay in the subcategory name:
+/// `email/work/gmail.gpg` becomes `email` / `work/gmail`. The first line of the file is the password
+pub fn read_pass_store(dir: &str, gpg: &Gpg) -> Result<Vec<ImportedEntry>, VaultError> {
+    let mut files = Vec::new();
+    collect_gpg_files(Path::new(dir), &mut files)?;
+    files.sort();
+
+    let mut result = Vec::new();
+
+    for file_name in files {
+        let relative = file_name
+            .strip_prefix(dir)
+            .unwrap_or(&file_name)
+            .with_extension("");
+
+        let components: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        let (category, sub_category) = match components.as_slice() {
+            [name] => (ROOT_CATEGORY.to_string(), name.to_string()),
+            [category, rest @ ..] => (category.to_string(), rest.join("/")),
+            [] => continue,
+        };
+
+        let mut content = gpg.decrypt(&file_name)?;
+
+        let text = {
+            let (password, rest) = content.split_once('\n').unwrap_or((content.as_str(), ""));
+            compose_entry_text(&[(PASSWORD_FIELD, password)], rest)
+        };
+
+        content.zeroize();
+
+        result.push(ImportedEntry {
+            category,
+            sub_category,
+            text,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Writes categories of the vault as the pass layout encrypted to the recipients.
+/// Returns the amount of written entries
+pub fn write_pass_store(
+    vault: &Vault,
+    dir: &str,
+    categories: Option<&[String]>,
+    recipients: &[String],
+    gpg: &Gpg,
+) -> Result<usize, VaultError> {
+    if recipients.is_empty() {
+        return Err(VaultError::Gpg(
+            "At least one recipient is required".to_string(),
+        ));
+    }
+
+    let content = vault.content()?;
+
+    if let Some(categories) = categories {
+        for category in categories {
+            crate::sharing::check_not_identity(category)?;
+
+            if !content.contains_key(category) {
+                return Err(VaultError::CategoryNotFound(category.to_string()));
+            }
+        }
+    }
+
+    std::fs::create_dir_all(dir)?;
+
+    let gpg_id_file = Path::new(dir).join(GPG_ID_FILE);
+    if !gpg_id_file.exists() {
+        std::fs::write(&gpg_id_file, format!("{}\n", recipients.join("\n")))?;
+    }
+
+    let mut written = 0;
+
+    for (category, sub_categories) in content {
+        if category == crate::sharing::IDENTITY_CATEGORY {
+            continue;
+        }
+
+        if let Some(categories) = categories {
+            if !categories.contains(category) {
+                continue;
+            }
+        }
+
+        for (sub_category, text) in sub_categories {
+            let file_name = compile_entry_file_name(dir, category, sub_category)?;
+
+            if let Some(parent) = file_name.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let mut pass_content = to_pass_content(text);
+            let result = gpg.encrypt(recipients, &pass_content, &file_name);
+            pass_content.zeroize();
+            result?;
+
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Puts the password field to the first line as `pass` expects. Text without it is written as is
+fn to_pass_content(text: &str) -> String {
+    let password = match fields::get_field(text, PASSWORD_FIELD) {
+        Some(password) => password,
+        None => return text.to_string(),
+    };
+
+    let mut result = format!("{}\n", password);
+    let mut password_skipped = false;
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if !password_skipped
+            && fields::parse_fields(line).first() == Some(&(PASSWORD_FIELD, password))
+        {
+            password_skipped = true;
+
+            // Separator between the fields and the notes added on import
+            if lines.peek() == Some(&"") {
+                lines.next();
+            }
+            continue;
+        }
+
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    result
+}
+
+fn compile_entry_file_name(
+    dir: &str,
+    category: &str,
+    sub_category: &str,
+) -> Result<PathBuf, VaultError> {
+    let mut result = PathBuf::from(dir);
+    let components: Vec<&str> = std::iter::once(category)
+        .chain(sub_category.split('/'))
+        .collect();
+
+    for (index, component) in components.iter().enumerate() {
+        if component.is_empty() || *component == "." || *component == ".." {
+            return Err(VaultError::InvalidData(format!(
+                "{}/{} can not be written as a file path",
+                category, sub_category
+            )));
+        }
+
+        if index == components.len() - 1 {
+            // Not set_extension: names like `example.com` already have a dot
+            result.push(format!("{}.{}", component, GPG_EXTENSION));
+        } else {
+            result.push(component);
+        }
+    }
+
+    Ok(result)
+}
+
+fn collect_gpg_files(dir: &Path, result: &mut Vec<PathBuf>) -> Result<(), VaultError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        let is_hidden = path
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(true);
+
+        if is_hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_gpg_files(&path, result)?;
+        } else if path.extension().map(|extension| extension == GPG_EXTENSION) == Some(true) {
+            result.push(path);
+        }
+    }
+
+    Ok(())
+}
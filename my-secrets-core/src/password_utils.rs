@@ -1,3 +1,6 @@
+use encryption::aes::AesKey;
+use zeroize::Zeroize;
+
 const PASSWORD_SIZE: usize = 32;
 
 pub fn make_password_complient(src: &[u8]) -> [u8; PASSWORD_SIZE] {
@@ -18,3 +21,13 @@ pub fn make_password_complient(src: &[u8]) -> [u8; PASSWORD_SIZE] {
 
     result
 }
+
+/// Derives the vault key from the master password and the IV stored in the keychain
+pub fn create_aes_key(password: &str, iv: [u8; 16]) -> AesKey {
+    let mut key = make_password_complient(password.as_bytes());
+
+    let result = AesKey { key, iv };
+    key.zeroize();
+
+    result
+}
@@ -0,0 +1,430 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use zeroize::Zeroize;
+
+use crate::{
+    container,
+    http::{self, Response},
+    storage::{self, Storage, StoredFile, WriteCondition, Written},
+    VaultError,
+};
+
+const TIMEOUT_SECONDS: u32 = 60;
+
+/// Exit codes of curl for a host which could not be reached: not resolved, connection refused,
+/// timed out, empty reply and connection reset
+const OFFLINE_EXIT_CODES: [i32; 5] = [6, 7, 28, 52, 56];
+
+/// Credentials are passed to curl on stdin, so they are not visible in the process list
+pub enum RemoteAuth {
+    None,
+    Basic {
+        user: String,
+        password: String,
+    },
+    /// Signature V4 of S3 compatible storages
+    AwsSigV4 {
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+/// Vault file on a WebDAV server, e.g. Nextcloud. Backups are copied into a collection on the same server
+pub struct WebDavStorage {
+    file: RemoteFile,
+    backups_url: Option<String>,
+}
+
+impl WebDavStorage {
+    pub fn new(url: String, backups_url: Option<String>, auth: RemoteAuth) -> Self {
+        Self {
+            file: RemoteFile { url, auth },
+            backups_url: backups_url.map(|url| url.trim_end_matches('/').to_string()),
+        }
+    }
+}
+
+impl Storage for WebDavStorage {
+    fn read(&self) -> Result<Option<StoredFile>, VaultError> {
+        self.file.read()
+    }
+
+    fn get_version(&self) -> Result<Option<String>, VaultError> {
+        self.file.get_version()
+    }
+
+    fn write(&self, data: &[u8], condition: WriteCondition) -> Result<Written, VaultError> {
+        self.file.write(data, condition)
+    }
+
+    fn backup(&self) -> Result<(), VaultError> {
+        let Some(backups_url) = &self.backups_url else {
+            return Ok(());
+        };
+
+        // Fails with 405 once the collection exists
+        self.file
+            .send("MKCOL", &format!("{}/", backups_url), Vec::new(), None)?;
+
+        let response = self.file.send(
+            "COPY",
+            &self.file.url,
+            vec![
+                (
+                    "Destination",
+                    format!("{}/{}", backups_url, storage::get_backup_name()),
+                ),
+                ("Overwrite", "F".to_string()),
+            ],
+            None,
+        )?;
+
+        // 404: nothing to back up yet
+        match response.status {
+            201 | 204 | 404 => Ok(()),
+            _ => Err(to_error(&self.file.url, &response)),
+        }
+    }
+
+    fn get_location(&self) -> String {
+        self.file.url.clone()
+    }
+}
+
+/// Vault file in a bucket of an S3 compatible storage, e.g. MinIO. Addressed path-style:
+/// `<endpoint>/<bucket>/<key>`. Backups are copied to keys with the prefix
+pub struct S3Storage {
+    file: RemoteFile,
+    endpoint: String,
+    bucket: String,
+    key: String,
+    backups_prefix: Option<String>,
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: &str,
+        bucket: String,
+        key: String,
+        backups_prefix: Option<String>,
+        auth: RemoteAuth,
+    ) -> Self {
+        Self {
+            file: RemoteFile {
+                url: get_object_url(endpoint, &bucket, &key),
+                auth,
+            },
+            endpoint: endpoint.to_string(),
+            bucket,
+            key,
+            backups_prefix,
+        }
+    }
+}
+
+impl Storage for S3Storage {
+    fn read(&self) -> Result<Option<StoredFile>, VaultError> {
+        self.file.read()
+    }
+
+    fn get_version(&self) -> Result<Option<String>, VaultError> {
+        self.file.get_version()
+    }
+
+    fn write(&self, data: &[u8], condition: WriteCondition) -> Result<Written, VaultError> {
+        self.file.write(data, condition)
+    }
+
+    fn backup(&self) -> Result<(), VaultError> {
+        let Some(backups_prefix) = &self.backups_prefix else {
+            return Ok(());
+        };
+
+        let backup_key = format!("{}{}", backups_prefix, storage::get_backup_name());
+        let backup_url = get_object_url(&self.endpoint, &self.bucket, &backup_key);
+
+        let response = self.file.send(
+            "PUT",
+            &backup_url,
+            vec![(
+                "x-amz-copy-source",
+                format!("/{}/{}", self.bucket, self.key),
+            )],
+            None,
+        )?;
+
+        // 404: nothing to back up yet
+        match response.status {
+            200 | 404 => Ok(()),
+            _ => Err(to_error(&backup_url, &response)),
+        }
+    }
+
+    fn get_location(&self) -> String {
+        self.file.url.clone()
+    }
+}
+
+/// File behind a URL supporting conditional requests with ETags. Requests are made with curl
+struct RemoteFile {
+    url: String,
+    auth: RemoteAuth,
+}
+
+impl RemoteFile {
+    fn read(&self) -> Result<Option<StoredFile>, VaultError> {
+        let response = self.send("GET", &self.url, Vec::new(), None)?;
+
+        match response.status {
+            200 => Ok(Some(StoredFile {
+                version: Some(get_etag(&self.url, &response)?),
+                data: response.body,
+                base: None,
+            })),
+            404 => Ok(None),
+            _ => Err(to_error(&self.url, &response)),
+        }
+    }
+
+    fn get_version(&self) -> Result<Option<String>, VaultError> {
+        let response = self.send("HEAD", &self.url, Vec::new(), None)?;
+
+        match response.status {
+            200 => Ok(Some(get_etag(&self.url, &response)?)),
+            404 => Ok(None),
+            _ => Err(to_error(&self.url, &response)),
+        }
+    }
+
+    fn write(&self, data: &[u8], condition: WriteCondition) -> Result<Written, VaultError> {
+        let headers = match condition {
+            WriteCondition::Any => Vec::new(),
+            WriteCondition::Missing => vec![("If-None-Match", "*".to_string())],
+            WriteCondition::Version(version) => vec![("If-Match", version.to_string())],
+        };
+
+        let response = self.send("PUT", &self.url, headers, Some(data))?;
+
+        match response.status {
+            200 | 201 | 204 => {}
+            // 409 is S3 for a conditional write racing with another one
+            412 | 409 => return Err(VaultError::FileChanged),
+            _ => return Err(to_error(&self.url, &response)),
+        }
+
+        // Not every WebDAV server returns the ETag of the written file
+        let version = match response.get_header("ETag") {
+            Some(etag) => etag.to_string(),
+            None => self
+                .get_version()?
+                .ok_or_else(|| VaultError::Storage(format!("{} is gone", self.url)))?,
+        };
+
+        Ok(Written::Stored(version))
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: Vec<(&str, String)>,
+        body: Option<&[u8]>,
+    ) -> Result<Response, VaultError> {
+        let mut values = vec![url];
+        values.extend(headers.iter().map(|(_, value)| value.as_str()));
+
+        match &self.auth {
+            RemoteAuth::None => {}
+            RemoteAuth::Basic { user, password } => {
+                values.extend([user.as_str(), password.as_str()])
+            }
+            RemoteAuth::AwsSigV4 {
+                region,
+                access_key,
+                secret_key,
+            } => values.extend([region.as_str(), access_key.as_str(), secret_key.as_str()]),
+        }
+
+        for value in values {
+            check_config_value(value)?;
+        }
+
+        let body_file = match body {
+            Some(body) => Some(TempFile::create(body)?),
+            None => None,
+        };
+
+        let mut config = vec![
+            format!("url = \"{}\"", escape(url)),
+            "silent".to_string(),
+            "show-error".to_string(),
+            "include".to_string(),
+            format!("max-time = {}", TIMEOUT_SECONDS),
+            // curl would wait for 100 Continue before sending the body
+            "header = \"Expect:\"".to_string(),
+        ];
+
+        if method == "HEAD" {
+            config.push("head".to_string());
+        } else {
+            config.push(format!("request = \"{}\"", method));
+        }
+
+        for (name, value) in headers {
+            config.push(format!("header = \"{}: {}\"", name, escape(&value)));
+        }
+
+        if let Some(body_file) = &body_file {
+            config.push(format!(
+                "data-binary = \"@{}\"",
+                escape(&body_file.file_name)
+            ));
+        }
+
+        match &self.auth {
+            RemoteAuth::None => {}
+            RemoteAuth::Basic { user, password } => {
+                config.push(format!("user = \"{}:{}\"", escape(user), escape(password)));
+            }
+            RemoteAuth::AwsSigV4 {
+                region,
+                access_key,
+                secret_key,
+            } => {
+                config.push(format!(
+                    "user = \"{}:{}\"",
+                    escape(access_key),
+                    escape(secret_key)
+                ));
+                config.push(format!("aws-sigv4 = \"aws:amz:{}:s3\"", escape(region)));
+            }
+        }
+
+        let mut config = config.join("\n");
+        config.push('\n');
+
+        let result = run_curl(&config);
+        config.zeroize();
+
+        let output = result?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+            return match output.status.code() {
+                Some(code) if OFFLINE_EXIT_CODES.contains(&code) => Err(VaultError::Offline(
+                    format!("Can not reach {}. Err: {}", url, err),
+                )),
+                _ => Err(VaultError::Storage(format!(
+                    "{} {}. Err: {}",
+                    method, url, err
+                ))),
+            };
+        }
+
+        http::parse_response(&output.stdout).map_err(|err| {
+            VaultError::Storage(format!("Invalid response from {}. Err: {}", url, err))
+        })
+    }
+}
+
+fn run_curl(config: &str) -> Result<std::process::Output, VaultError> {
+    let mut child = Command::new("curl")
+        .arg("--config")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| VaultError::Storage(format!("Can not start curl. Err: {}", err)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+
+    Ok(child.wait_with_output()?)
+}
+
+/// Encrypted vault data to upload. Removed once the request is done
+struct TempFile {
+    file_name: String,
+}
+
+impl TempFile {
+    fn create(data: &[u8]) -> Result<Self, VaultError> {
+        let mut random = [0u8; 8];
+        container::fill_random(&mut random)?;
+
+        let suffix: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        let file_name = std::env::temp_dir()
+            .join(format!("my-secrets-upload-{}", suffix))
+            .to_string_lossy()
+            .to_string();
+
+        crate::file::write_private_file(&file_name, data)?;
+
+        Ok(Self { file_name })
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.file_name);
+    }
+}
+
+fn get_object_url(endpoint: &str, bucket: &str, key: &str) -> String {
+    format!("{}/{}/{}", endpoint.trim_end_matches('/'), bucket, key)
+}
+
+fn get_etag(url: &str, response: &Response) -> Result<String, VaultError> {
+    response
+        .get_header("ETag")
+        .map(|etag| etag.to_string())
+        .ok_or_else(|| VaultError::Storage(format!("{} returned no ETag", url)))
+}
+
+fn to_error(url: &str, response: &Response) -> VaultError {
+    VaultError::Storage(format!(
+        "{} responded {}. {}",
+        url,
+        response.status,
+        String::from_utf8_lossy(&response.body).trim()
+    ))
+}
+
+/// A line break would end the quoted value of the curl config and start another option
+fn check_config_value(value: &str) -> Result<(), VaultError> {
+    if value.chars().any(char::is_control) {
+        return Err(VaultError::Storage(
+            "Remote storage settings can not contain line breaks or other control characters"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Quoted value of a curl config file
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_line_breaks_in_config_values() {
+        assert!(check_config_value("https://example.com/vault").is_ok());
+
+        for value in ["secret\nurl = \"http://evil\"", "secret\r", "a\0b"] {
+            assert!(check_config_value(value).is_err(), "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use zeroize::Zeroize;
+
+use crate::{container::ContainerKey, TypeContent, VaultError};
+
+/// A sealed category keeps its subcategories in this single entry
+/// as a base64 container encrypted with the password of the category
+pub const SEALED_SUB_CATEGORY: &str = ".sealed";
+
+/// Locked sealed category. Opened ones hold the plain subcategories in memory
+pub fn is_sealed(sub_categories: &BTreeMap<String, String>) -> bool {
+    sub_categories.len() == 1 && sub_categories.contains_key(SEALED_SUB_CATEGORY)
+}
+
+/// Only the single entry of a locked sealed category may have the reserved name
+pub(crate) fn check_sub_category_name(name: &str) -> Result<(), VaultError> {
+    if name == SEALED_SUB_CATEGORY {
+        return Err(VaultError::InvalidData(format!(
+            "{} is a reserved subcategory name",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn check_content(content: &TypeContent) -> Result<(), VaultError> {
+    for sub_categories in content.values() {
+        if !is_sealed(sub_categories) {
+            for name in sub_categories.keys() {
+                check_sub_category_name(name)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn seal(
+    key: &ContainerKey,
+    sub_categories: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, VaultError> {
+    let mut json = serde_json::to_vec(sub_categories).unwrap();
+    let sealed = key.seal(&json);
+    json.zeroize();
+
+    let text = base64::engine::general_purpose::STANDARD.encode(sealed?);
+    Ok([(SEALED_SUB_CATEGORY.to_string(), text)].into())
+}
+
+pub fn open(
+    password: &str,
+    sub_categories: &BTreeMap<String, String>,
+) -> Result<(ContainerKey, BTreeMap<String, String>), VaultError> {
+    let sealed = decode(sub_categories)?;
+    let (key, json) = ContainerKey::open(password, &sealed)?;
+
+    Ok((key, deserialize(json)?))
+}
+
+/// Opens another copy of the category sealed with the same key, e.g. from a synced version of the vault
+pub fn open_with_key(
+    key: &ContainerKey,
+    sub_categories: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, VaultError> {
+    let sealed = decode(sub_categories)?;
+    deserialize(key.open_sealed(&sealed)?)
+}
+
+fn decode(sub_categories: &BTreeMap<String, String>) -> Result<Vec<u8>, VaultError> {
+    let sealed = sub_categories
+        .get(SEALED_SUB_CATEGORY)
+        .ok_or_else(|| VaultError::InvalidData("Category is not sealed".to_string()))?;
+
+    base64::engine::general_purpose::STANDARD
+        .decode(sealed.trim())
+        .map_err(|err| VaultError::InvalidData(err.to_string()))
+}
+
+fn deserialize(mut json: Vec<u8>) -> Result<BTreeMap<String, String>, VaultError> {
+    let result =
+        serde_json::from_slice(&json).map_err(|err| VaultError::InvalidData(err.to_string()));
+    json.zeroize();
+
+    result
+}
@@ -0,0 +1,213 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+use encryption::aes::AesKey;
+use zeroize::Zeroize;
+
+use crate::{
+    secure_memory::{lock_memory, unlock_memory, warn_not_locked},
+    VaultError,
+};
+
+pub type TypeContent = BTreeMap<String, BTreeMap<String, String>>;
+
+/// AesKey which is wiped from memory as soon as it is dropped
+pub struct SecretKey {
+    aes_key: Box<AesKey>,
+    locked: bool,
+}
+
+impl SecretKey {
+    /// Takes the key over wiping the source
+    pub fn new(aes_key: &mut AesKey) -> Self {
+        let mut result = Self::allocate();
+
+        if let Err(err) = result.lock() {
+            warn_not_locked(&err);
+        }
+
+        result.take(aes_key);
+        result
+    }
+
+    /// Fails if the key can not be kept out of swap, e.g. for the unlock agent keeping it for hours.
+    /// The source is wiped either way
+    pub fn new_locked(aes_key: &mut AesKey) -> Result<Self, VaultError> {
+        let mut result = Self::allocate();
+        let lock_result = result.lock();
+        result.take(aes_key);
+
+        lock_result?;
+        Ok(result)
+    }
+
+    /// Locked before the key is copied in, so it never is in an unlocked buffer
+    fn allocate() -> Self {
+        Self {
+            aes_key: Box::new(AesKey {
+                key: [0; 32],
+                iv: [0; 16],
+            }),
+            locked: false,
+        }
+    }
+
+    fn lock(&mut self) -> std::io::Result<()> {
+        let (ptr, len) = self.memory_region();
+        lock_memory(ptr, len)?;
+        self.locked = true;
+        Ok(())
+    }
+
+    fn take(&mut self, aes_key: &mut AesKey) {
+        self.aes_key.key.copy_from_slice(&aes_key.key);
+        self.aes_key.iv.copy_from_slice(&aes_key.iv);
+        aes_key.key.zeroize();
+        aes_key.iv.zeroize();
+    }
+
+    fn memory_region(&self) -> (*const u8, usize) {
+        let aes_key: &AesKey = &self.aes_key;
+        (
+            aes_key as *const AesKey as *const u8,
+            std::mem::size_of::<AesKey>(),
+        )
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = AesKey;
+
+    fn deref(&self) -> &Self::Target {
+        &self.aes_key
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.aes_key.key.zeroize();
+        self.aes_key.iv.zeroize();
+
+        if self.locked {
+            let (ptr, len) = self.memory_region();
+            unlock_memory(ptr, len);
+        }
+    }
+}
+
+/// Decrypted content which is wiped from memory as soon as it is dropped or replaced.
+/// Changes go through [`SecretContent::update`], so new buffers are locked as well
+/// and the ones of the replaced texts are unlocked
+pub struct SecretContent {
+    content: TypeContent,
+    /// Buffers locked by the last [`SecretContent::lock`] as address and length
+    locked: Vec<(usize, usize)>,
+}
+
+impl SecretContent {
+    pub fn new(content: TypeContent) -> Self {
+        let mut result = Self {
+            content,
+            locked: Vec::new(),
+        };
+        result.lock();
+        result
+    }
+
+    pub fn replace(&mut self, content: TypeContent) {
+        wipe_content(std::mem::replace(&mut self.content, content));
+        self.relock();
+    }
+
+    /// The change has to wipe the texts it replaces or removes, see [`crate::VaultCommand::apply`]
+    pub fn update<R>(&mut self, change: impl FnOnce(&mut TypeContent) -> R) -> R {
+        let result = change(&mut self.content);
+        self.relock();
+        result
+    }
+
+    fn lock(&mut self) {
+        let mut error = None;
+
+        for (category, sub_categories) in &self.content {
+            let buffers = sub_categories
+                .iter()
+                .flat_map(|(sub_category, text)| [sub_category, text]);
+
+            for buffer in std::iter::once(category).chain(buffers) {
+                match lock_memory(buffer.as_ptr(), buffer.capacity()) {
+                    Ok(()) => self
+                        .locked
+                        .push((buffer.as_ptr() as usize, buffer.capacity())),
+                    Err(err) => error = Some(err),
+                }
+            }
+        }
+
+        if let Some(err) = error {
+            warn_not_locked(&err);
+        }
+    }
+
+    /// Buffers still there are locked again before the previous locks are released,
+    /// so pages they share with the freed ones stay locked
+    fn relock(&mut self) {
+        let previous = std::mem::take(&mut self.locked);
+        self.lock();
+        unlock_buffers(previous);
+    }
+}
+
+impl Deref for SecretContent {
+    type Target = TypeContent;
+
+    fn deref(&self) -> &Self::Target {
+        &self.content
+    }
+}
+
+impl Drop for SecretContent {
+    fn drop(&mut self) {
+        wipe_content(std::mem::take(&mut self.content));
+        unlock_buffers(std::mem::take(&mut self.locked));
+    }
+}
+
+fn unlock_buffers(buffers: Vec<(usize, usize)>) {
+    for (ptr, len) in buffers {
+        unlock_memory(ptr as *const u8, len);
+    }
+}
+
+pub fn wipe_content(content: TypeContent) {
+    for (mut category, sub_categories) in content {
+        category.zeroize();
+        wipe_sub_categories(sub_categories);
+    }
+}
+
+pub fn wipe_sub_categories(sub_categories: BTreeMap<String, String>) {
+    for (mut sub_category, mut text) in sub_categories {
+        sub_category.zeroize();
+        text.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_key_over_wiping_source() {
+        let mut aes_key = AesKey {
+            key: [7; 32],
+            iv: [9; 16],
+        };
+
+        let secret_key = SecretKey::new(&mut aes_key);
+
+        assert_eq!(secret_key.key, [7; 32]);
+        assert_eq!(secret_key.iv, [9; 16]);
+        assert_eq!(aes_key.key, [0; 32]);
+        assert_eq!(aes_key.iv, [0; 16]);
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(all(unix, feature = "mlock"))]
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// How many locked buffers every locked page has. Buffers share pages, so a page is unlocked
+/// only together with the last buffer on it
+#[cfg(all(unix, feature = "mlock"))]
+static LOCKED_PAGES: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+/// Asks the OS to keep the pages of the buffer out of swap until [`unlock_memory`] is called for it.
+/// Fails if the OS refuses, e.g. over RLIMIT_MEMLOCK. Without the `mlock` feature it is a no-op
+pub fn lock_memory(ptr: *const u8, len: usize) -> std::io::Result<()> {
+    #[cfg(all(unix, feature = "mlock"))]
+    if len > 0 {
+        let mut locked_pages = LOCKED_PAGES.lock().unwrap();
+        let pages = get_pages(ptr, len);
+
+        for (index, page) in pages.clone().enumerate() {
+            if !locked_pages.contains_key(&page) {
+                let result = unsafe { libc::mlock(page as *const libc::c_void, get_page_size()) };
+
+                if result != 0 {
+                    let err = std::io::Error::last_os_error();
+
+                    for page in pages.take(index) {
+                        release_page(&mut locked_pages, page);
+                    }
+
+                    return Err(err);
+                }
+            }
+
+            *locked_pages.entry(page).or_insert(0) += 1;
+        }
+    }
+
+    #[cfg(not(all(unix, feature = "mlock")))]
+    let _ = (ptr, len);
+
+    Ok(())
+}
+
+/// Pairs [`lock_memory`] which succeeded for the same buffer. Called once the buffer is wiped
+pub fn unlock_memory(ptr: *const u8, len: usize) {
+    #[cfg(all(unix, feature = "mlock"))]
+    if len > 0 {
+        let mut locked_pages = LOCKED_PAGES.lock().unwrap();
+
+        for page in get_pages(ptr, len) {
+            release_page(&mut locked_pages, page);
+        }
+    }
+
+    #[cfg(not(all(unix, feature = "mlock")))]
+    let _ = (ptr, len);
+}
+
+/// The secrets stay usable when locking fails, they may only get to swap
+pub fn warn_not_locked(err: &std::io::Error) {
+    eprintln!(
+        "Warning: can not lock the memory with secrets, they may be swapped to disk. Err: {}",
+        err
+    );
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn release_page(locked_pages: &mut BTreeMap<usize, usize>, page: usize) {
+    match locked_pages.get_mut(&page) {
+        Some(count) if *count > 1 => *count -= 1,
+        Some(_) => {
+            locked_pages.remove(&page);
+            unsafe {
+                libc::munlock(page as *const libc::c_void, get_page_size());
+            }
+        }
+        None => {}
+    }
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn get_pages(ptr: *const u8, len: usize) -> std::iter::StepBy<std::ops::Range<usize>> {
+    let page_size = get_page_size();
+    let start = ptr as usize / page_size * page_size;
+    (start..ptr as usize + len).step_by(page_size)
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn get_page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+#[cfg(all(test, unix, feature = "mlock"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlocks_page_with_last_buffer() {
+        // A page of its own, so buffers of other tests are not on it
+        let layout = std::alloc::Layout::from_size_align(get_page_size(), get_page_size()).unwrap();
+        let page = unsafe { std::alloc::alloc_zeroed(layout) };
+        let second = unsafe { page.add(64) };
+
+        lock_memory(page, 64).unwrap();
+        lock_memory(second, 64).unwrap();
+
+        unlock_memory(page, 64);
+        assert!(LOCKED_PAGES.lock().unwrap().contains_key(&(page as usize)));
+
+        unlock_memory(second, 64);
+        assert!(!LOCKED_PAGES.lock().unwrap().contains_key(&(page as usize)));
+
+        unsafe { std::alloc::dealloc(page, layout) };
+    }
+}
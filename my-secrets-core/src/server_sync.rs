@@ -0,0 +1,215 @@
+use std::fs;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file,
+    git_sync::PullResult,
+    http::{self, Response},
+    storage::{LocalStorage, WriteCondition},
+    sync_server::VAULT_PATH,
+    VaultError,
+};
+
+/// Pulls and pushes the vault file from a `my-secrets serve` server. The last version exchanged
+/// with the server is kept next to the vault file in `<vault>.sync`: its ETag makes the push conditional
+/// and its data is the base for merging when both sides changed
+pub struct ServerSync {
+    pub url: String,
+    token: Option<String>,
+    file_name: String,
+    state_file_name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncedVersion {
+    etag: String,
+    data: String,
+}
+
+impl SyncedVersion {
+    fn new(etag: String, data: &[u8]) -> Self {
+        Self {
+            etag,
+            data: base64::engine::general_purpose::STANDARD.encode(data),
+        }
+    }
+
+    fn decode(&self) -> Result<Vec<u8>, VaultError> {
+        base64::engine::general_purpose::STANDARD
+            .decode(&self.data)
+            .map_err(|err| VaultError::InvalidData(err.to_string()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SyncState {
+    /// Version both sides had after the last pull or push
+    #[serde(default)]
+    synced: Option<SyncedVersion>,
+    /// Version pulled to merge. Becomes the synced one once the merged vault is saved
+    #[serde(default)]
+    fetched: Option<SyncedVersion>,
+}
+
+impl ServerSync {
+    pub fn new(vault_file_name: &str, url: String, token: Option<String>) -> Self {
+        Self {
+            url,
+            token,
+            file_name: vault_file_name.to_string(),
+            state_file_name: format!("{}.sync", vault_file_name),
+        }
+    }
+
+    /// Downloads the vault if it changed on the server and takes it if the local file did not change
+    /// since the last sync. The vault has to be reloaded after [`PullResult::FastForwarded`]
+    pub fn pull(&self) -> Result<PullResult, VaultError> {
+        let mut state = self.read_state()?;
+
+        let mut headers = self.get_auth_headers();
+        if let Some(synced) = &state.synced {
+            headers.push(("If-None-Match", synced.etag.clone()));
+        }
+
+        let response = http::send(&self.get_vault_url(), "GET", &headers, &[])?;
+
+        match response.status {
+            200 => {}
+            304 | 404 => return Ok(PullResult::UpToDate),
+            _ => return Err(to_error(&response)),
+        }
+
+        let etag = get_etag(&response)?;
+        let theirs = response.body;
+
+        let base = match &state.synced {
+            Some(synced) => Some(synced.decode()?),
+            None => None,
+        };
+
+        let local = read_file(&self.file_name)?;
+
+        if local.is_none() || local == base {
+            file::save_data_to_file(
+                &LocalStorage::new(self.file_name.as_str()),
+                &theirs,
+                WriteCondition::Any,
+            )?;
+
+            state.synced = Some(SyncedVersion::new(etag, &theirs));
+            state.fetched = None;
+            self.write_state(&state)?;
+
+            return Ok(PullResult::FastForwarded);
+        }
+
+        if local.as_deref() == Some(theirs.as_slice()) {
+            state.synced = Some(SyncedVersion::new(etag, &theirs));
+            state.fetched = None;
+            self.write_state(&state)?;
+
+            return Ok(PullResult::UpToDate);
+        }
+
+        state.fetched = Some(SyncedVersion::new(etag, &theirs));
+        self.write_state(&state)?;
+
+        Ok(PullResult::Diverged { base, theirs })
+    }
+
+    /// The merged vault is saved, so the pulled version is the one the next push replaces
+    pub fn complete_merge(&self) -> Result<(), VaultError> {
+        let mut state = self.read_state()?;
+
+        if let Some(fetched) = state.fetched.take() {
+            state.synced = Some(fetched);
+            self.write_state(&state)?;
+        }
+
+        Ok(())
+    }
+
+    /// Uploads the vault file. Fails if the server has a version this client did not pull yet
+    pub fn push(&self) -> Result<(), VaultError> {
+        let Some(data) = read_file(&self.file_name)? else {
+            return Ok(());
+        };
+
+        let mut state = self.read_state()?;
+
+        let mut headers = self.get_auth_headers();
+        match &state.synced {
+            Some(synced) => headers.push(("If-Match", synced.etag.clone())),
+            None => headers.push(("If-None-Match", "*".to_string())),
+        }
+
+        let response = http::send(&self.get_vault_url(), "PUT", &headers, &data)?;
+
+        match response.status {
+            200 | 201 => {}
+            412 => {
+                return Err(VaultError::Server(
+                    "The vault on the server changed. Pull the changes first".to_string(),
+                ))
+            }
+            _ => return Err(to_error(&response)),
+        }
+
+        state.synced = Some(SyncedVersion::new(get_etag(&response)?, &data));
+        self.write_state(&state)
+    }
+
+    fn get_vault_url(&self) -> String {
+        format!("{}{}", self.url.trim_end_matches('/'), VAULT_PATH)
+    }
+
+    fn get_auth_headers(&self) -> Vec<(&'static str, String)> {
+        match &self.token {
+            Some(token) => vec![("Authorization", format!("Bearer {}", token))],
+            None => Vec::new(),
+        }
+    }
+
+    fn read_state(&self) -> Result<SyncState, VaultError> {
+        match read_file(&self.state_file_name)? {
+            Some(data) => serde_json::from_slice(&data)
+                .map_err(|err| VaultError::InvalidData(err.to_string())),
+            None => Ok(SyncState::default()),
+        }
+    }
+
+    fn write_state(&self, state: &SyncState) -> Result<(), VaultError> {
+        file::write_private_file(&self.state_file_name, &serde_json::to_vec(state).unwrap())?;
+        Ok(())
+    }
+}
+
+fn read_file(file_name: &str) -> Result<Option<Vec<u8>>, VaultError> {
+    match fs::read(file_name) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn get_etag(response: &Response) -> Result<String, VaultError> {
+    response
+        .get_header("ETag")
+        .map(|etag| etag.to_string())
+        .ok_or_else(|| VaultError::Server("The response has no ETag".to_string()))
+}
+
+fn to_error(response: &Response) -> VaultError {
+    let message = match response.status {
+        401 => "Unauthorized. Check the token in the settings".to_string(),
+        status => format!(
+            "Unexpected response {}. {}",
+            status,
+            String::from_utf8_lossy(&response.body).trim()
+        ),
+    };
+
+    VaultError::Server(message)
+}
@@ -0,0 +1,164 @@
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    secrecy::ExposeSecret,
+    x25519,
+};
+use zeroize::Zeroize;
+
+use crate::{
+    address_book::AddressBook, bundle, fields, import::ImportedEntry, TypeContent, Vault,
+    VaultError,
+};
+
+/// The identity is a regular entry of the vault, so it is saved and backed up with it.
+/// It never leaves the vault through shares, bundles, exports or the local API
+pub const IDENTITY_CATEGORY: &str = "Identity";
+pub const IDENTITY_SUB_CATEGORY: &str = "age";
+
+const PUBLIC_KEY_FIELD: &str = "public_key";
+const SECRET_KEY_FIELD: &str = "secret_key";
+
+pub const SHARED_EXTENSION: &str = "age";
+
+/// Text of a new identity entry: `public_key: age1...` and `secret_key: AGE-SECRET-KEY-1...`
+pub fn generate_identity_text() -> String {
+    let identity = x25519::Identity::generate();
+
+    format!(
+        "{}: {}\n{}: {}\n",
+        PUBLIC_KEY_FIELD,
+        identity.to_public(),
+        SECRET_KEY_FIELD,
+        identity.to_string().expose_secret()
+    )
+}
+
+/// Fails for the category of the identity
+pub(crate) fn check_not_identity(category: &str) -> Result<(), VaultError> {
+    if category == IDENTITY_CATEGORY {
+        return Err(VaultError::Sharing(format!(
+            "{} category holds the secret key of the vault and can not leave it",
+            IDENTITY_CATEGORY
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn has_identity(content: &TypeContent) -> bool {
+    get_identity_text(content).is_ok()
+}
+
+pub fn get_public_key(content: &TypeContent) -> Result<String, VaultError> {
+    Ok(get_identity(content)?.to_public().to_string())
+}
+
+/// Encrypts the entries to the recipients. Returns ASCII armored age file.
+/// Recipients are names from the address book or `age1...` public keys
+pub fn share_entries(
+    vault: &Vault,
+    paths: &[String],
+    recipients: &[String],
+    address_book: &AddressBook,
+) -> Result<String, VaultError> {
+    let recipients = recipients
+        .iter()
+        .map(|recipient| parse_recipient(address_book.resolve(recipient)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if recipients.is_empty() {
+        return Err(VaultError::Sharing(
+            "At least one recipient is required".to_string(),
+        ));
+    }
+
+    let mut json = bundle::serialize_entries(vault, paths)?;
+    let result = encrypt_to_recipients(&recipients, &json);
+    json.zeroize();
+
+    result
+}
+
+/// Decrypts entries shared to the identity of the vault
+pub fn read_shared(content: &TypeContent, data: &[u8]) -> Result<Vec<ImportedEntry>, VaultError> {
+    let identity = get_identity(content)?;
+
+    let mut json = decrypt_with_identity(&identity, data)?;
+    let result = bundle::deserialize_entries(&json);
+    json.zeroize();
+
+    result
+}
+
+/// ASCII armored age file readable by any of the recipients
+pub(crate) fn encrypt_to_recipients(
+    recipients: &[x25519::Recipient],
+    data: &[u8],
+) -> Result<String, VaultError> {
+    let encryptor = age::Encryptor::with_recipients(
+        recipients
+            .iter()
+            .map(|recipient| recipient as &dyn age::Recipient),
+    )
+    .map_err(|err| VaultError::Sharing(err.to_string()))?;
+
+    let mut result = Vec::new();
+    let armored = ArmoredWriter::wrap_output(&mut result, Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armored)?;
+    writer.write_all(data)?;
+    writer.finish()?.finish()?;
+
+    Ok(String::from_utf8(result).unwrap())
+}
+
+pub(crate) fn decrypt_with_identity(
+    identity: &x25519::Identity,
+    data: &[u8],
+) -> Result<Vec<u8>, VaultError> {
+    let decryptor = age::Decryptor::new(ArmoredReader::new(data))
+        .map_err(|err| VaultError::Sharing(err.to_string()))?;
+
+    let mut reader = decryptor
+        .decrypt(std::iter::once(identity as &dyn age::Identity))
+        .map_err(|err| VaultError::Sharing(err.to_string()))?;
+
+    let mut result = Vec::new();
+    if let Err(err) = reader.read_to_end(&mut result) {
+        result.zeroize();
+        return Err(err.into());
+    }
+
+    Ok(result)
+}
+
+pub fn parse_recipient(public_key: &str) -> Result<x25519::Recipient, VaultError> {
+    x25519::Recipient::from_str(public_key.trim())
+        .map_err(|err| VaultError::Sharing(format!("Invalid public key {}: {}", public_key, err)))
+}
+
+fn get_identity(content: &TypeContent) -> Result<x25519::Identity, VaultError> {
+    let secret_key =
+        fields::get_field(get_identity_text(content)?, SECRET_KEY_FIELD).ok_or_else(|| {
+            VaultError::FieldNotFound {
+                category: IDENTITY_CATEGORY.to_string(),
+                sub_category: IDENTITY_SUB_CATEGORY.to_string(),
+                field: SECRET_KEY_FIELD.to_string(),
+            }
+        })?;
+
+    x25519::Identity::from_str(secret_key)
+        .map_err(|err| VaultError::Sharing(format!("Invalid identity: {}", err)))
+}
+
+fn get_identity_text(content: &TypeContent) -> Result<&str, VaultError> {
+    content
+        .get(IDENTITY_CATEGORY)
+        .and_then(|sub_categories| sub_categories.get(IDENTITY_SUB_CATEGORY))
+        .map(|text| text.as_str())
+        .ok_or_else(|| VaultError::sub_category_not_found(IDENTITY_CATEGORY, IDENTITY_SUB_CATEGORY))
+}
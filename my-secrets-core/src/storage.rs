@@ -0,0 +1,377 @@
+use std::{fs, time::UNIX_EPOCH};
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+use serde::{Deserialize, Serialize};
+
+use crate::VaultError;
+
+/// The vault file as it is in the storage
+pub struct StoredFile {
+    pub data: Vec<u8>,
+    /// Changes with every write. None for a file saved only to the offline cache
+    /// while the storage had no file yet
+    pub version: Option<String>,
+    /// Offline changes are based on this file of the storage. None when the data is the stored file itself
+    pub base: Option<Vec<u8>>,
+}
+
+pub enum WriteCondition<'s> {
+    /// Replace whatever is stored
+    Any,
+    /// There is no file yet
+    Missing,
+    /// The file still has this version
+    Version(&'s str),
+}
+
+impl<'s> WriteCondition<'s> {
+    pub fn from_version(version: Option<&'s str>) -> Self {
+        match version {
+            Some(version) => WriteCondition::Version(version),
+            None => WriteCondition::Missing,
+        }
+    }
+
+    /// Whether the file of this version may be replaced
+    pub fn holds(&self, version: Option<&str>) -> bool {
+        match self {
+            WriteCondition::Any => true,
+            WriteCondition::Missing => version.is_none(),
+            WriteCondition::Version(expected) => version == Some(*expected),
+        }
+    }
+}
+
+pub enum Written {
+    /// The storage has the file with this version now
+    Stored(String),
+    /// The storage is not reachable. The file waits in the offline cache, the storage still has the previous version
+    Cached,
+}
+
+/// Where the vault file and its backups are kept. Writes are conditional on the version read before,
+/// so a file changed meanwhile on another machine is not overwritten
+pub trait Storage {
+    /// None if there is no file yet
+    fn read(&self) -> Result<Option<StoredFile>, VaultError>;
+
+    /// The file to open. Differs from [`Storage::read`] when there are offline changes
+    fn load(&self) -> Result<Option<StoredFile>, VaultError> {
+        self.read()
+    }
+
+    /// Version of the current file without reading it
+    fn get_version(&self) -> Result<Option<String>, VaultError>;
+
+    /// Fails with [`VaultError::FileChanged`] if the condition does not hold
+    fn write(&self, data: &[u8], condition: WriteCondition) -> Result<Written, VaultError>;
+
+    /// Keeps a copy of the current file among the backups
+    fn backup(&self) -> Result<(), VaultError>;
+
+    /// Path or URL of the file for messages
+    fn get_location(&self) -> String;
+}
+
+/// Vault file on the local disk. Backups go to iCloud Drive
+pub struct LocalStorage {
+    file_name: String,
+}
+
+impl LocalStorage {
+    pub fn new(file_name: impl Into<String>) -> Self {
+        Self {
+            file_name: file_name.into(),
+        }
+    }
+}
+
+impl Storage for LocalStorage {
+    fn read(&self) -> Result<Option<StoredFile>, VaultError> {
+        let version = self.get_version()?;
+
+        match fs::read(&self.file_name) {
+            Ok(data) => Ok(Some(StoredFile {
+                data,
+                version,
+                base: None,
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Modification time and size. A file touched without changes gets a new version,
+    /// the vault compares the data before treating it as changed
+    fn get_version(&self) -> Result<Option<String>, VaultError> {
+        let metadata = match fs::metadata(&self.file_name) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_nanos())
+            .unwrap_or_default();
+
+        Ok(Some(format!("{}-{}", modified, metadata.len())))
+    }
+
+    fn write(&self, data: &[u8], condition: WriteCondition) -> Result<Written, VaultError> {
+        if !condition.holds(self.get_version()?.as_deref()) {
+            return Err(VaultError::FileChanged);
+        }
+
+        crate::file::write_private_file(&self.file_name, data)?;
+
+        Ok(Written::Stored(self.get_version()?.unwrap_or_default()))
+    }
+
+    fn backup(&self) -> Result<(), VaultError> {
+        let Some(content) = read_file(&self.file_name)? else {
+            return Ok(());
+        };
+
+        let mut icloud_docs_path = format!(
+            "{}/Library/Mobile Documents/com~apple~CloudDocs/backups/my-secrets",
+            std::env::var("HOME").unwrap()
+        );
+
+        fs::create_dir_all(icloud_docs_path.as_str())?;
+
+        icloud_docs_path.push('/');
+        icloud_docs_path.push_str(&get_backup_name());
+
+        crate::file::write_private_file(&icloud_docs_path, &content)?;
+        Ok(())
+    }
+
+    fn get_location(&self) -> String {
+        self.file_name.clone()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CacheState {
+    /// Version of the stored file the cache has or is based on
+    #[serde(default)]
+    version: Option<String>,
+    /// The cache has changes saved while the storage was not reachable
+    #[serde(default)]
+    pending: bool,
+}
+
+/// Remote storage with the local vault file as the encrypted cache. While the storage is not reachable
+/// the vault opens from the cache and saves go to it. They are uploaded on the next save or open,
+/// and merged like any other change if the stored file changed meanwhile
+pub struct CachedStorage {
+    remote: Box<dyn Storage>,
+    cache: LocalStorage,
+    state_file_name: String,
+    base_file_name: String,
+}
+
+impl CachedStorage {
+    pub fn new(remote: Box<dyn Storage>, cache_file_name: &str) -> Self {
+        Self {
+            remote,
+            cache: LocalStorage::new(cache_file_name),
+            state_file_name: format!("{}.cache", cache_file_name),
+            base_file_name: format!("{}.base", cache_file_name),
+        }
+    }
+
+    /// The cache with the offline changes if there are any
+    fn read_cache(&self, state: &CacheState) -> Result<Option<StoredFile>, VaultError> {
+        let Some(cached) = self.cache.read()? else {
+            return Ok(None);
+        };
+
+        let base = if state.pending {
+            read_file(&self.base_file_name)?
+        } else {
+            None
+        };
+
+        Ok(Some(StoredFile {
+            data: cached.data,
+            version: state.version.clone(),
+            base,
+        }))
+    }
+
+    /// The cache has the stored file of this version now
+    fn update_cache(&self, data: &[u8], version: Option<String>) -> Result<(), VaultError> {
+        self.cache.write(data, WriteCondition::Any)?;
+        self.write_state(&CacheState {
+            version,
+            pending: false,
+        })?;
+
+        match fs::remove_file(&self.base_file_name) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn read_state(&self) -> Result<CacheState, VaultError> {
+        match read_file(&self.state_file_name)? {
+            Some(data) => serde_json::from_slice(&data)
+                .map_err(|err| VaultError::InvalidData(err.to_string())),
+            None => Ok(CacheState::default()),
+        }
+    }
+
+    fn write_state(&self, state: &CacheState) -> Result<(), VaultError> {
+        crate::file::write_private_file(
+            &self.state_file_name,
+            &serde_json::to_vec(state).unwrap(),
+        )?;
+        Ok(())
+    }
+}
+
+impl Storage for CachedStorage {
+    /// The stored file. Offline it is the cached one, which offline changes are based on
+    fn read(&self) -> Result<Option<StoredFile>, VaultError> {
+        let state = self.read_state()?;
+
+        match self.remote.read() {
+            Ok(Some(stored)) => {
+                if !state.pending {
+                    self.update_cache(&stored.data, stored.version.clone())?;
+                }
+
+                Ok(Some(stored))
+            }
+            Ok(None) => Ok(None),
+            Err(VaultError::Offline(err)) => {
+                eprintln!("{}. Using the cached vault", err);
+
+                if state.pending {
+                    return Ok(read_file(&self.base_file_name)?.map(|data| StoredFile {
+                        data,
+                        version: state.version,
+                        base: None,
+                    }));
+                }
+
+                match self.cache.read()? {
+                    Some(cached) => Ok(Some(StoredFile {
+                        version: state.version,
+                        ..cached
+                    })),
+                    None => Err(VaultError::Offline(err)),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Uploads the offline changes if the stored file did not change since they were made
+    fn load(&self) -> Result<Option<StoredFile>, VaultError> {
+        let state = self.read_state()?;
+
+        if !state.pending {
+            return self.read();
+        }
+
+        let stored_version = match self.remote.get_version() {
+            Ok(version) => version,
+            Err(VaultError::Offline(err)) => {
+                eprintln!("{}. Using the cached vault", err);
+                return self.read_cache(&state);
+            }
+            Err(err) => return Err(err),
+        };
+
+        if stored_version != state.version {
+            // Changed on both sides. Saving merges them
+            return self.read_cache(&state);
+        }
+
+        let Some(cached) = self.cache.read()? else {
+            return self.read();
+        };
+
+        let condition = WriteCondition::from_version(state.version.as_deref());
+
+        match self.remote.write(&cached.data, condition)? {
+            Written::Stored(version) => {
+                self.update_cache(&cached.data, Some(version.clone()))?;
+
+                Ok(Some(StoredFile {
+                    data: cached.data,
+                    version: Some(version),
+                    base: None,
+                }))
+            }
+            Written::Cached => self.read_cache(&state),
+        }
+    }
+
+    fn get_version(&self) -> Result<Option<String>, VaultError> {
+        match self.remote.get_version() {
+            Err(VaultError::Offline(_)) => Ok(self.read_state()?.version),
+            result => result,
+        }
+    }
+
+    fn write(&self, data: &[u8], condition: WriteCondition) -> Result<Written, VaultError> {
+        match self.remote.write(data, condition) {
+            Ok(Written::Stored(version)) => {
+                self.update_cache(data, Some(version.clone()))?;
+                Ok(Written::Stored(version))
+            }
+            Ok(Written::Cached) => Ok(Written::Cached),
+            Err(VaultError::Offline(err)) => {
+                eprintln!("{}. Saved to the cache until it is reachable", err);
+
+                let mut state = self.read_state()?;
+
+                if !state.pending {
+                    // The cache is the stored file the offline changes are based on
+                    if let Some(cached) = self.cache.read()? {
+                        crate::file::write_private_file(&self.base_file_name, &cached.data)?;
+                    }
+
+                    state.pending = true;
+                }
+
+                self.cache.write(data, WriteCondition::Any)?;
+                self.write_state(&state)?;
+
+                Ok(Written::Cached)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn backup(&self) -> Result<(), VaultError> {
+        match self.remote.backup() {
+            Err(VaultError::Offline(_)) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn get_location(&self) -> String {
+        self.remote.get_location()
+    }
+}
+
+/// Backup file name: the current time
+pub fn get_backup_name() -> String {
+    DateTimeAsMicroseconds::now().to_rfc3339().replace(":", "-")[..19].to_string()
+}
+
+fn read_file(file_name: &str) -> Result<Option<Vec<u8>>, VaultError> {
+    match fs::read(file_name) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
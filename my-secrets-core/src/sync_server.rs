@@ -0,0 +1,370 @@
+use std::{
+    fs,
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    http::{self, Request, Response},
+    VaultError,
+};
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:7878";
+pub const VAULT_PATH: &str = "/vault";
+
+/// Keeps the encrypted vault file for `my-secrets serve`. The server never has the password,
+/// it only stores the blob. Every version has an ETag: a write replaces the file only if the client
+/// sends the ETag of the version it has, so two machines can not overwrite each other's changes
+pub struct SyncServer {
+    file_name: String,
+    token: Option<String>,
+    /// A conditional write never interleaves with another one
+    write_lock: Mutex<()>,
+}
+
+impl SyncServer {
+    /// Requests have to send `Authorization: Bearer <token>` if the token is set
+    pub fn new(file_name: String, token: Option<String>) -> Self {
+        Self {
+            file_name,
+            token,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Serves every connection on its own thread, so a slow client does not block the others
+    pub fn serve(self, listen: &str) -> Result<(), VaultError> {
+        if let Some(dir) = Path::new(&self.file_name).parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        Arc::new(self).accept(TcpListener::bind(listen)?);
+        Ok(())
+    }
+
+    fn accept(self: &Arc<Self>, listener: TcpListener) {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Can not handle the connection. Err: {}", err);
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+
+            std::thread::spawn(move || {
+                if let Err(err) = server.handle_connection(stream) {
+                    eprintln!("Can not handle the connection. Err: {}", err);
+                }
+            });
+        }
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream) -> Result<(), VaultError> {
+        stream.set_read_timeout(Some(http::TIMEOUT))?;
+        stream.set_write_timeout(Some(http::TIMEOUT))?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let response = match http::read_request(&mut reader) {
+            Ok(request) => {
+                let response = self.handle_request(&request);
+                eprintln!("{} {} {}", request.method, request.path, response.status);
+                response
+            }
+            Err(err) => Response::new(400).with_body(err.to_string().into_bytes()),
+        };
+
+        http::write_response(&mut stream, &response)?;
+        Ok(())
+    }
+
+    fn handle_request(&self, request: &Request) -> Response {
+        if !self.is_authorized(request) {
+            return Response::new(401);
+        }
+
+        if request.path != VAULT_PATH {
+            return Response::new(404);
+        }
+
+        let result = match request.method.as_str() {
+            "GET" => self.get_vault(request),
+            "PUT" => self.put_vault(request),
+            _ => Ok(Response::new(405).with_header("Allow", "GET, PUT")),
+        };
+
+        result.unwrap_or_else(|err| Response::new(500).with_body(err.to_string().into_bytes()))
+    }
+
+    fn get_vault(&self, request: &Request) -> Result<Response, VaultError> {
+        let Some(data) = self.read_vault()? else {
+            return Ok(Response::new(404));
+        };
+
+        let etag = get_etag(&data);
+
+        if request.get_header("If-None-Match") == Some(etag.as_str()) {
+            return Ok(Response::new(304).with_header("ETag", &etag));
+        }
+
+        Ok(Response::new(200)
+            .with_header("ETag", &etag)
+            .with_body(data))
+    }
+
+    /// Needs `If-Match` with the ETag of the current version, or `If-None-Match: *` for the first upload
+    fn put_vault(&self, request: &Request) -> Result<Response, VaultError> {
+        let _write_lock = self.write_lock.lock().unwrap();
+
+        let current_etag = self.read_vault()?.map(|data| get_etag(&data));
+
+        let matches = match (
+            request.get_header("If-Match"),
+            request.get_header("If-None-Match"),
+        ) {
+            (Some(etag), _) => current_etag.as_deref() == Some(etag),
+            (None, Some("*")) => current_etag.is_none(),
+            _ => return Ok(Response::new(428)),
+        };
+
+        if !matches {
+            let response = Response::new(412);
+
+            return Ok(match &current_etag {
+                Some(etag) => response.with_header("ETag", etag),
+                None => response,
+            });
+        }
+
+        // Readers never see a half written file
+        let temp_file_name = format!("{}.tmp", self.file_name);
+        crate::file::write_private_file(&temp_file_name, &request.body)?;
+        fs::rename(&temp_file_name, &self.file_name)?;
+
+        let status = if current_etag.is_some() { 200 } else { 201 };
+
+        Ok(Response::new(status).with_header("ETag", &get_etag(&request.body)))
+    }
+
+    fn read_vault(&self) -> Result<Option<Vec<u8>>, VaultError> {
+        match fs::read(&self.file_name) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+
+        match request
+            .get_header("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+        {
+            Some(sent) => is_same_token(sent.as_bytes(), token.as_bytes()),
+            None => false,
+        }
+    }
+}
+
+/// SHA-256 of the content, so another version can not have the same ETag
+fn get_etag(data: &[u8]) -> String {
+    let hash: String = Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("\"{}\"", hash)
+}
+
+/// Takes the same time wherever the tokens differ
+fn is_same_token(sent: &[u8], token: &[u8]) -> bool {
+    sent.len() == token.len()
+        && sent
+            .iter()
+            .zip(token)
+            .fold(0u8, |result, (left, right)| result | (left ^ right))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{git_sync::PullResult, server_sync::ServerSync};
+
+    struct Client {
+        server_sync: ServerSync,
+        file_name: String,
+    }
+
+    impl Client {
+        fn new(dir: &tempfile::TempDir, name: &str, url: &str, token: Option<&str>) -> Self {
+            let file_name = dir.path().join(name).to_string_lossy().to_string();
+
+            Self {
+                server_sync: ServerSync::new(
+                    &file_name,
+                    url.to_string(),
+                    token.map(|token| token.to_string()),
+                ),
+                file_name,
+            }
+        }
+
+        fn write(&self, data: &str) {
+            fs::write(&self.file_name, data).unwrap();
+        }
+
+        fn read(&self) -> String {
+            fs::read_to_string(&self.file_name).unwrap()
+        }
+    }
+
+    /// Serves on a free loopback port. Returns the URL of the server
+    fn start_server(dir: &tempfile::TempDir, token: Option<&str>) -> String {
+        let server = SyncServer::new(
+            dir.path()
+                .join("server/vault")
+                .to_string_lossy()
+                .to_string(),
+            token.map(|token| token.to_string()),
+        );
+        fs::create_dir(dir.path().join("server")).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || Arc::new(server).accept(listener));
+
+        url
+    }
+
+    #[test]
+    fn pulls_pushed_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = start_server(&dir, None);
+        let a = Client::new(&dir, "a", &url, None);
+        let b = Client::new(&dir, "b", &url, None);
+
+        assert!(matches!(
+            b.server_sync.pull().unwrap(),
+            PullResult::UpToDate
+        ));
+
+        a.write("v1");
+        a.server_sync.push().unwrap();
+
+        assert!(matches!(
+            b.server_sync.pull().unwrap(),
+            PullResult::FastForwarded
+        ));
+        assert_eq!(b.read(), "v1");
+        assert!(matches!(
+            b.server_sync.pull().unwrap(),
+            PullResult::UpToDate
+        ));
+    }
+
+    #[test]
+    fn rejects_push_over_not_pulled_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = start_server(&dir, None);
+        let a = Client::new(&dir, "a", &url, None);
+        let b = Client::new(&dir, "b", &url, None);
+
+        a.write("v1");
+        a.server_sync.push().unwrap();
+        b.server_sync.pull().unwrap();
+
+        a.write("v2 a");
+        a.server_sync.push().unwrap();
+
+        b.write("v2 b");
+        assert!(matches!(b.server_sync.push(), Err(VaultError::Server(_))));
+
+        match b.server_sync.pull().unwrap() {
+            PullResult::Diverged { base, theirs } => {
+                assert_eq!(base.as_deref(), Some(b"v1".as_slice()));
+                assert_eq!(theirs, b"v2 a");
+            }
+            _ => panic!("Changes of both sides are not detected"),
+        }
+
+        b.write("v3 merged");
+        b.server_sync.complete_merge().unwrap();
+        b.server_sync.push().unwrap();
+
+        // Pulling over a changed file would back it up to the home directory
+        let c = Client::new(&dir, "c", &url, None);
+        assert!(matches!(
+            c.server_sync.pull().unwrap(),
+            PullResult::FastForwarded
+        ));
+        assert_eq!(c.read(), "v3 merged");
+    }
+
+    #[test]
+    fn serves_while_another_client_is_idle() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = start_server(&dir, None);
+        let _idle = TcpStream::connect(url.trim_start_matches("http://")).unwrap();
+
+        let a = Client::new(&dir, "a", &url, None);
+        a.write("v1");
+
+        let started = std::time::Instant::now();
+        a.server_sync.push().unwrap();
+        assert!(started.elapsed() < http::TIMEOUT);
+    }
+
+    #[test]
+    fn requires_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = start_server(&dir, Some("token"));
+
+        let client = Client::new(&dir, "a", &url, Some("token"));
+        client.write("v1");
+        client.server_sync.push().unwrap();
+
+        for token in [None, Some("wrong")] {
+            let client = Client::new(&dir, "b", &url, token);
+            assert!(client.server_sync.pull().is_err());
+        }
+    }
+
+    #[test]
+    fn requires_precondition_for_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("{}{}", start_server(&dir, None), VAULT_PATH);
+
+        let response = http::send(&url, "GET", &[], &[]).unwrap();
+        assert_eq!(response.status, 404);
+
+        let response = http::send(&url, "PUT", &[], b"v1").unwrap();
+        assert_eq!(response.status, 428);
+
+        let response =
+            http::send(&url, "PUT", &[("If-None-Match", "*".to_string())], b"v1").unwrap();
+        assert_eq!(response.status, 201);
+        let etag = response.get_header("ETag").unwrap().to_string();
+
+        let response =
+            http::send(&url, "PUT", &[("If-None-Match", "*".to_string())], b"v2").unwrap();
+        assert_eq!(response.status, 412);
+
+        let response = http::send(&url, "GET", &[("If-None-Match", etag.clone())], &[]).unwrap();
+        assert_eq!(response.status, 304);
+
+        let response = http::send(&url, "PUT", &[("If-Match", etag)], b"v2").unwrap();
+        assert_eq!(response.status, 200);
+    }
+}
@@ -0,0 +1,424 @@
+use std::str::FromStr;
+
+use age::{secrecy::ExposeSecret, x25519};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    container::{self, KEY_SIZE, NONCE_SIZE},
+    entry_ids::{self, EntryIds},
+    sharing, TypeContent, VaultError,
+};
+
+const TEAM_FORMAT: &str = "my-secrets-team";
+const TEAM_VERSION: u32 = 1;
+
+/// Pasting an identity instead of a password unlocks as a public key member
+const IDENTITY_PREFIX: &str = "AGE-SECRET-KEY-1";
+
+/// Every member has an X25519 key the data key is wrapped to.
+/// Password members keep the secret part in the file sealed with their password,
+/// so removing a member re-keys the vault without asking the others for their passwords
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamMember {
+    pub name: String,
+    pub public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_identity: Option<String>,
+    wrapped_key: String,
+}
+
+impl TeamMember {
+    pub fn is_password_member(&self) -> bool {
+        self.sealed_identity.is_some()
+    }
+}
+
+pub enum MemberKey<'s> {
+    Password(&'s str),
+    PublicKey(&'s str),
+}
+
+#[derive(Serialize, Deserialize)]
+struct TeamFile {
+    format: String,
+    version: u32,
+    members: Vec<TeamMember>,
+    /// Nonce and XChaCha20-Poly1305 ciphertext of the content
+    content: String,
+}
+
+/// Keys of the unlocked team vault
+pub struct TeamKey {
+    identity: x25519::Identity,
+    data_key: [u8; KEY_SIZE],
+    pub member_name: String,
+    pub members: Vec<TeamMember>,
+}
+
+impl TeamKey {
+    /// New team with the single password member
+    pub fn create(member_name: &str, password: &str) -> Result<Self, VaultError> {
+        let mut data_key = [0u8; KEY_SIZE];
+        container::fill_random(&mut data_key)?;
+
+        let (identity, member) = create_password_member(member_name, password, &data_key)?;
+
+        Ok(Self {
+            identity,
+            data_key,
+            member_name: member_name.to_string(),
+            members: vec![member],
+        })
+    }
+
+    pub fn add_member(&mut self, name: &str, key: MemberKey) -> Result<(), VaultError> {
+        if self.members.iter().any(|member| member.name == name) {
+            return Err(VaultError::Team(format!("Member {} already exists", name)));
+        }
+
+        let member = match key {
+            MemberKey::Password(password) => {
+                create_password_member(name, password, &self.data_key)?.1
+            }
+            MemberKey::PublicKey(public_key) => {
+                let recipient = sharing::parse_recipient(public_key)?;
+                TeamMember {
+                    name: name.to_string(),
+                    public_key: recipient.to_string(),
+                    sealed_identity: None,
+                    wrapped_key: sharing::encrypt_to_recipients(&[recipient], &self.data_key)?,
+                }
+            }
+        };
+
+        self.members.push(member);
+        Ok(())
+    }
+
+    /// Removes the member and wraps a new data key for the rest.
+    /// The removed member may know the old key, so the content has to be saved with the new one
+    pub fn remove_member(&mut self, name: &str) -> Result<(), VaultError> {
+        if name == self.member_name {
+            return Err(VaultError::Team("You can not remove yourself".to_string()));
+        }
+
+        let index = self
+            .members
+            .iter()
+            .position(|member| member.name == name)
+            .ok_or_else(|| VaultError::Team(format!("Member {} not found", name)))?;
+
+        let mut data_key = [0u8; KEY_SIZE];
+        container::fill_random(&mut data_key)?;
+
+        // The members stay as they are if wrapping the key for any of them fails
+        let members = self
+            .members
+            .iter()
+            .enumerate()
+            .filter(|(member_index, _)| *member_index != index)
+            .map(|(_, member)| {
+                let recipient = sharing::parse_recipient(&member.public_key)?;
+                Ok(TeamMember {
+                    wrapped_key: sharing::encrypt_to_recipients(&[recipient], &data_key)?,
+                    ..member.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, VaultError>>();
+
+        let members = match members {
+            Ok(members) => members,
+            Err(err) => {
+                data_key.zeroize();
+                return Err(err);
+            }
+        };
+
+        self.members = members;
+        self.data_key.zeroize();
+        self.data_key = data_key;
+        data_key.zeroize();
+
+        Ok(())
+    }
+
+    /// Content without the members, e.g. for the draft
+    pub fn encrypt_content(
+        &self,
+        content: &TypeContent,
+        ids: &EntryIds,
+    ) -> Result<Vec<u8>, VaultError> {
+        self.seal_content(content, ids, TEAM_FORMAT.as_bytes())
+    }
+
+    pub fn decrypt_content(&self, encrypted: &[u8]) -> Result<(TypeContent, EntryIds), VaultError> {
+        self.open_content(encrypted, TEAM_FORMAT.as_bytes())
+    }
+
+    fn seal_content(
+        &self,
+        content: &TypeContent,
+        ids: &EntryIds,
+        aad: &[u8],
+    ) -> Result<Vec<u8>, VaultError> {
+        let mut json = entry_ids::to_document(content, ids);
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        container::fill_random(&mut nonce)?;
+
+        let encrypted = container::seal_with_key(&self.data_key, &nonce, aad, &json);
+        json.zeroize();
+
+        let mut result = nonce.to_vec();
+        result.extend_from_slice(&encrypted?);
+        Ok(result)
+    }
+
+    fn open_content(
+        &self,
+        encrypted: &[u8],
+        aad: &[u8],
+    ) -> Result<(TypeContent, EntryIds), VaultError> {
+        if encrypted.len() < NONCE_SIZE {
+            return Err(VaultError::InvalidData("Content is too short".to_string()));
+        }
+
+        let (nonce, encrypted) = encrypted.split_at(NONCE_SIZE);
+        let mut json = container::open_with_key(&self.data_key, nonce, aad, encrypted)?;
+
+        let result = entry_ids::from_document(&json);
+        json.zeroize();
+
+        result
+    }
+
+    /// Team file with the members and the encrypted content
+    pub fn to_file_content(
+        &self,
+        content: &TypeContent,
+        ids: &EntryIds,
+    ) -> Result<Vec<u8>, VaultError> {
+        let file = TeamFile {
+            format: TEAM_FORMAT.to_string(),
+            version: TEAM_VERSION,
+            members: self.members.clone(),
+            content: base64::engine::general_purpose::STANDARD.encode(self.seal_content(
+                content,
+                ids,
+                &get_file_aad(&self.members),
+            )?),
+        };
+
+        Ok(serde_json::to_vec_pretty(&file).unwrap())
+    }
+
+    /// Reads the team file again with the identity used to unlock it. Picks up keys changed by other members
+    pub fn reload(&self, data: &[u8]) -> Result<(Self, TypeContent, EntryIds), VaultError> {
+        let file = parse_team_file(data)?;
+        let identity = x25519::Identity::from_str(self.identity.to_string().expose_secret())
+            .map_err(|err| VaultError::Team(err.to_string()))?;
+
+        unlock_with_identity(file, identity)
+    }
+}
+
+impl Drop for TeamKey {
+    fn drop(&mut self) {
+        self.data_key.zeroize();
+    }
+}
+
+pub fn is_team_file(data: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return false;
+    };
+
+    text.trim_start().starts_with('{') && text.contains(TEAM_FORMAT)
+}
+
+/// Unlocks with the password of a password member or with an `AGE-SECRET-KEY-1...` identity
+pub fn unlock(data: &[u8], secret: &str) -> Result<(TeamKey, TypeContent, EntryIds), VaultError> {
+    let file = parse_team_file(data)?;
+
+    if secret.trim().starts_with(IDENTITY_PREFIX) {
+        let identity = x25519::Identity::from_str(secret.trim())
+            .map_err(|err| VaultError::Team(format!("Invalid identity: {}", err)))?;
+
+        return unlock_with_identity(file, identity);
+    }
+
+    for member in &file.members {
+        let Some(sealed_identity) = &member.sealed_identity else {
+            continue;
+        };
+
+        let sealed_identity = base64::engine::general_purpose::STANDARD
+            .decode(sealed_identity)
+            .map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+        let mut identity = match container::open(secret, &sealed_identity) {
+            Ok(identity) => identity,
+            Err(VaultError::InvalidPassword) => continue,
+            Err(err) => return Err(err),
+        };
+
+        let parsed = std::str::from_utf8(&identity)
+            .ok()
+            .and_then(|identity| x25519::Identity::from_str(identity).ok());
+        identity.zeroize();
+
+        let identity =
+            parsed.ok_or_else(|| VaultError::InvalidData("Invalid member identity".to_string()))?;
+
+        return unlock_with_identity(file, identity);
+    }
+
+    Err(VaultError::InvalidPassword)
+}
+
+fn unlock_with_identity(
+    file: TeamFile,
+    identity: x25519::Identity,
+) -> Result<(TeamKey, TypeContent, EntryIds), VaultError> {
+    let public_key = identity.to_public().to_string();
+
+    let member = file
+        .members
+        .iter()
+        .find(|member| member.public_key == public_key)
+        .ok_or(VaultError::InvalidPassword)?;
+
+    let mut data_key = sharing::decrypt_with_identity(&identity, member.wrapped_key.as_bytes())?;
+
+    if data_key.len() != KEY_SIZE {
+        data_key.zeroize();
+        return Err(VaultError::InvalidData("Invalid data key".to_string()));
+    }
+
+    let mut key = TeamKey {
+        identity,
+        data_key: [0u8; KEY_SIZE],
+        member_name: member.name.clone(),
+        members: Vec::new(),
+    };
+    key.data_key.copy_from_slice(&data_key);
+    data_key.zeroize();
+
+    let encrypted = base64::engine::general_purpose::STANDARD
+        .decode(&file.content)
+        .map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+    // Members are authenticated with the content, so nobody can add or swap them without the data key
+    let (content, ids) = key
+        .open_content(&encrypted, &get_file_aad(&file.members))
+        .map_err(|err| match err {
+            VaultError::InvalidPassword => VaultError::InvalidData(
+                "Members or content of the team vault were altered".to_string(),
+            ),
+            err => err,
+        })?;
+    key.members = file.members;
+
+    Ok((key, content, ids))
+}
+
+/// Binds the members to the content of the file
+fn get_file_aad(members: &[TeamMember]) -> Vec<u8> {
+    let mut result = format!("{}/{}/", TEAM_FORMAT, TEAM_VERSION).into_bytes();
+    result.extend_from_slice(&serde_json::to_vec(members).unwrap());
+    result
+}
+
+fn create_password_member(
+    name: &str,
+    password: &str,
+    data_key: &[u8; KEY_SIZE],
+) -> Result<(x25519::Identity, TeamMember), VaultError> {
+    if password.is_empty() {
+        return Err(VaultError::EmptyPassword);
+    }
+
+    let identity = x25519::Identity::generate();
+    let recipient = identity.to_public();
+
+    let sealed_identity =
+        container::seal(password, identity.to_string().expose_secret().as_bytes())?;
+
+    let member = TeamMember {
+        name: name.to_string(),
+        public_key: recipient.to_string(),
+        sealed_identity: Some(base64::engine::general_purpose::STANDARD.encode(sealed_identity)),
+        wrapped_key: sharing::encrypt_to_recipients(&[recipient], data_key)?,
+    };
+
+    Ok((identity, member))
+}
+
+fn parse_team_file(data: &[u8]) -> Result<TeamFile, VaultError> {
+    let file: TeamFile =
+        serde_json::from_slice(data).map_err(|err| VaultError::InvalidData(err.to_string()))?;
+
+    if file.format != TEAM_FORMAT || file.version != TEAM_VERSION {
+        return Err(VaultError::InvalidData(format!(
+            "Unsupported team vault {} version {}",
+            file.format, file.version
+        )));
+    }
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_team() -> TeamKey {
+        let mut key = TeamKey::create("alice", "alice password").unwrap();
+        key.add_member("bob", MemberKey::Password("bob password"))
+            .unwrap();
+        key
+    }
+
+    #[test]
+    fn unlocks_with_member_password() {
+        let key = create_team();
+        let data = key
+            .to_file_content(&TypeContent::new(), &EntryIds::default())
+            .unwrap();
+
+        let (key, _, _) = unlock(&data, "bob password").unwrap();
+
+        assert_eq!(key.member_name, "bob");
+        assert_eq!(key.members.len(), 2);
+    }
+
+    #[test]
+    fn rejects_altered_members() {
+        let key = create_team();
+        let data = key
+            .to_file_content(&TypeContent::new(), &EntryIds::default())
+            .unwrap();
+
+        let mut file = parse_team_file(&data).unwrap();
+        file.members[1].name = "mallory".to_string();
+        let data = serde_json::to_vec(&file).unwrap();
+
+        assert!(matches!(
+            unlock(&data, "alice password"),
+            Err(VaultError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_members_if_rekeying_fails() {
+        let mut key = create_team();
+        key.add_member("carol", MemberKey::Password("carol password"))
+            .unwrap();
+        key.members[1].public_key = "invalid".to_string();
+
+        assert!(key.remove_member("carol").is_err());
+        assert_eq!(key.members.len(), 3);
+    }
+}
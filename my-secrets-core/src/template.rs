@@ -0,0 +1,440 @@
+use std::collections::BTreeSet;
+
+use crate::{Vault, VaultError};
+
+const OPEN_TAG: &str = "{{";
+const CLOSE_TAG: &str = "}}";
+const SECRET_FUNCTION: &str = "secret";
+
+/// Format of the rendered file. Defines how secret values are escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateFormat {
+    Env,
+    Yaml,
+    Json,
+    Toml,
+    Raw,
+}
+
+impl TemplateFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "env" | "dotenv" => Some(Self::Env),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "raw" | "txt" => Some(Self::Raw),
+            _ => None,
+        }
+    }
+
+    /// Detects the format by the template file name: `app.env.tpl`, `values.yaml.tpl`, `.env`
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let file_name = std::path::Path::new(file_name).file_name()?.to_str()?;
+        let file_name = file_name.strip_suffix(".tpl").unwrap_or(file_name);
+
+        if file_name == ".env" {
+            return Some(Self::Env);
+        }
+
+        let (_, extension) = file_name.rsplit_once('.')?;
+        Self::from_name(extension)
+    }
+
+    /// Value as a complete literal of the format, quotes included
+    pub fn escape(&self, value: &str) -> String {
+        match self {
+            Self::Raw => value.to_string(),
+            _ => format!("\"{}\"", self.escape_quoted(value)),
+        }
+    }
+
+    /// Value to put between double quotes the template already has
+    pub fn escape_quoted(&self, value: &str) -> String {
+        match self {
+            // The common subset of JSON strings, double-quoted YAML scalars and TOML basic strings
+            Self::Json | Self::Yaml | Self::Toml => escape_string(value),
+            Self::Env => escape_env(value),
+            Self::Raw => value.to_string(),
+        }
+    }
+}
+
+/// Reference from the `{{ secret "category/subcategory" "field" }}` placeholder
+struct SecretReference {
+    category: String,
+    sub_category: String,
+    field: Option<String>,
+}
+
+/// Replaces every `{{ secret "<category>/<subcategory>" ["<field>"] }}` with the escaped value.
+/// A placeholder between double quotes gets the value without quotes of its own: `KEY="{{ secret "a/b" }}"`.
+/// Other `{{ ... }}` placeholders, e.g. of Helm, are kept as they are.
+/// Fails with the list of all broken references if at least one of them can not be resolved
+pub fn render_template(
+    vault: &Vault,
+    template: &str,
+    format: TemplateFormat,
+) -> Result<String, VaultError> {
+    let mut result = String::with_capacity(template.len());
+    let mut errors = Vec::new();
+
+    let mut rest = template;
+    let mut line = 1;
+
+    while let Some(open_pos) = rest.find(OPEN_TAG) {
+        let before = &rest[..open_pos];
+        result.push_str(before);
+        line += before.matches('\n').count();
+
+        let after_open = &rest[open_pos + OPEN_TAG.len()..];
+
+        let close_pos = match after_open.find(CLOSE_TAG) {
+            Some(close_pos) => close_pos,
+            None if is_secret_placeholder(after_open) => {
+                errors.push(format!("line {}: placeholder is not closed", line));
+                rest = "";
+                break;
+            }
+            None => {
+                rest = &rest[open_pos..];
+                break;
+            }
+        };
+
+        let placeholder = &after_open[..close_pos];
+        let after_close = &after_open[close_pos + CLOSE_TAG.len()..];
+
+        if is_secret_placeholder(placeholder) {
+            match resolve_placeholder(vault, placeholder) {
+                Ok(value) => match get_quote(&result, after_close) {
+                    None => result.push_str(&format.escape(value)),
+                    Some('"') => result.push_str(&format.escape_quoted(value)),
+                    Some(_) if format == TemplateFormat::Raw => result.push_str(value),
+                    Some(_) => errors.push(format!(
+                        "line {}: value in single quotes can not be escaped. Use double quotes or none",
+                        line
+                    )),
+                },
+                Err(err) => errors.push(format!("line {}: {}", line, err)),
+            }
+        } else {
+            result.push_str(OPEN_TAG);
+            result.push_str(placeholder);
+            result.push_str(CLOSE_TAG);
+        }
+
+        line += placeholder.matches('\n').count();
+        rest = after_close;
+    }
+
+    result.push_str(rest);
+
+    if !errors.is_empty() {
+        return Err(VaultError::Template(errors.join("\n")));
+    }
+
+    Ok(result)
+}
+
+/// Whether the template has placeholders of another template engine, which are kept when rendering
+pub fn has_other_placeholders(template: &str) -> bool {
+    template
+        .split(OPEN_TAG)
+        .skip(1)
+        .any(|placeholder| !is_secret_placeholder(placeholder))
+}
+
+/// Categories the secret placeholders refer to, so sealed ones can be unlocked before rendering
+pub fn get_categories(template: &str) -> BTreeSet<String> {
+    let mut result = BTreeSet::new();
+
+    for after_open in template.split(OPEN_TAG).skip(1) {
+        let Some(close_pos) = after_open.find(CLOSE_TAG) else {
+            continue;
+        };
+
+        let placeholder = &after_open[..close_pos];
+
+        if !is_secret_placeholder(placeholder) {
+            continue;
+        }
+
+        // Broken placeholders are reported by the rendering
+        if let Ok(reference) = parse_placeholder(placeholder) {
+            result.insert(reference.category);
+        }
+    }
+
+    result
+}
+
+fn is_secret_placeholder(placeholder: &str) -> bool {
+    match placeholder.trim_start().strip_prefix(SECRET_FUNCTION) {
+        Some(rest) => rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '"'),
+        None => false,
+    }
+}
+
+/// The quote the placeholder is between
+fn get_quote(before: &str, after: &str) -> Option<char> {
+    match (before.chars().last(), after.chars().next()) {
+        (Some(before), Some(after)) if before == after && (before == '"' || before == '\'') => {
+            Some(before)
+        }
+        _ => None,
+    }
+}
+
+fn resolve_placeholder<'v>(vault: &'v Vault, placeholder: &str) -> Result<&'v str, String> {
+    let reference = parse_placeholder(placeholder)?;
+
+    let result = match &reference.field {
+        Some(field) => vault.get_field(&reference.category, &reference.sub_category, field),
+        None => vault.get(&reference.category, &reference.sub_category),
+    };
+
+    result.map_err(|err| err.to_string())
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<SecretReference, String> {
+    let tokens = tokenize(placeholder)?;
+
+    let (path, field) = match tokens.as_slice() {
+        [function, path] if function == SECRET_FUNCTION => (path, None),
+        [function, path, field] if function == SECRET_FUNCTION => (path, Some(field.to_string())),
+        _ => {
+            return Err(format!(
+                "invalid placeholder {{{{{}}}}}. Expected {{{{ secret \"<category>/<subcategory>\" [\"<field>\"] }}}}",
+                placeholder
+            ))
+        }
+    };
+
+    let (category, sub_category) = path
+        .split_once('/')
+        .ok_or_else(|| format!("invalid path {}. Expected <category>/<subcategory>", path))?;
+
+    Ok(SecretReference {
+        category: category.to_string(),
+        sub_category: sub_category.to_string(),
+        field,
+    })
+}
+
+/// Splits the placeholder into bare words and double-quoted strings
+fn tokenize(placeholder: &str) -> Result<Vec<String>, String> {
+    let mut result = Vec::new();
+    let mut chars = placeholder.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut token = String::new();
+
+        if c == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped) => token.push(escaped),
+                        None => return Err("unterminated string in placeholder".to_string()),
+                    },
+                    Some(c) => token.push(c),
+                    None => return Err("unterminated string in placeholder".to_string()),
+                }
+            }
+        } else {
+            token.push(c);
+            while let Some(c) = chars.peek() {
+                if c.is_whitespace() || *c == '"' {
+                    break;
+                }
+                token.push(*c);
+                chars.next();
+            }
+        }
+
+        result.push(token);
+    }
+
+    Ok(result)
+}
+
+fn escape_env(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '$' => result.push_str("\\$"),
+            '`' => result.push_str("\\`"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// TOML and YAML do not allow DEL and other control characters in strings, which JSON leaves as they are
+fn escape_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04X}", c as u32)),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_vault(dir: &tempfile::TempDir) -> Vault {
+        let mut vault = Vault::open(dir.path().join("vault").to_string_lossy(), [0; 16]);
+        vault.unlock("password").unwrap();
+        vault
+            .put(
+                "Database",
+                "prod",
+                "user: admin\npassword: p\"a$s\n".to_string(),
+            )
+            .unwrap();
+        vault
+    }
+
+    #[test]
+    fn renders_fields_escaped_for_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = create_vault(&dir);
+
+        let result = render_template(
+            &vault,
+            "DB_USER={{ secret \"Database/prod\" \"user\" }}\nDB_PASSWORD={{secret \"Database/prod\" \"password\"}}\n",
+            TemplateFormat::Env,
+        )
+        .unwrap();
+
+        assert_eq!(result, "DB_USER=\"admin\"\nDB_PASSWORD=\"p\\\"a\\$s\"\n");
+    }
+
+    #[test]
+    fn renders_fields_escaped_for_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = create_vault(&dir);
+
+        let result = render_template(
+            &vault,
+            "{\"password\": {{ secret \"Database/prod\" \"password\" }}}",
+            TemplateFormat::Json,
+        )
+        .unwrap();
+
+        assert_eq!(result, "{\"password\": \"p\\\"a$s\"}");
+    }
+
+    #[test]
+    fn does_not_quote_value_between_quotes() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = create_vault(&dir);
+
+        let template = "password: \"{{ secret \"Database/prod\" \"password\" }}\"";
+
+        assert_eq!(
+            render_template(&vault, template, TemplateFormat::Yaml).unwrap(),
+            "password: \"p\\\"a$s\""
+        );
+        assert!(render_template(
+            &vault,
+            "password: '{{ secret \"Database/prod\" \"password\" }}'",
+            TemplateFormat::Yaml
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn keeps_other_placeholders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = create_vault(&dir);
+
+        let template = "name: {{ .Release.Name }}\nuser: {{ secret \"Database/prod\" \"user\" }}\n{{- if .Values.debug }}\n";
+
+        assert!(has_other_placeholders(template));
+        assert!(!has_other_placeholders(
+            "user: {{ secret \"Database/prod\" \"user\" }}"
+        ));
+        assert_eq!(
+            render_template(&vault, template, TemplateFormat::Yaml).unwrap(),
+            "name: {{ .Release.Name }}\nuser: \"admin\"\n{{- if .Values.debug }}\n"
+        );
+    }
+
+    #[test]
+    fn lists_categories_of_placeholders() {
+        let template = "A={{ secret \"Database/prod\" \"user\" }}\nB={{ secret \"Mail/work\" }}\nC={{ .Values.name }}\nD={{ secret \"Database/dev\" }}\nE={{ secret \"broken\" }}\n";
+
+        assert_eq!(
+            get_categories(template).into_iter().collect::<Vec<_>>(),
+            vec!["Database".to_string(), "Mail".to_string()]
+        );
+    }
+
+    #[test]
+    fn escapes_control_characters() {
+        assert_eq!(
+            TemplateFormat::Toml.escape("a\u{7f}b\u{1}\t\""),
+            "\"a\\u007Fb\\u0001\\t\\\"\""
+        );
+    }
+
+    #[test]
+    fn reports_every_broken_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = create_vault(&dir);
+
+        let result = render_template(
+            &vault,
+            "A={{ secret \"Database/dev\" }}\nB={{ secret \"Database/prod\" \"token\" }}\n",
+            TemplateFormat::Env,
+        );
+
+        match result {
+            Err(VaultError::Template(message)) => {
+                assert!(message.contains("line 1"), "{}", message);
+                assert!(message.contains("line 2"), "{}", message);
+            }
+            _ => panic!("Broken references are not reported"),
+        }
+    }
+
+    #[test]
+    fn detects_format_by_file_name() {
+        assert_eq!(
+            TemplateFormat::from_file_name("config/app.env.tpl"),
+            Some(TemplateFormat::Env)
+        );
+        assert_eq!(
+            TemplateFormat::from_file_name("values.yaml.tpl"),
+            Some(TemplateFormat::Yaml)
+        );
+        assert_eq!(
+            TemplateFormat::from_file_name(".env"),
+            Some(TemplateFormat::Env)
+        );
+        assert_eq!(TemplateFormat::from_file_name("notes"), None);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    fs,
+    ops::Deref,
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+};
+
+/// Listener holding the lock of its socket path until it is dropped
+pub(crate) struct PrivateListener {
+    listener: UnixListener,
+    _lock_file: fs::File,
+}
+
+impl Deref for PrivateListener {
+    type Target = UnixListener;
+
+    fn deref(&self) -> &Self::Target {
+        &self.listener
+    }
+}
+
+/// Binds the socket in a directory only the user can open, so other users can not connect.
+/// Fails with `AddrInUse` while another process listens on it.
+/// The process listening holds the lock of `<socket>.lock`, which the OS releases when it dies.
+/// So a socket file there without the lock is left by a killed process and can be replaced
+pub(crate) fn bind_private(socket_path: &str) -> std::io::Result<PrivateListener> {
+    if let Some(dir) = Path::new(socket_path).parent() {
+        fs::create_dir_all(dir)?;
+
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let lock_file = lock_socket_path(socket_path)?;
+
+    match fs::remove_file(socket_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    Ok(PrivateListener {
+        listener: UnixListener::bind(socket_path)?,
+        _lock_file: lock_file,
+    })
+}
+
+fn lock_socket_path(socket_path: &str) -> std::io::Result<fs::File> {
+    use std::os::{fd::AsRawFd, unix::fs::OpenOptionsExt};
+
+    let lock_file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(format!("{}.lock", socket_path))?;
+
+    let result = unsafe { libc::flock(lock_file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+
+    if result != 0 {
+        let err = std::io::Error::last_os_error();
+
+        if err.kind() == std::io::ErrorKind::WouldBlock {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is used by a running process", socket_path),
+            ));
+        }
+
+        return Err(err);
+    }
+
+    Ok(lock_file)
+}
+
+/// Whether the process on the other end runs as the same user. Unknown peers are not
+pub(crate) fn is_peer_current_user(stream: &UnixStream) -> bool {
+    get_peer_uid(stream) == Some(unsafe { libc::getuid() })
+}
+
+/// Process on the other end of the socket. None where the OS does not tell it
+#[cfg(target_os = "macos")]
+pub(crate) fn get_peer_pid(stream: &UnixStream) -> Option<i32> {
+    use std::os::fd::AsRawFd;
+
+    let mut pid: libc::pid_t = 0;
+    let mut len = std::mem::size_of::<libc::pid_t>() as libc::socklen_t;
+
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_LOCAL,
+            libc::LOCAL_PEERPID,
+            &mut pid as *mut libc::pid_t as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    (result == 0).then_some(pid)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn get_peer_pid(stream: &UnixStream) -> Option<i32> {
+    get_peer_cred(stream).map(|cred| cred.pid)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn get_peer_uid(stream: &UnixStream) -> Option<u32> {
+    get_peer_cred(stream).map(|cred| cred.uid)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn get_peer_cred(stream: &UnixStream) -> Option<libc::ucred> {
+    use std::os::fd::AsRawFd;
+
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    (result == 0).then_some(cred)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "android")))]
+pub(crate) fn get_peer_pid(_stream: &UnixStream) -> Option<i32> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn get_peer_uid(stream: &UnixStream) -> Option<u32> {
+    use std::os::fd::AsRawFd;
+
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+
+    let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+
+    (result == 0).then_some(uid)
+}
+
+/// Path of the executable of the process
+#[cfg(target_os = "macos")]
+pub(crate) fn get_executable(pid: i32) -> Option<String> {
+    let mut buffer = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+
+    let len = unsafe {
+        libc::proc_pidpath(
+            pid,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len() as u32,
+        )
+    };
+
+    if len <= 0 {
+        return None;
+    }
+
+    buffer.truncate(len as usize);
+    String::from_utf8(buffer).ok()
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn get_executable(pid: i32) -> Option<String> {
+    fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_only_socket_of_dead_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("test.sock").to_string_lossy().to_string();
+
+        let listener = bind_private(&socket_path).unwrap();
+        assert_eq!(
+            bind_private(&socket_path).err().unwrap().kind(),
+            std::io::ErrorKind::AddrInUse
+        );
+
+        let stream = UnixStream::connect(&socket_path).unwrap();
+        let (accepted, _) = listener.accept().unwrap();
+        assert!(is_peer_current_user(&stream));
+        assert!(is_peer_current_user(&accepted));
+
+        // The socket file stays as if the process was killed
+        drop(listener);
+        assert!(Path::new(&socket_path).exists());
+
+        let listener = bind_private(&socket_path).unwrap();
+        UnixStream::connect(&socket_path).unwrap();
+        listener.accept().unwrap();
+    }
+}
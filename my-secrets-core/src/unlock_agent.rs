@@ -0,0 +1,350 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use base64::Engine;
+use encryption::aes::AesKey;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{unix_socket::PrivateListener, SecretKey, VaultError};
+
+const SOCKET_DIR: &str = "agent";
+const SOCKET_FILE_NAME: &str = "agent.sock";
+
+const MAX_REQUEST_SIZE: u64 = 4096;
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn get_socket_path() -> String {
+    crate::file::compile_full_filename(&format!("{}/{}", SOCKET_DIR, SOCKET_FILE_NAME))
+}
+
+/// One JSON line per connection
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum AgentRequest {
+    Get,
+    Add { key: String },
+    Lock,
+    Status,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct AgentResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default)]
+    has_key: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+struct AgentState {
+    key: Option<SecretKey>,
+    last_used: Instant,
+}
+
+/// Keeps the key derived from the master password, like ssh-agent keeps ssh keys, so the CLI and the GUI
+/// do not ask the password every time. The key is in locked memory and is wiped once it was not used
+/// for the idle timeout. Only the key is kept, the IV stays in the keychain
+pub struct UnlockAgent {
+    socket_path: String,
+    idle_timeout: Duration,
+}
+
+impl UnlockAgent {
+    pub fn new(socket_path: String, idle_timeout: Duration) -> Self {
+        Self {
+            socket_path,
+            idle_timeout,
+        }
+    }
+
+    /// Serves until a client sends stop
+    pub fn run(&self) -> Result<(), VaultError> {
+        let listener = self.bind()?;
+
+        let state = Arc::new(Mutex::new(AgentState {
+            key: None,
+            last_used: Instant::now(),
+        }));
+
+        let timer_state = state.clone();
+        let idle_timeout = self.idle_timeout;
+
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(1));
+
+            let mut state = timer_state.lock().unwrap();
+
+            if state.key.is_some() && state.last_used.elapsed() >= idle_timeout {
+                state.key = None;
+                eprintln!("The key was not used for {:?}. Forgot it", idle_timeout);
+            }
+        });
+
+        for stream in listener.incoming() {
+            let result = match stream {
+                Ok(stream) => handle_connection(&state, stream),
+                Err(err) => Err(err.into()),
+            };
+
+            match result {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(err) => eprintln!("Can not handle the connection. Err: {}", err),
+            }
+        }
+
+        state.lock().unwrap().key = None;
+        let _ = fs::remove_file(&self.socket_path);
+
+        Ok(())
+    }
+
+    fn bind(&self) -> Result<PrivateListener, VaultError> {
+        crate::unix_socket::bind_private(&self.socket_path).map_err(|err| {
+            if err.kind() == std::io::ErrorKind::AddrInUse {
+                VaultError::Agent(format!(
+                    "An agent is already running on {}",
+                    self.socket_path
+                ))
+            } else {
+                err.into()
+            }
+        })
+    }
+}
+
+/// Returns true if the agent has to stop
+fn handle_connection(state: &Mutex<AgentState>, stream: UnixStream) -> Result<bool, VaultError> {
+    // The directory of the socket keeps other users out. This makes sure of it before giving out the key
+    if !crate::unix_socket::is_peer_current_user(&stream) {
+        return Err(VaultError::Agent(
+            "Refused the connection of another user".to_string(),
+        ));
+    }
+
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut line = String::new();
+    let read = BufReader::new(&stream)
+        .take(MAX_REQUEST_SIZE)
+        .read_line(&mut line)?;
+
+    // Closed without a request
+    if read == 0 {
+        return Ok(false);
+    }
+
+    let request = serde_json::from_str::<AgentRequest>(&line);
+    line.zeroize();
+
+    let mut stop = false;
+
+    let mut response = match request {
+        Ok(AgentRequest::Get) => {
+            let mut state = state.lock().unwrap();
+
+            match &state.key {
+                Some(key) => {
+                    let response = AgentResponse {
+                        key: Some(encode_key(key)),
+                        has_key: true,
+                        error: None,
+                    };
+                    state.last_used = Instant::now();
+                    response
+                }
+                None => AgentResponse::default(),
+            }
+        }
+        Ok(AgentRequest::Add { mut key }) => {
+            // The agent keeps the key for hours, so it must not get to swap
+            let decoded = decode_key(&key, [0; 16], |aes_key| {
+                SecretKey::new_locked(aes_key).map_err(|err| {
+                    VaultError::Agent(format!("Can not lock the memory of the key. Err: {}", err))
+                })
+            });
+            key.zeroize();
+
+            match decoded {
+                Ok(key) => {
+                    let mut state = state.lock().unwrap();
+                    state.key = Some(key);
+                    state.last_used = Instant::now();
+
+                    AgentResponse {
+                        has_key: true,
+                        ..Default::default()
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Can not add the key. Err: {}", err);
+
+                    AgentResponse {
+                        error: Some(err.to_string()),
+                        ..Default::default()
+                    }
+                }
+            }
+        }
+        Ok(AgentRequest::Lock) => {
+            state.lock().unwrap().key = None;
+            AgentResponse::default()
+        }
+        Ok(AgentRequest::Status) => AgentResponse {
+            has_key: state.lock().unwrap().key.is_some(),
+            ..Default::default()
+        },
+        Ok(AgentRequest::Stop) => {
+            stop = true;
+            AgentResponse::default()
+        }
+        Err(err) => AgentResponse {
+            error: Some(format!("Invalid request. Err: {}", err)),
+            ..Default::default()
+        },
+    };
+
+    let result = write_line(&stream, &response);
+
+    if let Some(key) = &mut response.key {
+        key.zeroize();
+    }
+
+    result?;
+    Ok(stop)
+}
+
+/// Talks to the agent of the user. Fails with [`VaultError::Agent`] if it is not running
+pub struct AgentClient {
+    socket_path: String,
+}
+
+impl Default for AgentClient {
+    fn default() -> Self {
+        Self::new(get_socket_path())
+    }
+}
+
+impl AgentClient {
+    pub fn new(socket_path: String) -> Self {
+        Self { socket_path }
+    }
+
+    /// The key with the given IV. None if the agent has no key
+    pub fn get_key(&self, iv: [u8; 16]) -> Result<Option<SecretKey>, VaultError> {
+        let mut response = self.send(&AgentRequest::Get)?;
+
+        let Some(mut key) = response.key.take() else {
+            return Ok(None);
+        };
+
+        let result = decode_key(&key, iv, |aes_key| Ok(SecretKey::new(aes_key)));
+        key.zeroize();
+
+        result.map(Some)
+    }
+
+    /// Replaces the key the agent has
+    pub fn add_key(&self, aes_key: &AesKey) -> Result<(), VaultError> {
+        let mut request = AgentRequest::Add {
+            key: encode_key(aes_key),
+        };
+
+        let result = self.send(&request);
+
+        if let AgentRequest::Add { key } = &mut request {
+            key.zeroize();
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Wipes the key. The agent keeps running
+    pub fn lock(&self) -> Result<(), VaultError> {
+        self.send(&AgentRequest::Lock).map(|_| ())
+    }
+
+    /// Whether the agent has a key
+    pub fn has_key(&self) -> Result<bool, VaultError> {
+        Ok(self.send(&AgentRequest::Status)?.has_key)
+    }
+
+    pub fn stop(&self) -> Result<(), VaultError> {
+        self.send(&AgentRequest::Stop).map(|_| ())
+    }
+
+    fn send(&self, request: &AgentRequest) -> Result<AgentResponse, VaultError> {
+        let stream = UnixStream::connect(&self.socket_path).map_err(|err| {
+            VaultError::Agent(format!(
+                "The agent is not running on {}. Err: {}",
+                self.socket_path, err
+            ))
+        })?;
+
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+        write_line(&stream, request)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .take(MAX_REQUEST_SIZE)
+            .read_line(&mut line)?;
+
+        let response = serde_json::from_str::<AgentResponse>(&line);
+        line.zeroize();
+
+        let response =
+            response.map_err(|err| VaultError::Agent(format!("Invalid response. Err: {}", err)))?;
+
+        match &response.error {
+            Some(err) => Err(VaultError::Agent(err.clone())),
+            None => Ok(response),
+        }
+    }
+}
+
+fn write_line(mut stream: &UnixStream, message: &impl Serialize) -> Result<(), VaultError> {
+    let mut line = serde_json::to_string(message).unwrap();
+    line.push('\n');
+
+    let result = stream.write_all(line.as_bytes());
+    line.zeroize();
+
+    Ok(result?)
+}
+
+fn encode_key(aes_key: &AesKey) -> String {
+    base64::engine::general_purpose::STANDARD.encode(aes_key.key)
+}
+
+fn decode_key(
+    encoded: &str,
+    iv: [u8; 16],
+    create: impl FnOnce(&mut AesKey) -> Result<SecretKey, VaultError>,
+) -> Result<SecretKey, VaultError> {
+    let mut decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|err| VaultError::Agent(format!("Invalid key. Err: {}", err)))?;
+
+    let result = match <[u8; 32]>::try_from(decoded.as_slice()) {
+        Ok(mut key) => {
+            let result = create(&mut AesKey { key, iv });
+            key.zeroize();
+            result
+        }
+        Err(_) => Err(VaultError::Agent("Invalid key length".to_string())),
+    };
+
+    decoded.zeroize();
+    result
+}
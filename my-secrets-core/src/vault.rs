@@ -0,0 +1,1036 @@
+use std::collections::BTreeMap;
+
+use encryption::aes::AesKey;
+use zeroize::Zeroize;
+
+use crate::{
+    container::ContainerKey,
+    entry_ids::EntryIds,
+    file,
+    file_lock::{FileLock, LockOwner},
+    git_sync::{GitSync, PullResult},
+    merge::{self, MergeInput, MergeResult},
+    sealed_category,
+    server_sync::ServerSync,
+    storage::{LocalStorage, Storage, StoredFile, WriteCondition, Written},
+    team::{self, MemberKey, TeamKey, TeamMember},
+    SecretContent, SecretKey, TypeContent, VaultCommand, VaultError,
+};
+
+const OLD_DRAFT_FILE_NAME: &str = ".my-secrets-draft";
+
+enum VaultKey {
+    /// Derived from the master password and the keychain IV
+    Password(SecretKey),
+    /// Random data key wrapped for every member of the team
+    Team(TeamKey),
+}
+
+/// The vault file as it was loaded or saved the last time
+#[derive(Default)]
+struct FileVersion {
+    data: Option<Vec<u8>>,
+    version: Option<String>,
+    /// File of the storage offline changes in the data are based on
+    base: Option<Vec<u8>>,
+}
+
+impl FileVersion {
+    /// None data means there is no file yet
+    fn load(storage: &dyn Storage) -> Result<Self, VaultError> {
+        Ok(Self::from_stored(storage.load()?))
+    }
+
+    fn from_stored(stored: Option<StoredFile>) -> Self {
+        match stored {
+            Some(stored) => Self {
+                data: Some(stored.data),
+                version: stored.version,
+                base: stored.base,
+            },
+            None => Self::default(),
+        }
+    }
+
+    /// The file as it is in the storage. Differs from the data while offline changes are not uploaded
+    fn get_stored_data(&self) -> Option<&[u8]> {
+        self.base.as_deref().or(self.data.as_deref())
+    }
+}
+
+struct UnlockedVault {
+    key: VaultKey,
+    content: SecretContent,
+    ids: EntryIds,
+    file_version: FileVersion,
+    /// Keys of the sealed categories which are unlocked and kept as plain text in the content
+    opened_categories: BTreeMap<String, ContainerKey>,
+}
+
+/// Encrypted vault file. Content is available only while the vault is unlocked
+pub struct Vault {
+    file_name: String,
+    storage: Box<dyn Storage>,
+    iv: [u8; 16],
+    unlocked: Option<UnlockedVault>,
+    git_sync: Option<GitSync>,
+    /// The file was saved but git sync could not commit it
+    commit_error: Option<VaultError>,
+    server_sync: Option<ServerSync>,
+    file_lock: Option<FileLock>,
+    /// Process having the vault open while this one opened it read-only
+    locked_by: Option<LockOwner>,
+}
+
+impl Vault {
+    pub fn open(file_name: impl Into<String>, iv: [u8; 16]) -> Self {
+        let file_name = file_name.into();
+
+        Self {
+            storage: Box::new(LocalStorage::new(file_name.as_str())),
+            file_name,
+            iv,
+            unlocked: None,
+            git_sync: None,
+            commit_error: None,
+            server_sync: None,
+            file_lock: None,
+            locked_by: None,
+        }
+    }
+
+    /// Vault at ~/.my-secrets/.my-secrets-data
+    pub fn open_default(iv: [u8; 16]) -> Self {
+        Self::open(file::compile_full_filename(file::DEFAULT_FILE_NAME), iv)
+    }
+
+    pub fn get_file_name(&self) -> &str {
+        self.file_name.as_str()
+    }
+
+    /// The vault file is kept in the storage instead of the file. The file is its offline cache then
+    pub fn set_storage(&mut self, storage: Box<dyn Storage>) {
+        self.storage = storage;
+    }
+
+    /// Team vaults accept the password of a member or an `AGE-SECRET-KEY-1...` identity
+    pub fn unlock(&mut self, password: &str) -> Result<(), VaultError> {
+        if password.is_empty() {
+            return Err(VaultError::EmptyPassword);
+        }
+
+        let file_version = FileVersion::load(&*self.storage)?;
+
+        let (key, content, ids) = match &file_version.data {
+            Some(data) if team::is_team_file(data) => {
+                let (key, content, ids) = team::unlock(data, password)?;
+                (VaultKey::Team(key), content, ids)
+            }
+            data => {
+                let aes_key = SecretKey::new(&mut crate::password_utils::create_aes_key(
+                    password, self.iv,
+                ));
+
+                let (content, ids) = match data {
+                    Some(data) => file::decrypt_content(&aes_key, data)?,
+                    None => Default::default(),
+                };
+
+                (VaultKey::Password(aes_key), content, ids)
+            }
+        };
+
+        self.unlocked = Some(UnlockedVault {
+            key,
+            content: SecretContent::new(content),
+            ids,
+            file_version,
+            opened_categories: BTreeMap::new(),
+        });
+
+        self.migrate_old_draft();
+
+        Ok(())
+    }
+
+    /// Unlocks with the key derived from the master password before, e.g. by the unlock agent.
+    /// Only an existing vault file can tell whether the key is right, so a new vault needs the password
+    pub fn unlock_with_key(&mut self, aes_key: SecretKey) -> Result<(), VaultError> {
+        let file_version = FileVersion::load(&*self.storage)?;
+
+        let (content, ids) = match &file_version.data {
+            Some(data) if team::is_team_file(data) => {
+                return Err(VaultError::Team(
+                    "Team vault has no master password key".to_string(),
+                ))
+            }
+            Some(data) => file::decrypt_content(&aes_key, data)?,
+            None => return Err(VaultError::Locked),
+        };
+
+        self.unlocked = Some(UnlockedVault {
+            key: VaultKey::Password(aes_key),
+            content: SecretContent::new(content),
+            ids,
+            file_version,
+            opened_categories: BTreeMap::new(),
+        });
+
+        self.migrate_old_draft();
+
+        Ok(())
+    }
+
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.is_some()
+    }
+
+    pub fn get_aes_key(&self) -> Result<&AesKey, VaultError> {
+        match &self.get_unlocked()?.key {
+            VaultKey::Password(aes_key) => Ok(&**aes_key),
+            VaultKey::Team(_) => Err(VaultError::Team(
+                "Team vault has no master password key".to_string(),
+            )),
+        }
+    }
+
+    pub fn content(&self) -> Result<&TypeContent, VaultError> {
+        Ok(&*self.get_unlocked()?.content)
+    }
+
+    /// The change has to wipe the texts it replaces or removes
+    pub fn update_content<R>(
+        &mut self,
+        change: impl FnOnce(&mut TypeContent) -> R,
+    ) -> Result<R, VaultError> {
+        Ok(self.get_unlocked_mut()?.content.update(change))
+    }
+
+    /// Replaces the whole content wiping the previous one. Entries keep their IDs by name
+    pub fn replace_content(&mut self, content: TypeContent) -> Result<(), VaultError> {
+        if let Err(err) = sealed_category::check_content(&content) {
+            crate::wipe_content(content);
+            return Err(err);
+        }
+
+        let unlocked = self.get_unlocked_mut()?;
+        unlocked.content.replace(content);
+        unlocked.forget_locked_categories();
+        Ok(())
+    }
+
+    /// Replaces the content with the result of a merge, taking the IDs the entries have there
+    pub fn replace_merged_content(
+        &mut self,
+        content: TypeContent,
+        ids: EntryIds,
+    ) -> Result<(), VaultError> {
+        self.replace_content(content)?;
+        self.get_unlocked_mut()?.ids = ids;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<&str>, VaultError> {
+        Ok(self
+            .content()?
+            .keys()
+            .map(|category| category.as_str())
+            .collect())
+    }
+
+    pub fn list_sub_categories(&self, category: &str) -> Result<Vec<&str>, VaultError> {
+        self.check_category_not_locked(category)?;
+
+        let sub_categories = self
+            .content()?
+            .get(category)
+            .ok_or_else(|| VaultError::CategoryNotFound(category.to_string()))?;
+
+        Ok(sub_categories
+            .keys()
+            .map(|sub_category| sub_category.as_str())
+            .collect())
+    }
+
+    pub fn get(&self, category: &str, sub_category: &str) -> Result<&str, VaultError> {
+        self.check_category_not_locked(category)?;
+
+        self.content()?
+            .get(category)
+            .and_then(|sub_categories| sub_categories.get(sub_category))
+            .map(|text| text.as_str())
+            .ok_or_else(|| VaultError::sub_category_not_found(category, sub_category))
+    }
+
+    /// Value of a `name: value` or `name=value` line of the subcategory text
+    pub fn get_field(
+        &self,
+        category: &str,
+        sub_category: &str,
+        field: &str,
+    ) -> Result<&str, VaultError> {
+        let text = self.get(category, sub_category)?;
+
+        crate::fields::get_field(text, field).ok_or_else(|| VaultError::FieldNotFound {
+            category: category.to_string(),
+            sub_category: sub_category.to_string(),
+            field: field.to_string(),
+        })
+    }
+
+    /// Sets the text of the subcategory creating the category and the subcategory if needed
+    pub fn put(
+        &mut self,
+        category: &str,
+        sub_category: &str,
+        text: String,
+    ) -> Result<(), VaultError> {
+        self.check_category_not_locked(category)?;
+        sealed_category::check_sub_category_name(sub_category)?;
+
+        self.update_content(|content| {
+            let sub_categories = content
+                .entry(category.to_string())
+                .or_insert_with(BTreeMap::new);
+
+            if let Some(mut prev_text) = sub_categories.insert(sub_category.to_string(), text) {
+                prev_text.zeroize();
+            }
+        })
+    }
+
+    pub fn rename_category(&mut self, from: &str, to: &str) -> Result<(), VaultError> {
+        self.apply(&VaultCommand::RenameCategory {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    pub fn rename_sub_category(
+        &mut self,
+        category: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<(), VaultError> {
+        self.apply(&VaultCommand::RenameSubCategory {
+            category: category.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    pub fn move_sub_category(
+        &mut self,
+        name: &str,
+        from_category: &str,
+        to_category: &str,
+    ) -> Result<(), VaultError> {
+        self.apply(&VaultCommand::MoveSubCategory {
+            name: name.to_string(),
+            from_category: from_category.to_string(),
+            to_category: to_category.to_string(),
+        })
+    }
+
+    pub fn delete_category(&mut self, category: &str) -> Result<(), VaultError> {
+        self.apply(&VaultCommand::DeleteCategory {
+            name: category.to_string(),
+            sub_categories: BTreeMap::new(),
+        })
+    }
+
+    pub fn delete_sub_category(
+        &mut self,
+        category: &str,
+        sub_category: &str,
+    ) -> Result<(), VaultError> {
+        self.apply(&VaultCommand::DeleteSubCategory {
+            category: category.to_string(),
+            name: sub_category.to_string(),
+            text: String::new(),
+        })
+    }
+
+    pub fn apply(&mut self, command: &VaultCommand) -> Result<(), VaultError> {
+        let categories = match command {
+            VaultCommand::EditText { category, .. }
+            | VaultCommand::CreateSubCategory { category, .. }
+            | VaultCommand::RenameSubCategory { category, .. }
+            | VaultCommand::DeleteSubCategory { category, .. }
+            | VaultCommand::RenameCategory { from: category, .. }
+            | VaultCommand::DeleteCategory { name: category, .. } => vec![category],
+            VaultCommand::MoveSubCategory {
+                from_category,
+                to_category,
+                ..
+            } => vec![from_category, to_category],
+            VaultCommand::CreateCategory { .. } => vec![],
+        };
+
+        for category in categories {
+            self.check_category_not_locked(category)?;
+        }
+
+        match command {
+            VaultCommand::CreateSubCategory { name, .. }
+            | VaultCommand::RenameSubCategory { to: name, .. }
+            | VaultCommand::MoveSubCategory { name, .. } => {
+                sealed_category::check_sub_category_name(name)?
+            }
+            VaultCommand::CreateCategory { sub_categories, .. } => {
+                for name in sub_categories.keys() {
+                    sealed_category::check_sub_category_name(name)?;
+                }
+            }
+            _ => {}
+        }
+
+        let unlocked = self.get_unlocked_mut()?;
+        unlocked.content.update(|content| command.apply(content))?;
+        unlocked.ids.apply(command);
+
+        match command {
+            VaultCommand::RenameCategory { from, to } => {
+                if let Some(key) = unlocked.opened_categories.remove(from) {
+                    unlocked.opened_categories.insert(to.clone(), key);
+                }
+            }
+            VaultCommand::DeleteCategory { name, .. } => {
+                unlocked.opened_categories.remove(name);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Writes the file. Fails with [`VaultError::FileChanged`] if another program wrote it since it was loaded
+    pub fn save(&mut self) -> Result<(), VaultError> {
+        let version = self.get_unchanged_version()?;
+        self.write_file(WriteCondition::from_version(version.as_deref()))
+    }
+
+    /// Writes the file even if another program changed it. New entries get their IDs here
+    pub fn overwrite(&mut self) -> Result<(), VaultError> {
+        self.write_file(WriteCondition::Any)
+    }
+
+    fn write_file(&mut self, condition: WriteCondition) -> Result<(), VaultError> {
+        self.check_not_read_only()?;
+
+        // Another process may have taken the lock over since it was acquired
+        if let Some(file_lock) = &self.file_lock {
+            file_lock.check_owner()?;
+        }
+        self.get_unlocked_mut()?.sync_ids()?;
+        let unlocked = self.get_unlocked()?;
+
+        let data = unlocked.with_sealed_content(|content| match &unlocked.key {
+            VaultKey::Password(aes_key) => {
+                Ok(file::encrypt_content(aes_key, content, &unlocked.ids))
+            }
+            VaultKey::Team(team_key) => team_key.to_file_content(content, &unlocked.ids),
+        })?;
+
+        let written = file::save_data_to_file(&*self.storage, &data, condition)?;
+
+        let file_version = &mut self.get_unlocked_mut()?.file_version;
+
+        *file_version = match written {
+            Written::Stored(version) => FileVersion {
+                data: Some(data),
+                version: Some(version),
+                base: None,
+            },
+            // The storage still has the file the offline changes are based on
+            Written::Cached => FileVersion {
+                base: file_version.get_stored_data().map(|data| data.to_vec()),
+                data: Some(data),
+                version: file_version.version.take(),
+            },
+        };
+
+        // The file is saved already. The next save commits it again
+        self.commit_error = match &self.git_sync {
+            Some(git_sync) => git_sync.commit("Update vault").err(),
+            None => None,
+        };
+
+        Ok(())
+    }
+
+    /// Reads the file again with the same key dropping all not saved changes
+    pub fn reload(&mut self) -> Result<(), VaultError> {
+        let file_version = FileVersion::load(&*self.storage)?;
+
+        let (key, (content, ids)) = match (&self.get_unlocked()?.key, &file_version.data) {
+            (_, None) => (None, Default::default()),
+            (VaultKey::Password(aes_key), Some(data)) => {
+                (None, file::decrypt_content(aes_key, data)?)
+            }
+            (VaultKey::Team(team_key), Some(data)) => {
+                let (team_key, content, ids) = team_key.reload(data)?;
+                (Some(VaultKey::Team(team_key)), (content, ids))
+            }
+        };
+
+        let unlocked = self.get_unlocked_mut()?;
+        if let Some(key) = key {
+            unlocked.key = key;
+        }
+        unlocked.content.replace(content);
+        unlocked.ids = ids;
+        unlocked.file_version = file_version;
+        unlocked.forget_locked_categories();
+
+        Ok(())
+    }
+
+    pub fn save_draft(&self) -> Result<(), VaultError> {
+        self.check_not_read_only()?;
+        let unlocked = self.get_unlocked()?;
+
+        let encrypted = unlocked.with_sealed_content(|content| match &unlocked.key {
+            VaultKey::Password(aes_key) => {
+                Ok(file::encrypt_content(aes_key, content, &unlocked.ids))
+            }
+            VaultKey::Team(team_key) => team_key.encrypt_content(content, &unlocked.ids),
+        })?;
+
+        file::write_private_file(&self.get_draft_file_name(), &encrypted)?;
+        Ok(())
+    }
+
+    pub fn load_draft(&self) -> Result<Option<TypeContent>, VaultError> {
+        let encrypted = match std::fs::read(self.get_draft_file_name()) {
+            Ok(encrypted) => encrypted,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        // Entries of the draft keep the IDs they have by name in the vault
+        let (content, _) = match &self.get_unlocked()?.key {
+            VaultKey::Password(aes_key) => file::decrypt_content(aes_key, &encrypted)?,
+            VaultKey::Team(team_key) => team_key.decrypt_content(&encrypted)?,
+        };
+
+        Ok(Some(content))
+    }
+
+    pub fn delete_draft(&self) -> Result<(), VaultError> {
+        match std::fs::remove_file(self.get_draft_file_name()) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Takes the `<vault>.lock` file. Fails with [`VaultError::FileLocked`] while another process has the vault open.
+    /// The lock is released when the vault is dropped
+    pub fn acquire_file_lock(&mut self) -> Result<(), VaultError> {
+        if self.file_lock.is_some() {
+            return Ok(());
+        }
+
+        self.file_lock = Some(FileLock::acquire(&self.file_name)?);
+        self.locked_by = None;
+        Ok(())
+    }
+
+    /// Takes the lock from the other process, e.g. when it is known to be gone
+    pub fn take_over_file_lock(&mut self) -> Result<(), VaultError> {
+        self.file_lock = Some(FileLock::take_over(&self.file_name)?);
+        self.locked_by = None;
+        Ok(())
+    }
+
+    /// Saves fail while the other process has the vault open
+    pub fn open_read_only(&mut self, locked_by: LockOwner) {
+        self.locked_by = Some(locked_by);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.locked_by.is_some()
+    }
+
+    /// Called periodically by long running processes, so other hosts do not take their lock for stale
+    pub fn refresh_file_lock(&mut self) {
+        if let Some(file_lock) = &mut self.file_lock {
+            file_lock.refresh_if_needed();
+        }
+    }
+
+    /// Every save commits the file to the git repository of the vault directory
+    pub fn set_git_sync(&mut self, git_sync: Option<GitSync>) {
+        self.git_sync = git_sync;
+    }
+
+    pub fn get_git_sync(&self) -> Option<&GitSync> {
+        self.git_sync.as_ref()
+    }
+
+    /// Error of the git commit made by the last save. The save itself succeeded, so it is a warning
+    pub fn take_commit_error(&mut self) -> Option<VaultError> {
+        self.commit_error.take()
+    }
+
+    /// Pull and push go to a `my-secrets serve` server. Git sync is used instead when both are set
+    pub fn set_server_sync(&mut self, server_sync: Option<ServerSync>) {
+        self.server_sync = server_sync;
+    }
+
+    pub fn get_server_sync(&self) -> Option<&ServerSync> {
+        self.server_sync.as_ref()
+    }
+
+    /// Pulling from the server writes the vault file, so a read-only vault can not do it
+    pub fn pull_from_server(&self) -> Result<PullResult, VaultError> {
+        self.check_not_read_only()?;
+
+        match &self.server_sync {
+            Some(server_sync) => server_sync.pull(),
+            None => Err(VaultError::Server(
+                "Sync server is not configured".to_string(),
+            )),
+        }
+    }
+
+    /// Decrypts another version of the vault file with the key of this vault, e.g. a synced copy.
+    /// Sealed categories unlocked here are opened in it as well
+    pub fn decrypt_version(&self, data: &[u8]) -> Result<(TypeContent, EntryIds), VaultError> {
+        let unlocked = self.get_unlocked()?;
+
+        let (mut content, ids) = match &unlocked.key {
+            VaultKey::Password(aes_key) => file::decrypt_content(aes_key, data)?,
+            VaultKey::Team(team_key) => {
+                let (_, content, ids) = team_key.reload(data)?;
+                (content, ids)
+            }
+        };
+
+        for (category, key) in &unlocked.opened_categories {
+            let Some(sub_categories) = content.get_mut(category) else {
+                continue;
+            };
+
+            if !sealed_category::is_sealed(sub_categories) {
+                continue;
+            }
+
+            // Sealed with another password there. Stays sealed and merges as a single entry
+            if let Ok(opened) = sealed_category::open_with_key(key, sub_categories) {
+                crate::wipe_sub_categories(std::mem::replace(sub_categories, opened));
+            }
+        }
+
+        Ok((content, ids))
+    }
+
+    /// Three-way merge of the current content with another version of the file.
+    /// Without the base version all differing entries are conflicts
+    pub fn merge_version(
+        &self,
+        base: Option<&[u8]>,
+        theirs: &[u8],
+    ) -> Result<MergeResult, VaultError> {
+        let (base, base_ids) = match base {
+            Some(base) => self.decrypt_version(base)?,
+            None => Default::default(),
+        };
+
+        let (theirs, their_ids) = match self.decrypt_version(theirs) {
+            Ok(theirs) => theirs,
+            Err(err) => {
+                crate::wipe_content(base);
+                return Err(err);
+            }
+        };
+
+        let unlocked = self.get_unlocked()?;
+
+        let result = merge::merge_content(
+            &MergeInput {
+                content: &base,
+                ids: &base_ids,
+            },
+            &MergeInput {
+                content: &unlocked.content,
+                ids: &unlocked.ids,
+            },
+            &MergeInput {
+                content: &theirs,
+                ids: &their_ids,
+            },
+        );
+
+        crate::wipe_content(base);
+        crate::wipe_content(theirs);
+
+        Ok(result)
+    }
+
+    /// Another program wrote the vault file since it was loaded or saved, e.g. a sync service
+    pub fn has_file_changed(&self) -> Result<bool, VaultError> {
+        match self.get_unchanged_version() {
+            Ok(_) => Ok(false),
+            Err(VaultError::FileChanged) => Ok(true),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Version of the stored file if it still has the loaded data.
+    /// Fails with [`VaultError::FileChanged`] otherwise
+    fn get_unchanged_version(&self) -> Result<Option<String>, VaultError> {
+        let loaded = &self.get_unlocked()?.file_version;
+
+        let version = self.storage.get_version()?;
+        if version == loaded.version {
+            return Ok(version);
+        }
+
+        // Touched without changing the data
+        let stored = FileVersion::from_stored(self.storage.read()?);
+        if stored.data.as_deref() != loaded.get_stored_data() {
+            return Err(VaultError::FileChanged);
+        }
+
+        Ok(stored.version)
+    }
+
+    /// Three-way merge of the current content with the file written by another program.
+    /// The version loaded the last time is the base
+    pub fn merge_file_changes(&self) -> Result<MergeResult, VaultError> {
+        let theirs = self
+            .storage
+            .read()?
+            .ok_or_else(|| VaultError::InvalidData("The vault file was deleted".to_string()))?
+            .data;
+
+        self.merge_version(self.get_unlocked()?.file_version.get_stored_data(), &theirs)
+    }
+
+    /// Sealed category which is not unlocked. Its subcategories are not available
+    pub fn is_category_locked(&self, category: &str) -> bool {
+        self.content()
+            .ok()
+            .and_then(|content| content.get(category))
+            .map(sealed_category::is_sealed)
+            .unwrap_or(false)
+    }
+
+    pub fn is_category_sealed(&self, category: &str) -> bool {
+        self.is_category_locked(category)
+            || self
+                .get_unlocked()
+                .map(|unlocked| unlocked.opened_categories.contains_key(category))
+                .unwrap_or(false)
+    }
+
+    /// Sealed categories which are unlocked at the moment
+    pub fn list_unlocked_sealed_categories(&self) -> Vec<&str> {
+        match self.get_unlocked() {
+            Ok(unlocked) => unlocked
+                .opened_categories
+                .keys()
+                .map(|category| category.as_str())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Requires the password to access the category from now on.
+    /// The category stays unlocked until [`Vault::lock_category`] and is sealed in the file with the next save
+    pub fn seal_category(&mut self, category: &str, password: &str) -> Result<(), VaultError> {
+        if !self.content()?.contains_key(category) {
+            return Err(VaultError::CategoryNotFound(category.to_string()));
+        }
+
+        if self.is_category_sealed(category) {
+            return Err(VaultError::InvalidData(format!(
+                "Category {} is already sealed",
+                category
+            )));
+        }
+
+        let key = ContainerKey::derive(password)?;
+        self.get_unlocked_mut()?
+            .opened_categories
+            .insert(category.to_string(), key);
+
+        Ok(())
+    }
+
+    /// Makes the unlocked sealed category a regular one
+    pub fn unseal_category(&mut self, category: &str) -> Result<(), VaultError> {
+        self.check_category_not_locked(category)?;
+
+        self.get_unlocked_mut()?
+            .opened_categories
+            .remove(category)
+            .map(|_| ())
+            .ok_or_else(|| VaultError::InvalidData(format!("Category {} is not sealed", category)))
+    }
+
+    pub fn unlock_category(&mut self, category: &str, password: &str) -> Result<(), VaultError> {
+        let unlocked = self.get_unlocked_mut()?;
+
+        let key = unlocked.content.update(|content| {
+            let sub_categories = content
+                .get_mut(category)
+                .ok_or_else(|| VaultError::CategoryNotFound(category.to_string()))?;
+
+            if !sealed_category::is_sealed(sub_categories) {
+                return Ok(None);
+            }
+
+            let (key, opened) = sealed_category::open(password, sub_categories)?;
+            *sub_categories = opened;
+
+            Ok::<_, VaultError>(Some(key))
+        })?;
+
+        if let Some(key) = key {
+            unlocked.opened_categories.insert(category.to_string(), key);
+        }
+
+        Ok(())
+    }
+
+    /// Encrypts the subcategories in memory again. Not saved changes are kept inside
+    pub fn lock_category(&mut self, category: &str) -> Result<(), VaultError> {
+        let unlocked = self.get_unlocked_mut()?;
+
+        let Some(key) = unlocked.opened_categories.remove(category) else {
+            return Ok(());
+        };
+
+        unlocked.content.update(|content| {
+            let sub_categories = content
+                .get_mut(category)
+                .ok_or_else(|| VaultError::CategoryNotFound(category.to_string()))?;
+
+            let sealed = sealed_category::seal(&key, sub_categories)?;
+            crate::wipe_sub_categories(std::mem::replace(sub_categories, sealed));
+
+            Ok(())
+        })
+    }
+
+    pub fn is_team(&self) -> bool {
+        matches!(
+            self.unlocked.as_ref().map(|unlocked| &unlocked.key),
+            Some(VaultKey::Team(_))
+        )
+    }
+
+    pub fn get_team_members(&self) -> Result<&[TeamMember], VaultError> {
+        Ok(&self.get_team_key()?.members)
+    }
+
+    /// Member the vault is unlocked by
+    pub fn get_team_member_name(&self) -> Result<&str, VaultError> {
+        Ok(&self.get_team_key()?.member_name)
+    }
+
+    /// Switches the vault to a random data key wrapped for the first member.
+    /// The file is written in the team format with the next save
+    pub fn convert_to_team(&mut self, member_name: &str, password: &str) -> Result<(), VaultError> {
+        let unlocked = self.get_unlocked_mut()?;
+
+        if let VaultKey::Team(_) = unlocked.key {
+            return Err(VaultError::Team(
+                "The vault is already a team vault".to_string(),
+            ));
+        }
+
+        unlocked.key = VaultKey::Team(TeamKey::create(member_name, password)?);
+        Ok(())
+    }
+
+    pub fn add_team_member(&mut self, name: &str, key: MemberKey) -> Result<(), VaultError> {
+        self.get_team_key_mut()?.add_member(name, key)
+    }
+
+    /// The vault gets a new data key. Save it, otherwise the removed member can still read the file
+    pub fn remove_team_member(&mut self, name: &str) -> Result<(), VaultError> {
+        self.get_team_key_mut()?.remove_member(name)
+    }
+
+    fn get_team_key(&self) -> Result<&TeamKey, VaultError> {
+        match &self.get_unlocked()?.key {
+            VaultKey::Team(team_key) => Ok(team_key),
+            VaultKey::Password(_) => Err(VaultError::Team("Not a team vault".to_string())),
+        }
+    }
+
+    fn get_team_key_mut(&mut self) -> Result<&mut TeamKey, VaultError> {
+        match &mut self.get_unlocked_mut()?.key {
+            VaultKey::Team(team_key) => Ok(team_key),
+            VaultKey::Password(_) => Err(VaultError::Team("Not a team vault".to_string())),
+        }
+    }
+
+    fn check_not_read_only(&self) -> Result<(), VaultError> {
+        match &self.locked_by {
+            Some(owner) => Err(VaultError::FileLocked(owner.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn check_category_not_locked(&self, category: &str) -> Result<(), VaultError> {
+        if self.is_category_locked(category) {
+            return Err(VaultError::CategoryLocked(category.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn get_draft_file_name(&self) -> String {
+        format!("{}.draft", self.file_name)
+    }
+
+    /// Versions before the vault path was configurable kept the draft of the default vault
+    /// at ~/.my-secrets/.my-secrets-draft
+    fn migrate_old_draft(&self) {
+        if self.file_name != file::compile_full_filename(file::DEFAULT_FILE_NAME) {
+            return;
+        }
+
+        let old_draft_file_name = file::compile_full_filename(OLD_DRAFT_FILE_NAME);
+        let draft_file_name = self.get_draft_file_name();
+
+        if !std::path::Path::new(&old_draft_file_name).exists()
+            || std::path::Path::new(&draft_file_name).exists()
+        {
+            return;
+        }
+
+        // Stays at the old path if it can not be moved
+        let _ = std::fs::rename(old_draft_file_name, draft_file_name);
+    }
+
+    fn get_unlocked(&self) -> Result<&UnlockedVault, VaultError> {
+        self.unlocked.as_ref().ok_or(VaultError::Locked)
+    }
+
+    fn get_unlocked_mut(&mut self) -> Result<&mut UnlockedVault, VaultError> {
+        self.unlocked.as_mut().ok_or(VaultError::Locked)
+    }
+}
+
+impl UnlockedVault {
+    /// Calls back with the content where unlocked sealed categories are encrypted again
+    fn with_sealed_content<TResult>(
+        &self,
+        callback: impl FnOnce(&TypeContent) -> Result<TResult, VaultError>,
+    ) -> Result<TResult, VaultError> {
+        if self.opened_categories.is_empty() {
+            return callback(&self.content);
+        }
+
+        let mut content = TypeContent::new();
+
+        for (category, sub_categories) in self.content.iter() {
+            let sub_categories = match self.opened_categories.get(category) {
+                Some(key) => match sealed_category::seal(key, sub_categories) {
+                    Ok(sealed) => sealed,
+                    Err(err) => {
+                        crate::wipe_content(content);
+                        return Err(err);
+                    }
+                },
+                None => sub_categories.clone(),
+            };
+
+            content.insert(category.clone(), sub_categories);
+        }
+
+        let result = callback(&content);
+        crate::wipe_content(content);
+
+        result
+    }
+
+    fn sync_ids(&mut self) -> Result<(), VaultError> {
+        let opened_categories = &self.opened_categories;
+        self.ids.sync(&self.content, |category| {
+            opened_categories.contains_key(category)
+        })
+    }
+
+    /// Drops keys of the categories the new content has sealed
+    fn forget_locked_categories(&mut self) {
+        let content = &self.content;
+        self.opened_categories.retain(|category, _| {
+            content
+                .get(category)
+                .map(|sub_categories| !sealed_category::is_sealed(sub_categories))
+                .unwrap_or(false)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sealed_category::SEALED_SUB_CATEGORY;
+
+    fn create_vault(dir: &tempfile::TempDir) -> Vault {
+        let mut vault = Vault::open(dir.path().join("vault").to_string_lossy(), [0; 16]);
+        vault.unlock("password").unwrap();
+        vault.put("Bank", "card", "pin: 1234".to_string()).unwrap();
+        vault
+    }
+
+    #[test]
+    fn rejects_reserved_sub_category_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = create_vault(&dir);
+
+        assert!(vault
+            .put("Bank", SEALED_SUB_CATEGORY, "text".to_string())
+            .is_err());
+        assert!(vault
+            .apply(&VaultCommand::CreateCategory {
+                name: "Work".to_string(),
+                sub_categories: [
+                    (SEALED_SUB_CATEGORY.to_string(), "text".to_string()),
+                    ("vpn".to_string(), "text".to_string()),
+                ]
+                .into(),
+            })
+            .is_err());
+
+        let mut content = vault.content().unwrap().clone();
+        content
+            .get_mut("Bank")
+            .unwrap()
+            .insert(SEALED_SUB_CATEGORY.to_string(), "text".to_string());
+        assert!(vault.replace_content(content).is_err());
+    }
+
+    #[test]
+    fn keeps_locked_category_from_rename_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = create_vault(&dir);
+
+        vault.seal_category("Bank", "bank password").unwrap();
+        vault.lock_category("Bank").unwrap();
+
+        assert!(matches!(
+            vault.rename_category("Bank", "Money"),
+            Err(VaultError::CategoryLocked(_))
+        ));
+        assert!(matches!(
+            vault.delete_category("Bank"),
+            Err(VaultError::CategoryLocked(_))
+        ));
+
+        vault.unlock_category("Bank", "bank password").unwrap();
+        vault.rename_category("Bank", "Money").unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use zeroize::Zeroize;
+
+use crate::{TypeContent, VaultError};
+
+/// Single reversible change of the vault content
+#[derive(Debug, Clone)]
+pub enum VaultCommand {
+    EditText {
+        category: String,
+        sub_category: String,
+        before: String,
+        after: String,
+    },
+    CreateCategory {
+        name: String,
+        sub_categories: BTreeMap<String, String>,
+    },
+    RenameCategory {
+        from: String,
+        to: String,
+    },
+    DeleteCategory {
+        name: String,
+        sub_categories: BTreeMap<String, String>,
+    },
+    CreateSubCategory {
+        category: String,
+        name: String,
+        text: String,
+    },
+    RenameSubCategory {
+        category: String,
+        from: String,
+        to: String,
+    },
+    DeleteSubCategory {
+        category: String,
+        name: String,
+        text: String,
+    },
+    MoveSubCategory {
+        name: String,
+        from_category: String,
+        to_category: String,
+    },
+}
+
+impl VaultCommand {
+    pub fn apply(&self, content: &mut TypeContent) -> Result<(), VaultError> {
+        match self {
+            VaultCommand::EditText {
+                category,
+                sub_category,
+                after,
+                ..
+            } => {
+                let text = content
+                    .get_mut(category)
+                    .and_then(|sub_categories| sub_categories.get_mut(sub_category))
+                    .ok_or_else(|| VaultError::sub_category_not_found(category, sub_category))?;
+
+                std::mem::replace(text, after.clone()).zeroize();
+            }
+            VaultCommand::CreateCategory {
+                name,
+                sub_categories,
+            } => {
+                if content.contains_key(name) {
+                    return Err(VaultError::CategoryAlreadyExists(name.clone()));
+                }
+
+                content.insert(name.clone(), sub_categories.clone());
+            }
+            VaultCommand::RenameCategory { from, to } => {
+                if content.contains_key(to) {
+                    return Err(VaultError::CategoryAlreadyExists(to.clone()));
+                }
+
+                let sub_categories = content
+                    .remove(from)
+                    .ok_or_else(|| VaultError::CategoryNotFound(from.clone()))?;
+
+                content.insert(to.clone(), sub_categories);
+            }
+            VaultCommand::DeleteCategory { name, .. } => {
+                let sub_categories = content
+                    .remove(name)
+                    .ok_or_else(|| VaultError::CategoryNotFound(name.clone()))?;
+
+                crate::wipe_sub_categories(sub_categories);
+            }
+            VaultCommand::CreateSubCategory {
+                category,
+                name,
+                text,
+            } => {
+                let sub_categories = content
+                    .get_mut(category)
+                    .ok_or_else(|| VaultError::CategoryNotFound(category.clone()))?;
+
+                if sub_categories.contains_key(name) {
+                    return Err(VaultError::sub_category_already_exists(category, name));
+                }
+
+                sub_categories.insert(name.clone(), text.clone());
+            }
+            VaultCommand::RenameSubCategory { category, from, to } => {
+                let sub_categories = content
+                    .get_mut(category)
+                    .ok_or_else(|| VaultError::CategoryNotFound(category.clone()))?;
+
+                if sub_categories.contains_key(to) {
+                    return Err(VaultError::sub_category_already_exists(category, to));
+                }
+
+                let text = sub_categories
+                    .remove(from)
+                    .ok_or_else(|| VaultError::sub_category_not_found(category, from))?;
+
+                sub_categories.insert(to.clone(), text);
+            }
+            VaultCommand::DeleteSubCategory { category, name, .. } => {
+                content
+                    .get_mut(category)
+                    .and_then(|sub_categories| sub_categories.remove(name))
+                    .ok_or_else(|| VaultError::sub_category_not_found(category, name))?
+                    .zeroize();
+            }
+            VaultCommand::MoveSubCategory {
+                name,
+                from_category,
+                to_category,
+            } => {
+                match content.get(to_category) {
+                    Some(sub_categories) => {
+                        if sub_categories.contains_key(name) {
+                            return Err(VaultError::sub_category_already_exists(to_category, name));
+                        }
+                    }
+                    None => return Err(VaultError::CategoryNotFound(to_category.clone())),
+                }
+
+                let text = content
+                    .get_mut(from_category)
+                    .and_then(|sub_categories| sub_categories.remove(name))
+                    .ok_or_else(|| VaultError::sub_category_not_found(from_category, name))?;
+
+                content
+                    .get_mut(to_category)
+                    .unwrap()
+                    .insert(name.clone(), text);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn revert(&self, content: &mut TypeContent) -> Result<(), VaultError> {
+        self.inverse().apply(content)
+    }
+
+    pub fn inverse(&self) -> Self {
+        match self {
+            VaultCommand::EditText {
+                category,
+                sub_category,
+                before,
+                after,
+            } => VaultCommand::EditText {
+                category: category.clone(),
+                sub_category: sub_category.clone(),
+                before: after.clone(),
+                after: before.clone(),
+            },
+            VaultCommand::CreateCategory {
+                name,
+                sub_categories,
+            } => VaultCommand::DeleteCategory {
+                name: name.clone(),
+                sub_categories: sub_categories.clone(),
+            },
+            VaultCommand::RenameCategory { from, to } => VaultCommand::RenameCategory {
+                from: to.clone(),
+                to: from.clone(),
+            },
+            VaultCommand::DeleteCategory {
+                name,
+                sub_categories,
+            } => VaultCommand::CreateCategory {
+                name: name.clone(),
+                sub_categories: sub_categories.clone(),
+            },
+            VaultCommand::CreateSubCategory {
+                category,
+                name,
+                text,
+            } => VaultCommand::DeleteSubCategory {
+                category: category.clone(),
+                name: name.clone(),
+                text: text.clone(),
+            },
+            VaultCommand::RenameSubCategory { category, from, to } => {
+                VaultCommand::RenameSubCategory {
+                    category: category.clone(),
+                    from: to.clone(),
+                    to: from.clone(),
+                }
+            }
+            VaultCommand::DeleteSubCategory {
+                category,
+                name,
+                text,
+            } => VaultCommand::CreateSubCategory {
+                category: category.clone(),
+                name: name.clone(),
+                text: text.clone(),
+            },
+            VaultCommand::MoveSubCategory {
+                name,
+                from_category,
+                to_category,
+            } => VaultCommand::MoveSubCategory {
+                name: name.clone(),
+                from_category: to_category.clone(),
+                to_category: from_category.clone(),
+            },
+        }
+    }
+
+    /// Category and subcategory which should be selected after the command is applied
+    pub fn focus(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            VaultCommand::EditText {
+                category,
+                sub_category,
+                ..
+            } => (Some(category.as_str()), Some(sub_category.as_str())),
+            VaultCommand::CreateCategory { name, .. } => (Some(name.as_str()), None),
+            VaultCommand::RenameCategory { to, .. } => (Some(to.as_str()), None),
+            VaultCommand::DeleteCategory { .. } => (None, None),
+            VaultCommand::CreateSubCategory { category, name, .. } => {
+                (Some(category.as_str()), Some(name.as_str()))
+            }
+            VaultCommand::RenameSubCategory { category, to, .. } => {
+                (Some(category.as_str()), Some(to.as_str()))
+            }
+            VaultCommand::DeleteSubCategory { category, .. } => (Some(category.as_str()), None),
+            VaultCommand::MoveSubCategory {
+                name, to_category, ..
+            } => (Some(to_category.as_str()), Some(name.as_str())),
+        }
+    }
+
+    /// Whether the command changes the category or moves a subcategory into or out of it
+    pub fn has_category(&self, category: &str) -> bool {
+        match self {
+            VaultCommand::EditText { category: name, .. }
+            | VaultCommand::CreateCategory { name, .. }
+            | VaultCommand::DeleteCategory { name, .. }
+            | VaultCommand::CreateSubCategory { category: name, .. }
+            | VaultCommand::RenameSubCategory { category: name, .. }
+            | VaultCommand::DeleteSubCategory { category: name, .. } => name == category,
+            VaultCommand::RenameCategory { from, to } => from == category || to == category,
+            VaultCommand::MoveSubCategory {
+                from_category,
+                to_category,
+                ..
+            } => from_category == category || to_category == category,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            VaultCommand::EditText {
+                category,
+                sub_category,
+                ..
+            } => format!("Edit {}/{}", category, sub_category),
+            VaultCommand::CreateCategory { name, .. } => format!("Create category {}", name),
+            VaultCommand::RenameCategory { from, to } => {
+                format!("Rename category {} → {}", from, to)
+            }
+            VaultCommand::DeleteCategory { name, .. } => format!("Delete category {}", name),
+            VaultCommand::CreateSubCategory { category, name, .. } => {
+                format!("Create subcategory {}/{}", category, name)
+            }
+            VaultCommand::RenameSubCategory { category, from, to } => {
+                format!("Rename subcategory {}/{} → {}", category, from, to)
+            }
+            VaultCommand::DeleteSubCategory { category, name, .. } => {
+                format!("Delete subcategory {}/{}", category, name)
+            }
+            VaultCommand::MoveSubCategory {
+                name,
+                from_category,
+                to_category,
+            } => format!("Move {}/{} → {}", from_category, name, to_category),
+        }
+    }
+
+    fn wipe(&mut self) {
+        match self {
+            VaultCommand::EditText { before, after, .. } => {
+                before.zeroize();
+                after.zeroize();
+            }
+            VaultCommand::CreateCategory { sub_categories, .. }
+            | VaultCommand::DeleteCategory { sub_categories, .. } => {
+                for (mut name, mut text) in std::mem::take(sub_categories) {
+                    name.zeroize();
+                    text.zeroize();
+                }
+            }
+            VaultCommand::CreateSubCategory { text, .. }
+            | VaultCommand::DeleteSubCategory { text, .. } => {
+                text.zeroize();
+            }
+            VaultCommand::RenameCategory { .. }
+            | VaultCommand::RenameSubCategory { .. }
+            | VaultCommand::MoveSubCategory { .. } => {}
+        }
+    }
+}
+
+impl Drop for VaultCommand {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{settings::UnsavedEditsPolicy, states::*, unsaved_changes::*, MyApp};
+
+const AUTO_LOCK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+impl MyApp {
+    pub fn handle_auto_lock(&mut self, ctx: &egui::Context) {
+        if !self.vault.is_unlocked() {
+            return;
+        }
+
+        let (has_activity, focus_lost, minimized) = ctx.input(|i| {
+            let has_activity = i.events.iter().any(|event| {
+                !matches!(
+                    event,
+                    egui::Event::PointerMoved(_)
+                        | egui::Event::PointerGone
+                        | egui::Event::WindowFocused(_)
+                )
+            });
+
+            // Only the last change counts: the focus may come back within the same frame
+            let focus_lost = i
+                .events
+                .iter()
+                .rev()
+                .find_map(|event| match event {
+                    egui::Event::WindowFocused(focused) => Some(!focused),
+                    _ => None,
+                })
+                .unwrap_or(false);
+
+            (
+                has_activity,
+                focus_lost,
+                i.viewport().minimized.unwrap_or(false),
+            )
+        });
+
+        if has_activity {
+            self.edit_state.extend_expiration_time();
+        }
+
+        let lock_now_pressed =
+            ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::L));
+
+        let just_minimized = minimized && !self.window_minimized;
+        self.window_minimized = minimized;
+
+        let auto_lock = &self.settings.auto_lock;
+
+        let lock_requested = lock_now_pressed
+            || self.edit_state.activity_expired()
+            || (auto_lock.lock_on_focus_lost
+                && focus_lost
+                && !crate::native_dialogs::has_taken_focus())
+            || (auto_lock.lock_on_minimize && just_minimized);
+
+        if lock_requested {
+            self.lock();
+            return;
+        }
+
+        self.handle_category_locks(has_activity);
+
+        ctx.request_repaint_after(AUTO_LOCK_CHECK_INTERVAL);
+    }
+
+    pub fn lock(&mut self) {
+        if !self.vault.is_unlocked() {
+            return;
+        }
+
+        if self.has_not_saved_data() {
+            match self.settings.auto_lock.unsaved_edits {
+                UnsavedEditsPolicy::SaveDraft => {
+                    self.save_draft();
+                }
+                UnsavedEditsPolicy::Prompt => match ask_about_unsaved_changes(
+                    "There are not saved changes. Do you want to save them before locking?",
+                ) {
+                    UnsavedChangesDecision::Save => {
+                        self.save_to_file();
+
+                        // The file changed meanwhile and the changes are still to be merged
+                        if self.has_not_saved_data() {
+                            self.edit_state.extend_expiration_time();
+                            return;
+                        }
+                    }
+                    UnsavedChangesDecision::Discard => {
+                        self.delete_draft();
+                    }
+                    UnsavedChangesDecision::Cancel => {
+                        self.edit_state.extend_expiration_time();
+                        return;
+                    }
+                },
+            }
+        }
+
+        self.vault.lock();
+        self.selected_category = None;
+        self.selected_sub_category = None;
+        self.changes.clear();
+        self.edit_state.wipe();
+        self.history.clear();
+        self.show_history = false;
+        self.modal_dialog
+            .set(ModalWindowState::Authenticate(Default::default()));
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use my_secrets_core::unlock_agent::{self, AgentClient, UnlockAgent};
+
+use crate::settings::SettingsModel;
+
+const USAGE: &str =
+    "Usage: my-secrets agent [start [--idle-timeout <minutes>] | lock | status | stop]
+
+start runs the unlock agent in the foreground. Once the vault is unlocked with the password
+it keeps the key, and the CLI and the GUI take it from the agent instead of asking the password.
+The agent forgets the key after agent: idle_timeout_minutes: of ~/.my-secrets/.my-secrets.yaml
+without use, 15 by default. lock makes it forget the key right away.";
+
+pub fn agent(settings: &SettingsModel, args: &[String]) -> Result<(), String> {
+    match args {
+        [] => start(settings, &[]),
+        [command, args @ ..] if command == "start" => start(settings, args),
+        [command] if command == "lock" => {
+            AgentClient::default()
+                .lock()
+                .map_err(|err| err.to_string())?;
+            println!("The agent forgot the key");
+            Ok(())
+        }
+        [command] if command == "status" => {
+            let has_key = AgentClient::default()
+                .has_key()
+                .map_err(|err| err.to_string())?;
+
+            if has_key {
+                println!("The agent is running and has the key");
+            } else {
+                println!("The agent is running without a key");
+            }
+            Ok(())
+        }
+        [command] if command == "stop" => {
+            AgentClient::default().stop().map_err(|err| err.to_string())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn start(settings: &SettingsModel, args: &[String]) -> Result<(), String> {
+    let idle_timeout_minutes = match args {
+        [] => settings.agent.idle_timeout_minutes,
+        [flag, minutes] if flag == "--idle-timeout" => minutes
+            .parse()
+            .map_err(|_| format!("Invalid idle timeout: {}", minutes))?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    if idle_timeout_minutes == 0 {
+        return Err("Idle timeout must be at least one minute".to_string());
+    }
+
+    let socket_path = unlock_agent::get_socket_path();
+
+    println!(
+        "Unlock agent on {}. The key is forgotten after {} minutes without use",
+        socket_path, idle_timeout_minutes
+    );
+
+    UnlockAgent::new(socket_path, Duration::from_secs(idle_timeout_minutes * 60))
+        .run()
+        .map_err(|err| err.to_string())
+}
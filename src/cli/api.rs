@@ -0,0 +1,35 @@
+use my_secrets_core::local_api::{self, ApiClients};
+
+const USAGE: &str = "Usage: my-secrets api [clients | revoke <name>]
+
+Manages the clients of the local API the GUI serves on ~/.my-secrets/api/api.sock
+once api: enabled: true is set in ~/.my-secrets/.my-secrets.yaml.
+clients prints the approved clients with the categories they may read,
+revoke removes the approval so the client has to register again.
+Every call is written to ~/.my-secrets/api/access.log.";
+
+pub fn api(args: &[String]) -> Result<(), String> {
+    let mut clients = ApiClients::load(local_api::get_clients_file_name())
+        .map_err(|err| format!("Can not read the API clients. Err: {}", err))?;
+
+    match args {
+        [] => Err(USAGE.to_string()),
+        [command] if command == "clients" => {
+            for client in clients.get_all() {
+                println!("{}: {}", client.name, client.categories.join(", "));
+            }
+            Ok(())
+        }
+        [command, name] if command == "revoke" => {
+            let revoked = clients.revoke(name).map_err(|err| err.to_string())?;
+
+            if !revoked {
+                return Err(format!("There is no client {}", name));
+            }
+
+            println!("Revoked the access of {}", name);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
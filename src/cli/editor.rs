@@ -0,0 +1,71 @@
+use std::{fs::OpenOptions, io::Write, process::Command};
+
+use zeroize::Zeroize;
+
+/// Opens the text in $VISUAL or $EDITOR and returns the edited version.
+/// The temporary file is readable only by the user and is overwritten before it is removed.
+pub fn edit_in_editor(text: &str) -> Result<String, String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    let file_name = std::env::temp_dir().join(format!(
+        "my-secrets-{}-{}",
+        std::process::id(),
+        rust_extensions::date_time::DateTimeAsMicroseconds::now().unix_microseconds
+    ));
+
+    let mut open_options = OpenOptions::new();
+    open_options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(0o600);
+    }
+
+    let mut file = open_options
+        .open(&file_name)
+        .map_err(|err| format!("Can not create temp file. Err: {}", err))?;
+
+    file.write_all(text.as_bytes())
+        .map_err(|err| format!("Can not write temp file. Err: {}", err))?;
+    drop(file);
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&file_name)
+        .status();
+
+    let result = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&file_name)
+            .map_err(|err| format!("Can not read temp file. Err: {}", err)),
+        Ok(status) => Err(format!("Editor {} exited with {}", editor, status)),
+        Err(err) => Err(format!("Can not start editor {}. Err: {}", editor, err)),
+    };
+
+    wipe_file(&file_name);
+
+    result
+}
+
+fn wipe_file(file_name: &std::path::Path) {
+    if let Ok(metadata) = std::fs::metadata(file_name) {
+        let mut zeros = vec![0u8; metadata.len() as usize];
+        if let Ok(mut file) = OpenOptions::new().write(true).open(file_name) {
+            let _ = file.write_all(&zeros);
+            let _ = file.sync_all();
+        }
+        zeros.zeroize();
+    }
+
+    if let Err(err) = std::fs::remove_file(file_name) {
+        eprintln!(
+            "Can not remove temp file: {}. Err: {}",
+            file_name.display(),
+            err
+        );
+    }
+}
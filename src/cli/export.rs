@@ -0,0 +1,120 @@
+use std::io::Write;
+
+use my_secrets_core::export::{self, ExportFormat, ExportRequest};
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets export <category> [--entry <subcategory>]... [--format k8s|env|compose] [--name <secret name>] [--namespace <namespace>] [--output <path>]
+
+Kubernetes and env formats are written to stdout unless --output is given.
+Docker compose layout requires --output directory.";
+
+pub fn export(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let mut category = None;
+    let mut entries = Vec::new();
+    let mut format = ExportFormat::Kubernetes;
+    let mut name = None;
+    let mut namespace = None;
+    let mut output = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--entry" => entries.push(next_value(&mut args)?.to_string()),
+            "--format" => {
+                let value = next_value(&mut args)?;
+                format = ExportFormat::from_name(value)
+                    .ok_or_else(|| format!("Unknown format: {}\n{}", value, USAGE))?;
+            }
+            "--name" => name = Some(next_value(&mut args)?),
+            "--namespace" => namespace = Some(next_value(&mut args)?),
+            "--output" => output = Some(next_value(&mut args)?),
+            _ if category.is_none() && !arg.starts_with("--") => category = Some(arg.as_str()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let category = category.ok_or_else(|| USAGE.to_string())?;
+
+    if format == ExportFormat::DockerCompose && output.is_none() {
+        return Err(USAGE.to_string());
+    }
+
+    let rules = settings.get_export_rules(category);
+
+    let mut vault = super::open_vault(settings, password_source)?;
+    super::unlock_category(&mut vault, category)?;
+
+    let files = export::export_category(
+        &vault,
+        &ExportRequest {
+            category,
+            entries: if entries.is_empty() {
+                None
+            } else {
+                Some(entries.as_slice())
+            },
+            rules: &rules,
+            format,
+            name: name.map(|name| name.as_str()),
+            namespace: namespace.map(|namespace| namespace.as_str()),
+        },
+    )
+    .map_err(|err| err.to_string())?;
+
+    drop(vault);
+
+    match output {
+        Some(output) => write_files(output, format, &files),
+        None => {
+            let mut stdout = std::io::stdout();
+            for file in &files {
+                stdout
+                    .write_all(file.content.as_bytes())
+                    .map_err(|err| format!("Can not write to stdout. Err: {}", err))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Single file formats are written to the output path, docker compose layout into the output directory
+fn write_files(
+    output: &str,
+    format: ExportFormat,
+    files: &[export::ExportedFile],
+) -> Result<(), String> {
+    if format != ExportFormat::DockerCompose {
+        for file in files {
+            my_secrets_core::file::write_private_file(output, file.content.as_bytes())
+                .map_err(|err| format!("Can not write file {}. Err: {}", output, err))?;
+        }
+        return Ok(());
+    }
+
+    for file in files {
+        let file_name = format!("{}/{}", output, file.file_name);
+
+        if let Some(parent) = std::path::Path::new(&file_name).parent() {
+            std::fs::create_dir_all(parent).map_err(|err| {
+                format!("Can not create folder {}. Err: {}", parent.display(), err)
+            })?;
+        }
+
+        my_secrets_core::file::write_private_file(&file_name, file.content.as_bytes())
+            .map_err(|err| format!("Can not write file {}. Err: {}", file_name, err))?;
+    }
+
+    Ok(())
+}
+
+fn next_value<'s>(args: &mut impl Iterator<Item = &'s String>) -> Result<&'s String, String> {
+    args.next().ok_or_else(|| USAGE.to_string())
+}
@@ -0,0 +1,45 @@
+use my_secrets_core::bundle;
+use zeroize::Zeroize;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets export-bundle --output <file> <category>[/<subcategory>]...
+
+Writes the entries into a file encrypted with its own password which is asked on the TTY.
+The bundle is imported with: my-secrets import bundle <file>";
+
+pub fn export_bundle(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let mut output = None;
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(args.next().ok_or_else(|| USAGE.to_string())?),
+            _ if arg.starts_with("--") => return Err(USAGE.to_string()),
+            _ => paths.push(arg.to_string()),
+        }
+    }
+
+    let (Some(output), false) = (output, paths.is_empty()) else {
+        return Err(USAGE.to_string());
+    };
+
+    let vault = super::open_vault(settings, password_source)?;
+
+    let mut password = super::read_new_password("Bundle password")?;
+    let result = bundle::create_bundle(&vault, &paths, &password);
+    password.zeroize();
+
+    let data = result.map_err(|err| err.to_string())?;
+
+    my_secrets_core::file::write_private_file(output, &data)
+        .map_err(|err| format!("Can not write file {}. Err: {}", output, err))
+}
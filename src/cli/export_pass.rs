@@ -0,0 +1,61 @@
+use my_secrets_core::pass_store::{self, Gpg};
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str =
+    "Usage: my-secrets export-pass --recipient <gpg id>... [--dir <path>] [<category>]...
+
+Writes categories as a pass password store: <dir>/<category>/<subcategory>.gpg
+with the password field on the first line. All categories are written if none are given.
+The directory is ~/.password-store unless --dir is given.";
+
+pub fn export_pass(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let mut recipients = Vec::new();
+    let mut categories = Vec::new();
+    let mut dir = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--recipient" => recipients.push(next_value(&mut args)?.to_string()),
+            "--dir" => dir = Some(next_value(&mut args)?.to_string()),
+            _ if arg.starts_with("--") => return Err(USAGE.to_string()),
+            _ => categories.push(arg.to_string()),
+        }
+    }
+
+    if recipients.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let dir = dir.unwrap_or_else(pass_store::get_default_store_dir);
+
+    let vault = super::open_vault(settings, password_source)?;
+
+    let written = pass_store::write_pass_store(
+        &vault,
+        &dir,
+        if categories.is_empty() {
+            None
+        } else {
+            Some(categories.as_slice())
+        },
+        &recipients,
+        &Gpg::default(),
+    )
+    .map_err(|err| err.to_string())?;
+
+    println!("{} entries written to {}", written, dir);
+    Ok(())
+}
+
+fn next_value<'s>(args: &mut impl Iterator<Item = &'s String>) -> Result<&'s String, String> {
+    args.next().ok_or_else(|| USAGE.to_string())
+}
@@ -0,0 +1,177 @@
+use my_secrets_core::{
+    import::{
+        self, ConflictResolution, GroupMapping, ImportAction, ImportFormat, ImportOptions,
+        ImportPlan, ImportedEntry,
+    },
+    pass_store::{self, Gpg},
+    sharing,
+};
+use zeroize::Zeroize;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets import <format> <file> [--key-file <path>] [--nested] [--gpg-key <id>] [--on-conflict skip|overwrite|keep-both] [--dry-run]
+
+Formats:
+    keepass     KeePass KDBX 3.1/4 database. The password is asked on the TTY
+    bitwarden   Bitwarden unencrypted JSON export
+    1password   1Password 1PUX or CSV export
+    chrome      Chrome password CSV
+    firefox     Firefox password CSV
+    bundle      Bundle made by export-bundle. The password is asked on the TTY
+    shared      Entries shared to the identity of the vault, - reads them from stdin
+    pass        pass password store directory, ~/.password-store if <file> is omitted.
+                Files are decrypted with the local gpg, --gpg-key is tried first
+
+Groups, folders and vaults become categories. Nested KeePass groups are flattened into their top level group unless --nested is given.
+Existing subcategories are skipped by default.
+--dry-run prints what would be imported without changing the vault.";
+
+pub fn import(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let mut format = None;
+    let mut file_name = None;
+    let mut key_file = None;
+    let mut gpg = Gpg::default();
+    let mut mapping = GroupMapping::Flatten;
+    let mut resolution = ConflictResolution::Skip;
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key-file" => key_file = Some(next_value(&mut args)?),
+            "--nested" => mapping = GroupMapping::Nested,
+            "--gpg-key" => gpg.secret_key = Some(next_value(&mut args)?.to_string()),
+            "--on-conflict" => {
+                let value = next_value(&mut args)?;
+                resolution = ConflictResolution::from_name(value)
+                    .ok_or_else(|| format!("Unknown conflict resolution: {}\n{}", value, USAGE))?;
+            }
+            "--dry-run" => dry_run = true,
+            _ if arg.starts_with("--") => return Err(USAGE.to_string()),
+            _ if format.is_none() => format = Some(arg.as_str()),
+            _ if file_name.is_none() => file_name = Some(arg.as_str()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let Some(format) = format else {
+        return Err(USAGE.to_string());
+    };
+
+    let mut vault = super::open_vault(settings, password_source)?;
+    let content = vault.content().map_err(|err| err.to_string())?;
+
+    let entries = if format == "pass" {
+        let dir = file_name
+            .map(|file_name| file_name.to_string())
+            .unwrap_or_else(pass_store::get_default_store_dir);
+
+        pass_store::read_pass_store(&dir, &gpg).map_err(|err| err.to_string())?
+    } else {
+        let file_name = file_name.ok_or_else(|| USAGE.to_string())?;
+
+        let format = ImportFormat::from_name(format)
+            .ok_or_else(|| format!("Unknown import format: {}\n{}", format, USAGE))?;
+
+        if format == ImportFormat::Shared {
+            let result = if file_name == "-" {
+                let mut data = super::read_stdin()?;
+                let result = sharing::read_shared(content, data.as_bytes());
+                data.zeroize();
+                result
+            } else {
+                import::read_entries(
+                    format,
+                    file_name,
+                    &ImportOptions {
+                        vault_content: Some(content),
+                        ..Default::default()
+                    },
+                )
+            };
+
+            result.map_err(|err| err.to_string())?
+        } else if format.requires_password() {
+            read_with_password(format, file_name, key_file, mapping)?
+        } else {
+            import::read_entries(format, file_name, &ImportOptions::default())
+                .map_err(|err| err.to_string())?
+        }
+    };
+
+    let plan = ImportPlan::new(content, entries, resolution);
+
+    print_plan(&plan);
+
+    if dry_run {
+        return Ok(());
+    }
+
+    plan.apply(&mut vault).map_err(|err| err.to_string())?;
+    super::save_vault(&mut vault)
+}
+
+fn read_with_password(
+    format: ImportFormat,
+    file_name: &str,
+    key_file: Option<&String>,
+    mapping: GroupMapping,
+) -> Result<Vec<ImportedEntry>, String> {
+    let mut password = rpassword::prompt_password(format!("{} password: ", format.get_name()))
+        .map_err(|err| format!("Can not read password from TTY. Err: {}", err))?;
+
+    if password.is_empty() && key_file.is_none() {
+        return Err("Password or key file is required".to_string());
+    }
+
+    let result = import::read_entries(
+        format,
+        file_name,
+        &ImportOptions {
+            password: Some(password.as_str()),
+            key_file: key_file.map(|key_file| key_file.as_str()),
+            mapping,
+            vault_content: None,
+        },
+    );
+
+    password.zeroize();
+
+    result.map_err(|err| err.to_string())
+}
+
+fn print_plan(plan: &ImportPlan) {
+    for item in &plan.items {
+        let entry = &item.entry;
+        match item.action {
+            ImportAction::Create => {
+                println!("create     {}/{}", entry.category, entry.sub_category)
+            }
+            ImportAction::Unchanged => {
+                println!("unchanged  {}/{}", entry.category, entry.sub_category)
+            }
+            ImportAction::Skip => println!("skip       {}/{}", entry.category, entry.sub_category),
+            ImportAction::Overwrite => {
+                println!("overwrite  {}/{}", entry.category, entry.sub_category)
+            }
+            ImportAction::KeepBoth => println!(
+                "keep both  {}/{} -> {}",
+                entry.category, entry.sub_category, item.target_sub_category
+            ),
+        }
+    }
+
+    println!("{}", plan.summary());
+}
+
+fn next_value<'s>(args: &mut impl Iterator<Item = &'s String>) -> Result<&'s String, String> {
+    args.next().ok_or_else(|| USAGE.to_string())
+}
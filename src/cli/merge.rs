@@ -0,0 +1,73 @@
+use my_secrets_core::merge::MergeChoice;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets merge <copy> [--base <file>] [--ours | --theirs]
+
+Merges another copy of the vault file, e.g. a conflicted copy made by Dropbox or iCloud, into the vault
+entry by entry and saves it. The copy has to unlock with the same master password.
+--base is the version both copies were made from, e.g. a backup. Without it every entry which differs is a conflict.
+Entries changed on both sides fail the merge unless --ours or --theirs is given.";
+
+pub fn merge(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let mut copy_file = None;
+    let mut base_file = None;
+    let mut choice = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--base" => base_file = Some(args.next().ok_or_else(|| USAGE.to_string())?),
+            "--ours" => choice = Some(MergeChoice::Ours),
+            "--theirs" => choice = Some(MergeChoice::Theirs),
+            _ if arg.starts_with("--") => return Err(USAGE.to_string()),
+            _ if copy_file.is_none() => copy_file = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let Some(copy_file) = copy_file else {
+        return Err(USAGE.to_string());
+    };
+
+    let theirs = read_file(copy_file)?;
+    let base = match base_file {
+        Some(base_file) => Some(read_file(base_file)?),
+        None => None,
+    };
+
+    let mut vault = super::open_vault(settings, password_source)?;
+
+    let mut result = vault
+        .merge_version(base.as_deref(), &theirs)
+        .map_err(|err| err.to_string())?;
+
+    let choice = match choice {
+        Some(choice) => choice,
+        None if result.conflicts.is_empty() => MergeChoice::Ours,
+        None => {
+            return Err(super::sync::format_conflicts(
+                &result,
+                "Merge again with --ours or --theirs, or resolve them in the app",
+            ))
+        }
+    };
+
+    let (content, ids) = result.resolve(&vec![choice; result.conflicts.len()]);
+
+    vault
+        .replace_merged_content(content, ids)
+        .map_err(|err| err.to_string())?;
+    super::save_vault(&mut vault)
+}
+
+fn read_file(file_name: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(file_name).map_err(|err| format!("Can not read {}. Err: {}", file_name, err))
+}
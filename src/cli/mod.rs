@@ -0,0 +1,367 @@
+mod agent;
+mod api;
+mod editor;
+mod export;
+mod export_bundle;
+mod export_pass;
+mod import;
+mod merge;
+mod password;
+mod render;
+mod run;
+mod serve;
+mod share;
+mod sync;
+mod team;
+mod vault;
+
+use std::io::{IsTerminal, Read};
+
+use zeroize::Zeroize;
+
+pub use password::*;
+pub use vault::*;
+
+use crate::settings::SettingsModel;
+
+const USAGE: &str = "Usage: my-secrets [--password-fd <fd>] <command> [args]
+
+Commands:
+    list [<category>]                      List categories or subcategories of a category
+    get <category>/<subcategory>           Print the secret
+    set <category>/<subcategory>           Set the secret read from stdin. Asked on the TTY
+                                           if stdin is a terminal
+    edit <category>/<subcategory>          Edit the secret with $VISUAL or $EDITOR
+    rm <category>[/<subcategory>]          Remove a subcategory or a whole category
+    mv <from>[/<sub>] <to>[/<sub>]         Rename or move a category or a subcategory
+    seal <category>                        Require an extra password for the category
+    unseal <category>                      Make the sealed category a regular one again
+    run [--env <NAME>=<path>[#<field>]]... [--env-from <path>]... -- <command> [args]
+                                           Run a command with secrets as environment variables
+    render [--format <format>] <template>  Render a template replacing {{ secret \"<path>\" [\"<field>\"] }}
+                                           placeholders. Format: env, yaml, json, toml or raw
+    export <category> [--entry <sub>]... [--format k8s|env|compose] [--output <path>]
+                                           Export a category as Kubernetes Secret, .env or
+                                           docker-compose secrets
+    import <format> <file> [--key-file <path>] [--nested] [--on-conflict skip|overwrite|keep-both] [--dry-run]
+                                           Import entries from KeePass, Bitwarden, 1Password,
+                                           Chrome, Firefox export, pass password store
+                                           a bundle or shared entries
+    export-bundle --output <file> <category>[/<subcategory>]...
+                                           Export entries into a password protected bundle
+    export-pass --recipient <gpg id>... [--dir <path>] [<category>]...
+                                           Export categories as a pass password store
+    identity [--create]                    Print the public key of the vault identity
+    contacts [add <name> <key> | rm <name>]
+                                           Manage public keys of teammates
+    share --to <name|key>... [--output <file>] <category>[/<subcategory>]...
+                                           Encrypt entries to teammates, import them with
+                                           import shared <file>
+    merge <copy> [--base <file>] [--ours | --theirs]
+                                           Merge a conflicted copy of the vault file made
+                                           by a sync service into the vault
+    sync [pull [--ours | --theirs] | push]
+                                           Pull and merge the vault from the git remote
+                                           or the sync server and push it back
+    serve [--listen <address>] [--file <path>]
+                                           Serve the encrypted vault file to sync it
+                                           between machines
+    agent [start [--idle-timeout <minutes>] | lock | status | stop]
+                                           Keep the unlocked key, so the password is not
+                                           asked until the agent forgets it
+    api [clients | revoke <name>]          Manage the clients of the local API of the GUI
+    team [init <name> | members | add <name> (--password | --public-key <key>) | rm <name>]
+                                           Manage members of a team vault
+
+Without a command the GUI is started.
+The master password is asked on the TTY unless --password-fd is given
+or the unlock agent has the key.
+Passwords of sealed categories are always asked on the TTY.";
+
+pub struct CliArgs {
+    pub password_source: PasswordSource,
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl CliArgs {
+    /// Returns None if there is no command and the GUI should be started
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut password_source = PasswordSource::Tty;
+        let mut command = None;
+        let mut command_args = Vec::new();
+
+        let mut args = args.skip(1);
+
+        while let Some(arg) = args.next() {
+            if command.is_some() {
+                command_args.push(arg);
+                continue;
+            }
+
+            match arg.as_str() {
+                "--password-fd" => {
+                    let fd = args
+                        .next()
+                        .ok_or_else(|| "--password-fd requires a value".to_string())?;
+
+                    let fd = fd
+                        .parse()
+                        .map_err(|_| format!("Invalid file descriptor: {}", fd))?;
+
+                    password_source = PasswordSource::FileDescriptor(fd);
+                }
+                "-h" | "--help" => {
+                    command = Some("help".to_string());
+                }
+                _ => {
+                    command = Some(arg);
+                }
+            }
+        }
+
+        Ok(command.map(|command| Self {
+            password_source,
+            command,
+            args: command_args,
+        }))
+    }
+}
+
+pub fn run(cli_args: CliArgs) -> i32 {
+    match run_command(cli_args) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn run_command(cli_args: CliArgs) -> Result<(), String> {
+    if cli_args.command == "help" {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    // Only read after the arguments are known, so the help works without the keychain
+    let settings = &SettingsModel::read()?;
+    let args = cli_args.args.as_slice();
+
+    match cli_args.command.as_str() {
+        "list" => {
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+            match args {
+                [] => {
+                    for category in vault.list().map_err(|err| err.to_string())? {
+                        println!("{}", category);
+                    }
+                }
+                [category] => {
+                    unlock_category(&mut vault, category)?;
+
+                    let sub_categories = vault
+                        .list_sub_categories(category)
+                        .map_err(|err| err.to_string())?;
+
+                    for sub_category in sub_categories {
+                        println!("{}/{}", category, sub_category);
+                    }
+                }
+                _ => return Err(USAGE.to_string()),
+            }
+            Ok(())
+        }
+        "get" => {
+            let [path] = args else {
+                return Err(USAGE.to_string());
+            };
+            let (category, sub_category) = parse_entry_path(path)?;
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+            unlock_category(&mut vault, &category)?;
+
+            let text = vault
+                .get(&category, &sub_category)
+                .map_err(|err| err.to_string())?;
+            println!("{}", text);
+            Ok(())
+        }
+        "set" => {
+            // Never from the arguments, they are visible to other processes
+            let [path] = args else {
+                return Err(USAGE.to_string());
+            };
+            let (category, sub_category) = parse_entry_path(path)?;
+            let text = read_secret_text()?;
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+            unlock_category(&mut vault, &category)?;
+
+            vault
+                .put(&category, &sub_category, text)
+                .map_err(|err| err.to_string())?;
+            save_vault(&mut vault)
+        }
+        "edit" => {
+            let [path] = args else {
+                return Err(USAGE.to_string());
+            };
+            let (category, sub_category) = parse_entry_path(path)?;
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+            unlock_category(&mut vault, &category)?;
+
+            let text = match vault.get(&category, &sub_category) {
+                Ok(text) => text.to_string(),
+                Err(_) => String::new(),
+            };
+
+            let edited = editor::edit_in_editor(&text)?;
+
+            if edited != text {
+                vault
+                    .put(&category, &sub_category, edited)
+                    .map_err(|err| err.to_string())?;
+                save_vault(&mut vault)?;
+            }
+            Ok(())
+        }
+        "rm" => {
+            let [path] = args else {
+                return Err(USAGE.to_string());
+            };
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+
+            if let Some((category, _)) = path.split_once('/') {
+                unlock_category(&mut vault, category)?;
+            }
+
+            let result = match path.split_once('/') {
+                Some((category, sub_category)) => vault.delete_sub_category(category, sub_category),
+                None => vault.delete_category(path),
+            };
+
+            result.map_err(|err| err.to_string())?;
+            save_vault(&mut vault)
+        }
+        "mv" => {
+            let [from, to] = args else {
+                return Err(USAGE.to_string());
+            };
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+
+            if let (Some((from_category, _)), Some((to_category, _))) =
+                (from.split_once('/'), to.split_once('/'))
+            {
+                unlock_category(&mut vault, from_category)?;
+                unlock_category(&mut vault, to_category)?;
+            }
+
+            let result = match (from.split_once('/'), to.split_once('/')) {
+                (None, None) => vault.rename_category(from, to),
+                (Some((from_category, from_name)), Some((to_category, to_name))) => {
+                    if from_category == to_category {
+                        vault.rename_sub_category(from_category, from_name, to_name)
+                    } else if from_name == to_name {
+                        vault.move_sub_category(from_name, from_category, to_category)
+                    } else {
+                        return Err("Moving and renaming a subcategory at once is not supported"
+                            .to_string());
+                    }
+                }
+                _ => {
+                    return Err(
+                        "Both paths must be categories or both must be subcategories".to_string(),
+                    )
+                }
+            };
+
+            result.map_err(|err| err.to_string())?;
+            save_vault(&mut vault)
+        }
+        "seal" => {
+            let [category] = args else {
+                return Err(USAGE.to_string());
+            };
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+
+            let mut password = read_new_password(&format!("Password of category {}", category))?;
+            let result = vault.seal_category(category, &password);
+            password.zeroize();
+
+            result.map_err(|err| err.to_string())?;
+            save_vault(&mut vault)
+        }
+        "unseal" => {
+            let [category] = args else {
+                return Err(USAGE.to_string());
+            };
+
+            let mut vault = open_vault(settings, &cli_args.password_source)?;
+            unlock_category(&mut vault, category)?;
+
+            vault
+                .unseal_category(category)
+                .map_err(|err| err.to_string())?;
+            save_vault(&mut vault)
+        }
+        "export" => export::export(settings, &cli_args.password_source, args),
+        "export-bundle" => export_bundle::export_bundle(settings, &cli_args.password_source, args),
+        "export-pass" => export_pass::export_pass(settings, &cli_args.password_source, args),
+        "import" => import::import(settings, &cli_args.password_source, args),
+        "identity" => share::identity(settings, &cli_args.password_source, args),
+        "contacts" => share::contacts(args),
+        "share" => share::share(settings, &cli_args.password_source, args),
+        "merge" => merge::merge(settings, &cli_args.password_source, args),
+        "sync" => sync::sync(settings, &cli_args.password_source, args),
+        "serve" => serve::serve(settings, args),
+        "agent" => agent::agent(settings, args),
+        "api" => api::api(args),
+        "team" => team::team(settings, &cli_args.password_source, args),
+        "render" => render::render(settings, &cli_args.password_source, args),
+        "run" => {
+            let exit_code = run::run_with_secrets(settings, &cli_args.password_source, args)?;
+            std::process::exit(exit_code);
+        }
+        command => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+/// Splits `category/subcategory` at the first slash
+pub fn parse_entry_path(path: &str) -> Result<(String, String), String> {
+    match path.split_once('/') {
+        Some((category, sub_category)) if !category.is_empty() && !sub_category.is_empty() => {
+            Ok((category.to_string(), sub_category.to_string()))
+        }
+        _ => Err(format!(
+            "Invalid path: {}. Expected <category>/<subcategory>",
+            path
+        )),
+    }
+}
+
+fn read_secret_text() -> Result<String, String> {
+    if std::io::stdin().is_terminal() {
+        return rpassword::prompt_password("Secret: ")
+            .map_err(|err| format!("Can not read the secret from TTY. Err: {}", err));
+    }
+
+    read_stdin()
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|err| format!("Can not read stdin. Err: {}", err))?;
+
+    if text.ends_with('\n') {
+        text.pop();
+    }
+
+    Ok(text)
+}
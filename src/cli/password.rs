@@ -0,0 +1,88 @@
+use std::io::Read;
+
+use zeroize::Zeroize;
+
+const PASSWORD_CAPACITY: usize = 1024;
+
+pub enum PasswordSource {
+    Tty,
+    FileDescriptor(i32),
+}
+
+pub fn read_password(source: &PasswordSource) -> Result<String, String> {
+    let password = match source {
+        PasswordSource::Tty => rpassword::prompt_password("Master password: ")
+            .map_err(|err| format!("Can not read password from TTY. Err: {}", err))?,
+        PasswordSource::FileDescriptor(fd) => read_password_from_fd(*fd)?,
+    };
+
+    if password.is_empty() {
+        return Err("Password can not be empty".to_string());
+    }
+
+    Ok(password)
+}
+
+/// Asks a new password twice on the TTY
+pub fn read_new_password(name: &str) -> Result<String, String> {
+    let password = rpassword::prompt_password(format!("{}: ", name))
+        .map_err(|err| format!("Can not read password from TTY. Err: {}", err))?;
+
+    let mut confirmation = rpassword::prompt_password(format!("{} (repeat): ", name))
+        .map_err(|err| format!("Can not read password from TTY. Err: {}", err))?;
+
+    let matches = password == confirmation;
+    confirmation.zeroize();
+
+    if !matches {
+        let mut password = password;
+        password.zeroize();
+        return Err("Passwords do not match".to_string());
+    }
+
+    if password.is_empty() {
+        return Err("Password can not be empty".to_string());
+    }
+
+    Ok(password)
+}
+
+/// Reads the first line only. The descriptor belongs to the caller and stays open
+fn read_password_from_fd(fd: i32) -> Result<String, String> {
+    use std::os::unix::io::FromRawFd;
+
+    let mut file = std::mem::ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(fd) });
+
+    // Byte by byte, so nothing after the line is consumed. Never grown to not leave copies behind
+    let mut line = Vec::with_capacity(PASSWORD_CAPACITY);
+    let mut byte = [0u8; 1];
+
+    loop {
+        match file.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) if byte[0] == b'\n' => break,
+            Ok(_) if line.len() == PASSWORD_CAPACITY => {
+                line.zeroize();
+                return Err(format!("Password from fd {} is too long", fd));
+            }
+            Ok(_) => line.push(byte[0]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                line.zeroize();
+                return Err(format!(
+                    "Can not read password from fd {}. Err: {}",
+                    fd, err
+                ));
+            }
+        }
+    }
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    String::from_utf8(line).map_err(|err| {
+        err.into_bytes().zeroize();
+        format!("Password from fd {} is not valid UTF-8", fd)
+    })
+}
@@ -0,0 +1,88 @@
+use std::io::Write;
+
+use my_secrets_core::template::{self, TemplateFormat};
+use zeroize::Zeroize;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str =
+    "Usage: my-secrets render [--format env|yaml|json|toml|raw] <template> (use - for stdin)";
+
+/// Renders the template with secrets from the vault to stdout
+pub fn render(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let (format, template_file) = match args {
+        [template_file] => (None, template_file),
+        [flag, format, template_file] | [template_file, flag, format] if flag == "--format" => {
+            let format = TemplateFormat::from_name(format)
+                .ok_or_else(|| format!("Unknown format: {}\n{}", format, USAGE))?;
+            (Some(format), template_file)
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let format = match format {
+        Some(format) => format,
+        None => TemplateFormat::from_file_name(template_file).ok_or_else(|| {
+            format!(
+                "Can not detect format of {}. Please specify --format",
+                template_file
+            )
+        })?,
+    };
+
+    let template = if template_file == "-" {
+        super::read_stdin()?
+    } else {
+        std::fs::read_to_string(template_file)
+            .map_err(|err| format!("Can not read template {}. Err: {}", template_file, err))?
+    };
+
+    let mut vault = super::open_vault(settings, password_source)?;
+
+    for category in template::get_categories(&template) {
+        super::unlock_category(&mut vault, &category)?;
+    }
+
+    let mut rendered =
+        template::render_template(&vault, &template, format).map_err(|err| err.to_string())?;
+    drop(vault);
+
+    // Placeholders of another template engine make it not a valid document yet
+    let validation_result = if template::has_other_placeholders(&template) {
+        Ok(())
+    } else {
+        validate(&rendered, format)
+    };
+
+    if validation_result.is_ok() {
+        let mut stdout = std::io::stdout();
+        let write_result = stdout
+            .write_all(rendered.as_bytes())
+            .and_then(|_| stdout.flush());
+
+        rendered.zeroize();
+        return write_result.map_err(|err| format!("Can not write to stdout. Err: {}", err));
+    }
+
+    rendered.zeroize();
+    validation_result
+}
+
+/// Makes sure the template together with the substituted values is still a valid document
+fn validate(rendered: &str, format: TemplateFormat) -> Result<(), String> {
+    match format {
+        TemplateFormat::Json => serde_json::from_str::<serde_json::Value>(rendered)
+            .map(|_| ())
+            .map_err(|err| format!("Rendered template is not a valid JSON. Err: {}", err)),
+        TemplateFormat::Yaml => serde_yaml::from_str::<serde_yaml::Value>(rendered)
+            .map(|_| ())
+            .map_err(|err| format!("Rendered template is not a valid YAML. Err: {}", err)),
+        TemplateFormat::Env | TemplateFormat::Toml | TemplateFormat::Raw => Ok(()),
+    }
+}
@@ -0,0 +1,222 @@
+use std::{collections::HashMap, process::Command};
+
+use my_secrets_core::{fields, Vault};
+use zeroize::Zeroize;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets run [--env <NAME>=<category>/<subcategory>[#<field>]]... [--env-from <category>[/<subcategory>]]... -- <command> [args]";
+
+enum EnvSource {
+    /// Single variable from the whole text of an entry or from one of its fields
+    Entry {
+        name: String,
+        category: String,
+        sub_category: String,
+        field: Option<String>,
+    },
+    /// Every field of the entry becomes a variable named by [`fields::to_env_name`]
+    EntryFields {
+        category: String,
+        sub_category: String,
+    },
+    /// Every entry of the category becomes a variable
+    Category(String),
+}
+
+impl EnvSource {
+    fn get_category(&self) -> &str {
+        match self {
+            Self::Entry { category, .. } => category,
+            Self::EntryFields { category, .. } => category,
+            Self::Category(category) => category,
+        }
+    }
+}
+
+/// Runs the command with secrets injected into its environment only.
+/// Returns the exit code of the command
+pub fn run_with_secrets(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<i32, String> {
+    let (sources, command) = parse_args(args)?;
+
+    let mut vault = super::open_vault(settings, password_source)?;
+
+    for source in &sources {
+        super::unlock_category(&mut vault, source.get_category())?;
+    }
+
+    let mut env_vars = resolve_env_vars(&vault, &sources)?;
+    drop(vault);
+
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .envs(env_vars.iter().map(|(name, value)| (name, value)))
+        .status();
+
+    for (_, value) in env_vars.iter_mut() {
+        value.zeroize();
+    }
+
+    let status = status.map_err(|err| format!("Can not run {}. Err: {}", command[0], err))?;
+
+    if let Some(code) = status.code() {
+        return Ok(code);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return Ok(128 + signal);
+        }
+    }
+
+    Ok(1)
+}
+
+fn parse_args(args: &[String]) -> Result<(Vec<EnvSource>, &[String]), String> {
+    let mut sources = Vec::new();
+    let mut index = 0;
+
+    while index < args.len() {
+        match args[index].as_str() {
+            "--env" => {
+                let value = args.get(index + 1).ok_or_else(|| USAGE.to_string())?;
+                sources.push(parse_env(value)?);
+                index += 2;
+            }
+            "--env-from" => {
+                let value = args.get(index + 1).ok_or_else(|| USAGE.to_string())?;
+                sources.push(match value.split_once('/') {
+                    Some((category, sub_category)) => EnvSource::EntryFields {
+                        category: category.to_string(),
+                        sub_category: sub_category.to_string(),
+                    },
+                    None => EnvSource::Category(value.to_string()),
+                });
+                index += 2;
+            }
+            "--" => {
+                index += 1;
+                break;
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let command = &args[index..];
+
+    if command.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    Ok((sources, command))
+}
+
+fn parse_env(value: &str) -> Result<EnvSource, String> {
+    let (name, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid --env value: {}. Expected NAME=<path>", value))?;
+
+    if name.is_empty() {
+        return Err(format!(
+            "Invalid --env value: {}. Empty variable name",
+            value
+        ));
+    }
+
+    let (path, field) = match path.split_once('#') {
+        Some((path, field)) => (path, Some(field.to_string())),
+        None => (path, None),
+    };
+
+    let (category, sub_category) = super::parse_entry_path(path)?;
+
+    Ok(EnvSource::Entry {
+        name: name.to_string(),
+        category,
+        sub_category,
+        field,
+    })
+}
+
+fn resolve_env_vars(vault: &Vault, sources: &[EnvSource]) -> Result<Vec<(String, String)>, String> {
+    let mut result = Vec::new();
+    // Keys of --env-from by the variable they become, as `db-password` and `db_password` clash
+    let mut named_keys = HashMap::new();
+
+    for source in sources {
+        match source {
+            EnvSource::Entry {
+                name,
+                category,
+                sub_category,
+                field,
+            } => {
+                let value = match field {
+                    Some(field) => vault.get_field(category, sub_category, field),
+                    None => vault.get(category, sub_category),
+                }
+                .map_err(|err| err.to_string())?;
+
+                result.push((name.to_string(), value.to_string()));
+            }
+            EnvSource::EntryFields {
+                category,
+                sub_category,
+            } => {
+                let text = vault
+                    .get(category, sub_category)
+                    .map_err(|err| err.to_string())?;
+
+                for (name, value) in fields::parse_fields(text) {
+                    let env_name = fields::to_env_name(name);
+                    let key = format!("{}/{}#{}", category, sub_category, name);
+                    add_named_key(&mut named_keys, &env_name, key)?;
+
+                    result.push((env_name, value.to_string()));
+                }
+            }
+            EnvSource::Category(category) => {
+                for sub_category in vault
+                    .list_sub_categories(category)
+                    .map_err(|err| err.to_string())?
+                {
+                    let value = vault
+                        .get(category, sub_category)
+                        .map_err(|err| err.to_string())?;
+
+                    let env_name = fields::to_env_name(sub_category);
+                    let key = format!("{}/{}", category, sub_category);
+                    add_named_key(&mut named_keys, &env_name, key)?;
+
+                    result.push((env_name, value.to_string()));
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn add_named_key(
+    named_keys: &mut HashMap<String, String>,
+    env_name: &str,
+    key: String,
+) -> Result<(), String> {
+    if let Some(other_key) = named_keys.get(env_name) {
+        return Err(format!(
+            "Both {} and {} become the variable {}",
+            other_key, key, env_name
+        ));
+    }
+
+    named_keys.insert(env_name.to_string(), key);
+    Ok(())
+}
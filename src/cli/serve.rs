@@ -0,0 +1,54 @@
+use my_secrets_core::sync_server::{SyncServer, DEFAULT_LISTEN};
+
+use crate::settings::SettingsModel;
+
+const USAGE: &str = "Usage: my-secrets serve [--listen <address>] [--file <path>]
+
+Serves the encrypted vault file over HTTP for my-secrets sync on other machines.
+The server only stores the encrypted file and never needs the password.
+--listen defaults to 127.0.0.1:7878, --file to ~/.my-secrets/server/.my-secrets-data.
+Requests need sync_server: token: from ~/.my-secrets/.my-secrets.yaml if it is set.";
+
+const DEFAULT_SERVER_FILE_NAME: &str = "server/.my-secrets-data";
+
+pub fn serve(settings: &SettingsModel, args: &[String]) -> Result<(), String> {
+    let mut listen = DEFAULT_LISTEN.to_string();
+    let mut file_name = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| USAGE.to_string())?;
+
+        match arg.as_str() {
+            "--listen" => listen = value.to_string(),
+            "--file" => file_name = Some(value.to_string()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let file_name = file_name
+        .unwrap_or_else(|| my_secrets_core::file::compile_full_filename(DEFAULT_SERVER_FILE_NAME));
+
+    let token = settings
+        .sync_server
+        .as_ref()
+        .and_then(|sync_server| sync_server.token.clone());
+
+    if token.is_none() && !is_loopback(&listen) {
+        eprintln!(
+            "Warning: anyone reaching {} can replace the vault file. Set sync_server: token: in the settings",
+            listen
+        );
+    }
+
+    println!("Serving {} on http://{}", file_name, listen);
+
+    SyncServer::new(file_name, token)
+        .serve(&listen)
+        .map_err(|err| format!("Can not serve on {}. Err: {}", listen, err))
+}
+
+fn is_loopback(listen: &str) -> bool {
+    listen.starts_with("127.") || listen.starts_with("localhost:") || listen.starts_with("[::1]:")
+}
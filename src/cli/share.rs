@@ -0,0 +1,140 @@
+use my_secrets_core::{address_book::AddressBook, sharing, VaultCommand};
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const IDENTITY_USAGE: &str = "Usage: my-secrets identity [--create]
+
+Prints the public key of the vault identity. --create generates the identity if the vault has none.";
+
+const CONTACTS_USAGE: &str = "Usage: my-secrets contacts [add <name> <public key> | rm <name>]";
+
+const SHARE_USAGE: &str = "Usage: my-secrets share --to <name|public key>... [--output <file>] <category>[/<subcategory>]...
+
+Encrypts the entries to the recipients as ASCII armored age file written to stdout unless --output is given.
+The recipient imports it with: my-secrets import shared <file>";
+
+pub fn identity(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let create = match args {
+        [] => false,
+        [arg] if arg == "--create" => true,
+        _ => return Err(IDENTITY_USAGE.to_string()),
+    };
+
+    let mut vault = super::open_vault(settings, password_source)?;
+
+    let has_identity = sharing::has_identity(vault.content().map_err(|err| err.to_string())?);
+
+    if !has_identity {
+        if !create {
+            return Err(
+                "The vault has no identity. Create it with: my-secrets identity --create"
+                    .to_string(),
+            );
+        }
+
+        let text = sharing::generate_identity_text();
+
+        let command = if vault
+            .list()
+            .map_err(|err| err.to_string())?
+            .contains(&sharing::IDENTITY_CATEGORY)
+        {
+            VaultCommand::CreateSubCategory {
+                category: sharing::IDENTITY_CATEGORY.to_string(),
+                name: sharing::IDENTITY_SUB_CATEGORY.to_string(),
+                text,
+            }
+        } else {
+            VaultCommand::CreateCategory {
+                name: sharing::IDENTITY_CATEGORY.to_string(),
+                sub_categories: [(sharing::IDENTITY_SUB_CATEGORY.to_string(), text)].into(),
+            }
+        };
+
+        vault.apply(&command).map_err(|err| err.to_string())?;
+        super::save_vault(&mut vault)?;
+    }
+
+    let public_key = sharing::get_public_key(vault.content().map_err(|err| err.to_string())?)
+        .map_err(|err| err.to_string())?;
+
+    println!("{}", public_key);
+    Ok(())
+}
+
+pub fn contacts(args: &[String]) -> Result<(), String> {
+    let mut address_book = AddressBook::load().map_err(|err| err.to_string())?;
+
+    match args {
+        [] => {
+            for (name, public_key) in &address_book.contacts {
+                println!("{}\t{}", name, public_key);
+            }
+            Ok(())
+        }
+        [command, name, public_key] if command == "add" => {
+            address_book
+                .add(name, public_key)
+                .map_err(|err| err.to_string())?;
+            address_book.save().map_err(|err| err.to_string())
+        }
+        [command, name] if command == "rm" => {
+            if !address_book.remove(name) {
+                return Err(format!("Contact {} not found", name));
+            }
+            address_book.save().map_err(|err| err.to_string())
+        }
+        _ => Err(CONTACTS_USAGE.to_string()),
+    }
+}
+
+pub fn share(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let mut recipients = Vec::new();
+    let mut paths = Vec::new();
+    let mut output = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => recipients.push(next_value(&mut args)?.to_string()),
+            "--output" => output = Some(next_value(&mut args)?),
+            _ if arg.starts_with("--") => return Err(SHARE_USAGE.to_string()),
+            _ => paths.push(arg.to_string()),
+        }
+    }
+
+    if recipients.is_empty() || paths.is_empty() {
+        return Err(SHARE_USAGE.to_string());
+    }
+
+    let address_book = AddressBook::load().map_err(|err| err.to_string())?;
+
+    let vault = super::open_vault(settings, password_source)?;
+
+    let shared = sharing::share_entries(&vault, &paths, &recipients, &address_book)
+        .map_err(|err| err.to_string())?;
+
+    match output {
+        Some(output) => my_secrets_core::file::write_private_file(output, shared.as_bytes())
+            .map_err(|err| format!("Can not write file {}. Err: {}", output, err)),
+        None => {
+            print!("{}", shared);
+            Ok(())
+        }
+    }
+}
+
+fn next_value<'s>(args: &mut impl Iterator<Item = &'s String>) -> Result<&'s String, String> {
+    args.next().ok_or_else(|| SHARE_USAGE.to_string())
+}
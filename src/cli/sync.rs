@@ -0,0 +1,129 @@
+use my_secrets_core::{
+    git_sync::{GitSync, PullResult},
+    merge::{MergeChoice, MergeResult},
+    Vault,
+};
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets sync [pull [--ours | --theirs] | push]
+
+Pulls the vault from the git remote or the sync server configured in ~/.my-secrets/.my-secrets.yaml
+merging it entry by entry and pushes it back. Entries changed on both sides fail the pull
+unless --ours or --theirs is given.";
+
+pub fn sync(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    let (pull, push, choice) = match args {
+        [] => (true, true, None),
+        [command] if command == "pull" => (true, false, None),
+        [command, side] if command == "pull" && side == "--ours" => {
+            (true, false, Some(MergeChoice::Ours))
+        }
+        [command, side] if command == "pull" && side == "--theirs" => {
+            (true, false, Some(MergeChoice::Theirs))
+        }
+        [command] if command == "push" => (false, true, None),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut vault = super::open_vault(settings, password_source)?;
+
+    if get_git_remote(&vault).is_none() && vault.get_server_sync().is_none() {
+        return Err("Sync is not configured. Add git: remote: <url> \
+            or sync_server: url: <url> to the settings"
+            .to_string());
+    }
+
+    if pull {
+        pull_vault(&mut vault, choice)?;
+    }
+
+    if push {
+        let result = match (get_git_remote(&vault), vault.get_server_sync()) {
+            (Some(git_sync), _) => git_sync.push(),
+            (None, Some(server_sync)) => server_sync.push(),
+            (None, None) => Ok(()),
+        };
+
+        result.map_err(|err| err.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn pull_vault(vault: &mut Vault, choice: Option<MergeChoice>) -> Result<(), String> {
+    let pull_result = match get_git_remote(vault) {
+        Some(git_sync) => git_sync.pull(),
+        None => vault.pull_from_server(),
+    };
+
+    let pull_result = pull_result.map_err(|err| err.to_string())?;
+
+    let (base, theirs) = match pull_result {
+        PullResult::UpToDate | PullResult::FastForwarded => return Ok(()),
+        PullResult::Diverged { base, theirs } => (base, theirs),
+    };
+
+    let mut result = vault
+        .merge_version(base.as_deref(), &theirs)
+        .map_err(|err| err.to_string())?;
+
+    let choice = match choice {
+        Some(choice) => choice,
+        None if result.conflicts.is_empty() => MergeChoice::Ours,
+        None => {
+            return Err(format_conflicts(
+                &result,
+                "Pull again with --ours or --theirs, or resolve them in the app",
+            ))
+        }
+    };
+
+    let (content, ids) = result.resolve(&vec![choice; result.conflicts.len()]);
+
+    if let Some(git_sync) = get_git_remote(vault) {
+        git_sync.begin_merge().map_err(|err| err.to_string())?;
+    }
+
+    let save_result = vault
+        .replace_merged_content(content, ids)
+        .and_then(|_| vault.save());
+
+    if let Err(err) = save_result {
+        if let Some(git_sync) = get_git_remote(vault) {
+            let _ = git_sync.abort_merge();
+        }
+        return Err(err.to_string());
+    }
+
+    super::warn_about_commit_error(vault);
+
+    match vault.get_server_sync() {
+        Some(server_sync) => server_sync.complete_merge().map_err(|err| err.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Git sync with a remote to pull from and push to. The sync server is used otherwise
+fn get_git_remote(vault: &Vault) -> Option<&GitSync> {
+    vault
+        .get_git_sync()
+        .filter(|git_sync| git_sync.remote.is_some())
+}
+
+pub fn format_conflicts(result: &MergeResult, hint: &str) -> String {
+    let mut message = "Entries changed on both sides:\n".to_string();
+
+    for conflict in &result.conflicts {
+        message.push_str(&format!("    {}\n", conflict.get_path()));
+    }
+
+    message.push_str(hint);
+    message
+}
@@ -0,0 +1,88 @@
+use my_secrets_core::team::MemberKey;
+use zeroize::Zeroize;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+const USAGE: &str = "Usage: my-secrets team <command>
+
+Commands:
+    init <name>                                   Convert the vault to a team vault with you as the first member
+    members                                       List members of the team vault
+    add <name> (--password | --public-key <key>)  Add a member unlocking with a password or an age identity
+    rm <name>                                     Remove a member. The vault gets a new data key
+
+Members with a public key unlock the vault with their AGE-SECRET-KEY-1... identity as the master password.";
+
+pub fn team(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+    args: &[String],
+) -> Result<(), String> {
+    match args {
+        [command, name] if command == "init" => {
+            let mut vault = super::open_vault(settings, password_source)?;
+
+            let mut password = super::read_new_password(&format!("Password of {}", name))?;
+            let result = vault.convert_to_team(name, &password);
+            password.zeroize();
+
+            result.map_err(|err| err.to_string())?;
+            super::save_vault(&mut vault)
+        }
+        [command] if command == "members" => {
+            let vault = super::open_vault(settings, password_source)?;
+
+            let member_name = vault
+                .get_team_member_name()
+                .map_err(|err| err.to_string())?;
+
+            for member in vault.get_team_members().map_err(|err| err.to_string())? {
+                println!(
+                    "{}\t{}\t{}{}",
+                    member.name,
+                    if member.is_password_member() {
+                        "password"
+                    } else {
+                        "public key"
+                    },
+                    member.public_key,
+                    if member.name == member_name {
+                        "\t(you)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            Ok(())
+        }
+        [command, name, key] if command == "add" && key == "--password" => {
+            let mut vault = super::open_vault(settings, password_source)?;
+
+            let mut password = super::read_new_password(&format!("Password of {}", name))?;
+            let result = vault.add_team_member(name, MemberKey::Password(&password));
+            password.zeroize();
+
+            result.map_err(|err| err.to_string())?;
+            super::save_vault(&mut vault)
+        }
+        [command, name, key, public_key] if command == "add" && key == "--public-key" => {
+            let mut vault = super::open_vault(settings, password_source)?;
+
+            vault
+                .add_team_member(name, MemberKey::PublicKey(public_key))
+                .map_err(|err| err.to_string())?;
+            super::save_vault(&mut vault)
+        }
+        [command, name] if command == "rm" => {
+            let mut vault = super::open_vault(settings, password_source)?;
+
+            vault
+                .remove_team_member(name)
+                .map_err(|err| err.to_string())?;
+            super::save_vault(&mut vault)
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
@@ -0,0 +1,78 @@
+use my_secrets_core::{Vault, VaultError};
+use zeroize::Zeroize;
+
+use crate::settings::SettingsModel;
+
+use super::PasswordSource;
+
+/// Opens the default vault and unlocks it with the key of the unlock agent, or with the master password
+/// from the given source which then goes to the agent. The vault is read-only while another process has it open
+pub fn open_vault(
+    settings: &SettingsModel,
+    password_source: &PasswordSource,
+) -> Result<Vault, String> {
+    let mut vault = Vault::open_default(settings.get_iv());
+    vault.set_git_sync(settings.create_git_sync(vault.get_file_name()));
+    vault.set_server_sync(settings.create_server_sync(vault.get_file_name()));
+
+    if let Some(storage) = settings.create_storage(vault.get_file_name()) {
+        vault.set_storage(storage);
+    }
+
+    match vault.acquire_file_lock() {
+        Ok(()) => {}
+        Err(VaultError::FileLocked(owner)) => {
+            eprintln!(
+                "{}. Opened read-only",
+                VaultError::FileLocked(owner.clone())
+            );
+            vault.open_read_only(owner);
+        }
+        Err(err) => return Err(format!("Can not lock the vault. Err: {}", err)),
+    }
+
+    if crate::unlock_agent::unlock_from_agent(&mut vault, settings) {
+        return Ok(vault);
+    }
+
+    let mut password = super::read_password(password_source)?;
+    let unlock_result = vault.unlock(&password);
+    password.zeroize();
+
+    unlock_result.map_err(|err| err.to_string())?;
+
+    crate::unlock_agent::add_key_to_agent(&vault);
+
+    Ok(vault)
+}
+
+/// Saves the vault. A failed git commit does not fail the save
+pub fn save_vault(vault: &mut Vault) -> Result<(), String> {
+    vault.save().map_err(|err| err.to_string())?;
+    warn_about_commit_error(vault);
+    Ok(())
+}
+
+pub fn warn_about_commit_error(vault: &mut Vault) {
+    if let Some(err) = vault.take_commit_error() {
+        eprintln!(
+            "Warning: the vault is saved but not committed. Err: {}",
+            err
+        );
+    }
+}
+
+/// Asks the password of the sealed category on the TTY if the category is locked
+pub fn unlock_category(vault: &mut Vault, category: &str) -> Result<(), String> {
+    if !vault.is_category_locked(category) {
+        return Ok(());
+    }
+
+    let mut password = rpassword::prompt_password(format!("Password of category {}: ", category))
+        .map_err(|err| format!("Can not read password from TTY. Err: {}", err))?;
+
+    let unlock_result = vault.unlock_category(category, &password);
+    password.zeroize();
+
+    unlock_result.map_err(|err| err.to_string())
+}
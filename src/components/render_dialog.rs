@@ -1,6 +1,6 @@
-use std::collections::BTreeMap;
-
-use encryption::aes::AesKey;
+use my_secrets_core::{
+    address_book::AddressBook, entry_ids::EntryIds, import::ImportPlan, sharing, TypeContent,
+};
 
 use crate::{states::*, MyApp};
 
@@ -14,10 +14,17 @@ pub enum ShowDialogResult {
     CreatedCategory(String),
     RenameCategory(String),
     CreatedSubCategory(String),
-    Authenticated {
-        aes_key: AesKey,
-        data: BTreeMap<String, BTreeMap<String, String>>,
-    },
+    RenameSubCategory(String),
+    MoveSubCategory(String),
+    Export(ExportState),
+    Import(ImportPlan),
+    ExportBundle(BundleExportState),
+    CreateIdentity,
+    CategoryUnlocked(String),
+    /// Sealed in memory, the vault still has to be saved
+    CategorySealed(String),
+    Merged(MergeSource, TypeContent, EntryIds),
+    Authenticated,
     Cancel,
 }
 
@@ -29,29 +36,22 @@ impl MyApp {
 
         match modal_dialog {
             ModalWindowState::None => {
-                if self.edit_state.activity_expired() {
-                    self.authenticated = None;
-                    self.modal_dialog
-                        .set(ModalWindowState::Authenticate(Default::default()));
-                }
-
                 return None;
             }
             ModalWindowState::Authenticate(state) => {
                 if state.render(ctx) {
-                    let password =
-                        crate::password_utils::make_password_complient(state.password.as_bytes());
-
-                    let aes_key = AesKey {
-                        key: password,
-                        iv: self.settings.get_iv(),
-                    };
-
-                    if let Some(data) = crate::file::load_file(&aes_key) {
-                        return Some(ShowDialogResult::Authenticated { aes_key, data });
-                    } else {
-                        state.error_message = "Invalid password".to_string().into();
-                        return Some(ShowDialogResult::DialogIsBeingRendered);
+                    let unlock_result = self.vault.unlock(&state.password);
+                    state.wipe_password();
+
+                    match unlock_result {
+                        Ok(()) => {
+                            crate::unlock_agent::add_key_to_agent(&self.vault);
+                            return Some(ShowDialogResult::Authenticated);
+                        }
+                        Err(err) => {
+                            state.error_message = err.to_string().into();
+                            return Some(ShowDialogResult::DialogIsBeingRendered);
+                        }
                     }
                 }
                 return Some(ShowDialogResult::DialogIsBeingRendered);
@@ -62,12 +62,6 @@ impl MyApp {
                 {
                     match dialog_result {
                         DialogResult::Ok => {
-                            self.authenticated
-                                .as_mut()
-                                .unwrap()
-                                .content
-                                .insert(category.to_string(), BTreeMap::new());
-                            self.has_not_saved_data = true;
                             return Some(ShowDialogResult::CreatedCategory(category.to_string()));
                         }
 
@@ -100,15 +94,6 @@ impl MyApp {
                 {
                     match dialog_result {
                         DialogResult::Ok => {
-                            self.authenticated
-                                .as_mut()
-                                .unwrap()
-                                .content
-                                .get_mut(self.selected_category.as_ref().unwrap())
-                                .unwrap()
-                                .insert(sub_category.to_string(), "".to_string());
-
-                            self.has_not_saved_data = true;
                             return Some(ShowDialogResult::CreatedSubCategory(
                                 sub_category.to_string(),
                             ));
@@ -121,6 +106,194 @@ impl MyApp {
                 }
                 return Some(ShowDialogResult::DialogIsBeingRendered);
             }
+            ModalWindowState::RenameSubCategory(sub_category) => {
+                if let Some(dialog_result) =
+                    render_edit_modal(ctx, "Enter subcategory name:", "Rename", sub_category)
+                {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            return Some(ShowDialogResult::RenameSubCategory(
+                                sub_category.to_string(),
+                            ));
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::Export(state) => {
+                if let Some(dialog_result) = state.render(ctx) {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            return Some(ShowDialogResult::Export(state.clone()));
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::Import(state) => {
+                let content = self.vault.content().unwrap();
+                if let Some(dialog_result) = state.render(ctx, content) {
+                    match dialog_result {
+                        DialogResult::Ok => match state.plan.take() {
+                            Some(plan) => return Some(ShowDialogResult::Import(plan)),
+                            None => state.read_database(content),
+                        },
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::ExportBundle(state) => {
+                if let Some(dialog_result) = state.render(ctx) {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            return Some(ShowDialogResult::ExportBundle(state.clone()));
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::Share(state) => {
+                if let Some(dialog_result) = state.render(ctx) {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            let recipients = state.get_recipients();
+                            let result = AddressBook::load().and_then(|address_book| {
+                                sharing::share_entries(
+                                    &self.vault,
+                                    &state.paths,
+                                    &recipients,
+                                    &address_book,
+                                )
+                            });
+
+                            match result {
+                                Ok(shared) => {
+                                    state.error_message = None;
+                                    state.shared = Some(shared);
+                                }
+                                Err(err) => {
+                                    state.error_message = Some(err.to_string());
+                                }
+                            }
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::Contacts(state) => {
+                match state.render(ctx) {
+                    Some(ContactsAction::CreateIdentity) => {
+                        return Some(ShowDialogResult::CreateIdentity);
+                    }
+                    Some(ContactsAction::Close) => {
+                        return Some(ShowDialogResult::Cancel);
+                    }
+                    None => {}
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::Members(state) => {
+                match state.render(ctx) {
+                    Some(MembersAction::Close) => {
+                        return Some(ShowDialogResult::Cancel);
+                    }
+                    Some(action) => state.apply(&mut self.vault, action),
+                    None => {}
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::CategoryPassword(state) => {
+                if let Some(dialog_result) = state.render(ctx) {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            let result = if state.sealing {
+                                self.vault.seal_category(&state.category, &state.password)
+                            } else {
+                                self.vault.unlock_category(&state.category, &state.password)
+                            };
+                            state.wipe_password();
+
+                            match result {
+                                Ok(()) if state.sealing => {
+                                    return Some(ShowDialogResult::CategorySealed(
+                                        state.category.clone(),
+                                    ));
+                                }
+                                Ok(()) => {
+                                    return Some(ShowDialogResult::CategoryUnlocked(
+                                        state.category.clone(),
+                                    ));
+                                }
+                                Err(err) => {
+                                    state.error_message = Some(err.to_string());
+                                }
+                            }
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::Merge(state) => {
+                if let Some(dialog_result) = state.render(ctx) {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            let (content, ids) = state.resolve();
+                            return Some(ShowDialogResult::Merged(state.source, content, ids));
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
+            ModalWindowState::MoveSubCategory(to_category) => {
+                let categories: Vec<String> = self
+                    .vault
+                    .list()
+                    .unwrap()
+                    .into_iter()
+                    .map(|category| category.to_string())
+                    .collect();
+
+                if let Some(dialog_result) = render_select_modal(
+                    ctx,
+                    "Move subcategory to category:",
+                    "Move",
+                    &categories,
+                    to_category,
+                ) {
+                    match dialog_result {
+                        DialogResult::Ok => {
+                            return Some(ShowDialogResult::MoveSubCategory(
+                                to_category.to_string(),
+                            ));
+                        }
+                        DialogResult::Cancel => {
+                            return Some(ShowDialogResult::Cancel);
+                        }
+                    }
+                }
+                return Some(ShowDialogResult::DialogIsBeingRendered);
+            }
         }
     }
 }
@@ -159,3 +332,45 @@ fn render_edit_modal(
 
     result
 }
+
+fn render_select_modal(
+    ctx: &egui::Context,
+    title: &str,
+    ok_btn: &str,
+    options: &[String],
+    value: &mut String,
+) -> Option<DialogResult> {
+    let mut result = None;
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.set_width(400.0);
+
+            ui.heading(title);
+            ui.group(|ui| {
+                ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                ui.vertical_centered_justified(|ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("select_modal")
+                            .selected_text(value.as_str())
+                            .show_ui(ui, |ui| {
+                                for option in options {
+                                    ui.selectable_value(value, option.to_string(), option);
+                                }
+                            });
+
+                        if ui.button(ok_btn).clicked() {
+                            result = Some(DialogResult::Ok);
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            result = Some(DialogResult::Cancel);
+                        }
+                    });
+                });
+            });
+        })
+    });
+
+    result
+}
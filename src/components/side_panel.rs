@@ -1,22 +1,41 @@
 use crate::MyApp;
 
+const DIRTY_MARK: &str = " ●";
+const LOCKED_MARK: &str = "🔒 ";
+
 pub enum SizePanelEvent {
     CategorySelected(String),
+    LockedCategorySelected(String),
     SubCategorySelected(Option<String>),
 }
 
 pub fn render(app: &mut MyApp, ui: &mut egui::Ui) -> Option<SizePanelEvent> {
     let mut result = None;
 
-    let is_editing = app.edit_state.is_editing();
-
-    let authenticated = app.authenticated.as_ref()?;
+    let content = app.vault.content().ok()?;
 
-    for (category, sub_categories) in &authenticated.content {
+    for (category, sub_categories) in content {
         //   ui.set_style(app.category_style.clone());
 
+        // Sealed category stays encrypted until it is unlocked with its own password
+        if app.vault.is_category_locked(category) {
+            ui.vertical_centered_justified(|ui| {
+                let text = egui::RichText::new(format!("{}{}", LOCKED_MARK, category));
+                let widget_text = egui::WidgetText::RichText(text).monospace().heading();
+
+                if ui.selectable_label(false, widget_text).clicked() {
+                    result = Some(SizePanelEvent::LockedCategorySelected(category.to_string()));
+                }
+            });
+            continue;
+        }
+
         ui.vertical_centered_justified(|ui| {
-            let text = egui::RichText::new(category);
+            let text = if app.changes.is_category_dirty(category) {
+                egui::RichText::new(format!("{}{}", category, DIRTY_MARK))
+            } else {
+                egui::RichText::new(category)
+            };
             let widget_text = egui::WidgetText::RichText(text).monospace().heading();
 
             let checked = if let Some(selected_category) = &app.selected_category {
@@ -27,10 +46,8 @@ pub fn render(app: &mut MyApp, ui: &mut egui::Ui) -> Option<SizePanelEvent> {
 
             let response = ui.selectable_label(checked, widget_text);
 
-            if !is_editing {
-                if response.clicked() {
-                    result = Some(SizePanelEvent::CategorySelected(category.to_string()));
-                }
+            if response.clicked() {
+                result = Some(SizePanelEvent::CategorySelected(category.to_string()));
             }
         });
 
@@ -49,14 +66,20 @@ pub fn render(app: &mut MyApp, ui: &mut egui::Ui) -> Option<SizePanelEvent> {
                                     false
                                 };
 
-                            let response = ui.selectable_label(checked, sub_category);
+                            let response =
+                                if app.changes.is_sub_category_dirty(category, sub_category) {
+                                    ui.selectable_label(
+                                        checked,
+                                        format!("{}{}", sub_category, DIRTY_MARK),
+                                    )
+                                } else {
+                                    ui.selectable_label(checked, sub_category)
+                                };
 
-                            if !is_editing {
-                                if response.clicked() && !is_editing {
-                                    result = Some(SizePanelEvent::SubCategorySelected(Some(
-                                        sub_category.to_string(),
-                                    )));
-                                }
+                            if response.clicked() {
+                                result = Some(SizePanelEvent::SubCategorySelected(Some(
+                                    sub_category.to_string(),
+                                )));
                             }
                         })
                     });
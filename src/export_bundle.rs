@@ -0,0 +1,48 @@
+use my_secrets_core::bundle::{self, BUNDLE_EXTENSION};
+
+use crate::{
+    native_dialogs::show_error,
+    states::{BundleExportState, ModalWindowState},
+    MyApp,
+};
+
+impl MyApp {
+    pub fn start_bundle_export(&mut self) {
+        self.update_edited_content();
+
+        let state = BundleExportState::new(
+            self.vault.content().unwrap(),
+            self.selected_category.as_deref(),
+        );
+        self.modal_dialog.set(ModalWindowState::ExportBundle(state));
+    }
+
+    pub fn export_bundle(&mut self, mut state: BundleExportState) {
+        let paths = state.get_selected_paths();
+        let result = bundle::create_bundle(&self.vault, &paths, &state.password);
+        state.wipe_password();
+
+        let data = match result {
+            Ok(data) => data,
+            Err(err) => {
+                show_error(&err.to_string());
+                return;
+            }
+        };
+
+        let file_name = match crate::native_dialogs::show(|| {
+            rfd::FileDialog::new()
+                .add_filter("My secrets bundle", &[BUNDLE_EXTENSION])
+                .set_file_name(format!("secrets.{}", BUNDLE_EXTENSION))
+                .save_file()
+        }) {
+            Some(file_name) => file_name,
+            None => return,
+        };
+
+        let file_name = file_name.to_string_lossy();
+        if let Err(err) = my_secrets_core::file::write_private_file(&file_name, &data) {
+            show_error(&format!("Can not write file {}. Err: {}", file_name, err));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use my_secrets_core::export::{self, ExportFormat, ExportRequest};
+
+use crate::{native_dialogs::show_error, states::ExportState, MyApp};
+
+impl MyApp {
+    pub fn start_export(&mut self, category: &str) {
+        let sub_categories = match self.vault.content().unwrap().get(category) {
+            Some(sub_categories) => sub_categories,
+            None => return,
+        };
+
+        let state = ExportState::new(category, sub_categories.keys());
+        self.modal_dialog
+            .set(crate::states::ModalWindowState::Export(state));
+    }
+
+    pub fn export_category(&mut self, state: ExportState) {
+        self.update_edited_content();
+
+        let rules = self.settings.get_export_rules(&state.category);
+        let entries = state.get_selected_entries();
+
+        let request = ExportRequest {
+            category: &state.category,
+            entries: entries.as_deref(),
+            rules: &rules,
+            format: state.format,
+            name: Some(state.name.as_str()).filter(|name| !name.is_empty()),
+            namespace: Some(state.namespace.as_str()).filter(|namespace| !namespace.is_empty()),
+        };
+
+        let files = match export::export_category(&self.vault, &request) {
+            Ok(files) => files,
+            Err(err) => {
+                show_error(&err.to_string());
+                return;
+            }
+        };
+
+        if let Err(err) = save_exported_files(state.format, &files) {
+            show_error(&err);
+        }
+    }
+}
+
+fn save_exported_files(format: ExportFormat, files: &[export::ExportedFile]) -> Result<(), String> {
+    if format == ExportFormat::DockerCompose {
+        let folder = match crate::native_dialogs::show(|| rfd::FileDialog::new().pick_folder()) {
+            Some(folder) => folder,
+            None => return Ok(()),
+        };
+
+        for file in files {
+            let file_name = folder.join(&file.file_name);
+
+            if let Some(parent) = file_name.parent() {
+                std::fs::create_dir_all(parent).map_err(|err| {
+                    format!("Can not create folder {}. Err: {}", parent.display(), err)
+                })?;
+            }
+
+            let file_name = file_name.to_string_lossy();
+            my_secrets_core::file::write_private_file(&file_name, file.content.as_bytes())
+                .map_err(|err| format!("Can not write file {}. Err: {}", file_name, err))?;
+        }
+
+        return Ok(());
+    }
+
+    for file in files {
+        let file_name = match crate::native_dialogs::show(|| {
+            rfd::FileDialog::new()
+                .set_file_name(file.file_name.as_str())
+                .save_file()
+        }) {
+            Some(file_name) => file_name,
+            None => return Ok(()),
+        };
+
+        let file_name = file_name.to_string_lossy();
+        my_secrets_core::file::write_private_file(&file_name, file.content.as_bytes())
+            .map_err(|err| format!("Can not write file {}. Err: {}", file_name, err))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use my_secrets_core::{entry_ids::EntryIds, TypeContent};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::{
+    native_dialogs::show_error,
+    states::{MergeSource, MergeState, ModalWindowState},
+    MyApp,
+};
+
+enum FileChangeDecision {
+    Reload,
+    Merge,
+    Overwrite,
+    Cancel,
+}
+
+impl MyApp {
+    /// Picks up the vault file written by another program.
+    /// Without not saved changes there is nothing to lose and it is just reloaded
+    pub fn handle_file_changes(&mut self) {
+        if !self.vault.is_unlocked() || !self.modal_dialog.is_none() {
+            return;
+        }
+
+        if !self.file_watcher.take_changed() {
+            return;
+        }
+
+        match self.vault.has_file_changed() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                show_error(&format!("Can not read the vault file. Err: {}", err));
+                return;
+            }
+        }
+
+        if self.has_not_saved_data() {
+            self.resolve_file_changes();
+        } else {
+            self.cancel_not_saved_data();
+        }
+    }
+
+    /// Returns whether the not saved content is written over the changed file
+    pub fn resolve_file_changes(&mut self) -> bool {
+        match ask_about_file_changes() {
+            FileChangeDecision::Reload => {
+                self.cancel_not_saved_data();
+                false
+            }
+            FileChangeDecision::Merge => {
+                self.merge_file_changes();
+                false
+            }
+            FileChangeDecision::Overwrite => self.write_to_file(true),
+            FileChangeDecision::Cancel => false,
+        }
+    }
+
+    fn merge_file_changes(&mut self) {
+        self.flush_text_edit();
+        self.update_edited_content();
+
+        match self.vault.merge_file_changes() {
+            Ok(mut result) if result.conflicts.is_empty() => {
+                let (content, ids) = result.resolve(&[]);
+                self.finish_file_merge(content, ids);
+            }
+            Ok(result) => {
+                self.modal_dialog
+                    .set(ModalWindowState::Merge(MergeState::new(
+                        MergeSource::FileChange,
+                        result,
+                    )));
+            }
+            Err(err) => {
+                show_error(&format!("Can not merge the vault file. Err: {}", err));
+            }
+        }
+    }
+
+    /// Writes the merged content over the changed file
+    pub fn finish_file_merge(&mut self, content: TypeContent, ids: EntryIds) {
+        self.select_category(None);
+
+        if let Ok(current) = self.vault.content() {
+            self.changes.mark_differences(current, &content);
+        }
+
+        if let Err(err) = self.vault.replace_merged_content(content, ids) {
+            show_error(&format!("Can not merge the vault file. Err: {}", err));
+            return;
+        }
+
+        self.history.clear();
+        self.write_to_file(true);
+    }
+}
+
+fn ask_about_file_changes() -> FileChangeDecision {
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("Vault file changed")
+        .set_buttons(MessageButtons::YesNoCancelCustom(
+            "Reload".to_string(),
+            "Merge".to_string(),
+            "Overwrite".to_string(),
+        ))
+        .set_description(
+            "Another program changed the vault file since it was loaded, e.g. it was synced from another device. \
+            Reload it dropping not saved changes, merge it with them, or overwrite it with this version?",
+        )
+        .show()
+    });
+
+    match dialog_result {
+        MessageDialogResult::Yes => FileChangeDecision::Reload,
+        MessageDialogResult::No => FileChangeDecision::Merge,
+        MessageDialogResult::Custom(button) if button == "Reload" => FileChangeDecision::Reload,
+        MessageDialogResult::Custom(button) if button == "Merge" => FileChangeDecision::Merge,
+        MessageDialogResult::Custom(button) if button == "Overwrite" => {
+            FileChangeDecision::Overwrite
+        }
+        _ => FileChangeDecision::Cancel,
+    }
+}
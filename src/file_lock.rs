@@ -0,0 +1,67 @@
+use my_secrets_core::{Vault, VaultError};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::native_dialogs::show_error;
+
+enum FileLockDecision {
+    ReadOnly,
+    TakeOver,
+    Quit,
+}
+
+/// Locks the vault file for this instance. While another process has it open the user
+/// opens the vault read-only, takes the lock over or quits. Returns false to quit
+pub fn lock_vault_file(vault: &mut Vault) -> bool {
+    let owner = match vault.acquire_file_lock() {
+        Ok(()) => return true,
+        Err(VaultError::FileLocked(owner)) => owner,
+        Err(err) => {
+            show_error(&format!("Can not lock the vault file. Err: {}", err));
+            return true;
+        }
+    };
+
+    let message = format!(
+        "{}. Changes can not be saved while it is open there.\n\
+        Take over only if you are sure that process is not running any more.",
+        VaultError::FileLocked(owner.clone())
+    );
+
+    match ask_about_file_lock(&message) {
+        FileLockDecision::ReadOnly => {
+            vault.open_read_only(owner);
+            true
+        }
+        FileLockDecision::TakeOver => {
+            if let Err(err) = vault.take_over_file_lock() {
+                show_error(&format!("Can not take the lock over. Err: {}", err));
+                vault.open_read_only(owner);
+            }
+            true
+        }
+        FileLockDecision::Quit => false,
+    }
+}
+
+fn ask_about_file_lock(description: &str) -> FileLockDecision {
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Vault is open")
+            .set_buttons(MessageButtons::YesNoCancelCustom(
+                "Read only".to_string(),
+                "Take over".to_string(),
+                "Quit".to_string(),
+            ))
+            .set_description(description)
+            .show()
+    });
+
+    match dialog_result {
+        MessageDialogResult::Yes => FileLockDecision::ReadOnly,
+        MessageDialogResult::No => FileLockDecision::TakeOver,
+        MessageDialogResult::Custom(button) if button == "Read only" => FileLockDecision::ReadOnly,
+        MessageDialogResult::Custom(button) if button == "Take over" => FileLockDecision::TakeOver,
+        _ => FileLockDecision::Quit,
+    }
+}
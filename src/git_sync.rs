@@ -0,0 +1,130 @@
+use my_secrets_core::{entry_ids::EntryIds, git_sync::PullResult, TypeContent};
+use rfd::{MessageButtons, MessageDialog, MessageLevel};
+
+use crate::{
+    native_dialogs::show_error,
+    states::{MergeSource, MergeState, ModalWindowState},
+    MyApp,
+};
+
+impl MyApp {
+    pub fn pull_from_git(&mut self) {
+        if self.has_not_saved_data() {
+            show_error("Please save or cancel the changes before pulling");
+            return;
+        }
+
+        let Some(git_sync) = self.vault.get_git_sync() else {
+            return;
+        };
+
+        match git_sync.pull() {
+            Ok(PullResult::UpToDate) => {}
+            Ok(PullResult::FastForwarded) => {
+                // Nothing is changed locally, so this only reloads the file keeping the selection
+                self.cancel_not_saved_data();
+            }
+            Ok(PullResult::Diverged { base, theirs }) => {
+                self.merge_remote(base.as_deref(), &theirs);
+            }
+            Err(err) => {
+                show_error(&format!("Can not pull. Err: {}", err));
+            }
+        }
+    }
+
+    /// Merges the pulled vault right away or asks to resolve the conflicts first
+    pub fn merge_remote(&mut self, base: Option<&[u8]>, theirs: &[u8]) {
+        match self.vault.merge_version(base, theirs) {
+            Ok(mut result) if result.conflicts.is_empty() => {
+                let (content, ids) = result.resolve(&[]);
+                self.finish_merge(content, ids);
+            }
+            Ok(result) => {
+                self.modal_dialog
+                    .set(ModalWindowState::Merge(MergeState::new(
+                        MergeSource::Remote,
+                        result,
+                    )));
+            }
+            Err(err) => {
+                show_error(&format!("Can not merge the remote vault. Err: {}", err));
+            }
+        }
+    }
+
+    pub fn push_to_git(&mut self) {
+        if self.has_not_saved_data() {
+            show_error("Please save the changes before pushing");
+            return;
+        }
+
+        if let Some(git_sync) = self.vault.get_git_sync() {
+            if let Err(err) = git_sync.push() {
+                show_error(&format!("Can not push. Err: {}", err));
+            }
+        }
+    }
+
+    /// Saves the merged content as the merge commit, or as the version to push to the sync server
+    pub fn finish_merge(&mut self, content: TypeContent, ids: EntryIds) {
+        self.select_category(None);
+
+        // Without a remote the merged version was pulled from the sync server
+        let result = match self
+            .vault
+            .get_git_sync()
+            .filter(|git_sync| git_sync.remote.is_some())
+        {
+            Some(git_sync) => git_sync.begin_merge(),
+            None => Ok(()),
+        };
+
+        if let Err(err) = result {
+            my_secrets_core::wipe_content(content);
+            show_error(&format!("Can not merge. Err: {}", err));
+            return;
+        }
+
+        if let Err(err) = self
+            .vault
+            .replace_merged_content(content, ids)
+            .and_then(|_| self.vault.save())
+        {
+            if let Some(git_sync) = self.vault.get_git_sync() {
+                let _ = git_sync.abort_merge();
+            }
+
+            show_error(&format!("Can not save the merged vault. Err: {}", err));
+            return;
+        }
+
+        if let Some(server_sync) = self.vault.get_server_sync() {
+            if let Err(err) = server_sync.complete_merge() {
+                show_error(&format!("Can not complete the merge. Err: {}", err));
+            }
+        }
+
+        self.history.clear();
+        self.changes.clear();
+    }
+
+    /// The file was saved, so a failed commit is only a warning
+    pub fn handle_commit_error(&mut self) {
+        let Some(err) = self.vault.take_commit_error() else {
+            return;
+        };
+
+        crate::native_dialogs::show(|| {
+            MessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title("Not committed")
+                .set_buttons(MessageButtons::Ok)
+                .set_description(format!(
+                    "The vault is saved but git sync could not commit it. The next save tries again.\nErr: {}",
+                    err
+                ))
+                .show()
+        });
+    }
+}
@@ -0,0 +1,137 @@
+use zeroize::Zeroize;
+
+pub use my_secrets_core::VaultCommand;
+
+const MAX_HISTORY_SIZE: usize = 500;
+
+/// Text of the selected subcategory at the moment the last text change was recorded
+pub struct TextCheckpoint {
+    pub category: String,
+    pub sub_category: String,
+    pub text: String,
+}
+
+pub struct History {
+    undo: Vec<VaultCommand>,
+    redo: Vec<VaultCommand>,
+    text_checkpoint: Option<TextCheckpoint>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            text_checkpoint: None,
+        }
+    }
+
+    pub fn push(&mut self, command: VaultCommand) {
+        self.redo.clear();
+        self.undo.push(command);
+
+        if self.undo.len() > MAX_HISTORY_SIZE {
+            self.undo.remove(0);
+        }
+    }
+
+    /// The command to undo next
+    pub fn last_undo(&self) -> Option<&VaultCommand> {
+        self.undo.last()
+    }
+
+    /// The command to redo next
+    pub fn last_redo(&self) -> Option<&VaultCommand> {
+        self.redo.last()
+    }
+
+    pub fn pop_undo(&mut self) -> Option<VaultCommand> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<VaultCommand> {
+        self.redo.pop()
+    }
+
+    pub fn undone(&mut self, command: VaultCommand) {
+        self.redo.push(command);
+    }
+
+    pub fn redone(&mut self, command: VaultCommand) {
+        self.undo.push(command);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Commands which can be undone, the oldest first
+    pub fn undo_items(&self) -> &[VaultCommand] {
+        &self.undo
+    }
+
+    /// Commands which can be redone, the next one to redo last
+    pub fn redo_items(&self) -> &[VaultCommand] {
+        &self.redo
+    }
+
+    pub fn get_text_checkpoint(&self) -> Option<&TextCheckpoint> {
+        self.text_checkpoint.as_ref()
+    }
+
+    pub fn set_text_checkpoint(&mut self, category: &str, sub_category: &str, text: &str) {
+        self.clear_text_checkpoint();
+        self.text_checkpoint = Some(TextCheckpoint {
+            category: category.to_string(),
+            sub_category: sub_category.to_string(),
+            text: text.to_string(),
+        });
+    }
+
+    pub fn clear_text_checkpoint(&mut self) {
+        if let Some(mut checkpoint) = self.text_checkpoint.take() {
+            checkpoint.text.zeroize();
+        }
+    }
+
+    /// Drops the commands of a locked category, so its texts are not kept in plain text.
+    /// Older commands go too, e.g. the ones made before the category was renamed to its name,
+    /// and the redo ones may build on the dropped ones
+    pub fn forget_category(&mut self, category: &str) {
+        if let Some(index) = self
+            .undo
+            .iter()
+            .rposition(|command| command.has_category(category))
+        {
+            self.undo.drain(..=index);
+        }
+
+        if self
+            .redo
+            .iter()
+            .any(|command| command.has_category(category))
+        {
+            self.redo.clear();
+        }
+
+        if matches!(&self.text_checkpoint, Some(checkpoint) if checkpoint.category == category) {
+            self.clear_text_checkpoint();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.clear_text_checkpoint();
+    }
+}
+
+impl Drop for History {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
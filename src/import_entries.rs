@@ -0,0 +1,43 @@
+use my_secrets_core::{bundle::BUNDLE_EXTENSION, import::ImportPlan};
+
+use crate::{native_dialogs::show_error, states::ImportState, MyApp};
+
+impl MyApp {
+    pub fn start_import(&mut self) {
+        let file_name = match crate::native_dialogs::show(|| {
+            rfd::FileDialog::new()
+                .add_filter(
+                    "Password export",
+                    &["kdbx", "json", "1pux", "csv", BUNDLE_EXTENSION],
+                )
+                .pick_file()
+        }) {
+            Some(file_name) => file_name,
+            None => return,
+        };
+
+        let state = ImportState::new(file_name.to_string_lossy().to_string());
+        self.modal_dialog
+            .set(crate::states::ModalWindowState::Import(state));
+    }
+
+    /// Every change of the import goes to the history, so the import can be undone before saving
+    pub fn import_entries(&mut self, plan: ImportPlan) {
+        self.flush_text_edit();
+        self.update_edited_content();
+
+        let commands = match self.vault.content() {
+            Ok(content) => plan.compile_commands(content),
+            Err(err) => {
+                show_error(&err.to_string());
+                return;
+            }
+        };
+
+        for command in commands {
+            if !self.execute(command) {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::mpsc::{self, Receiver};
+
+use my_secrets_core::local_api::{
+    self, AccessLog, ApiCall, ApiClients, ApiError, ApiRequest, PeerProcess, DENIED,
+    INVALID_REQUEST, UNKNOWN_CLIENT, VAULT_LOCKED,
+};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use serde_json::json;
+
+use crate::MyApp;
+
+/// Calls of other tools over the local API, answered between frames
+pub struct LocalApi {
+    requests: Receiver<ApiRequest>,
+    access_log: AccessLog,
+}
+
+impl LocalApi {
+    /// None if the API can not be started. The GUI works without it
+    pub fn start(ctx: &egui::Context) -> Option<Self> {
+        let (sender, requests) = mpsc::channel();
+        let ctx = ctx.clone();
+
+        let socket_path = local_api::get_socket_path();
+
+        let started = local_api::start_server(&socket_path, move |request| {
+            if sender.send(request).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        if let Err(err) = started {
+            eprintln!(
+                "Can not start the local API on {}. Err: {}",
+                socket_path, err
+            );
+            return None;
+        }
+
+        Some(Self {
+            requests,
+            access_log: AccessLog::new(local_api::get_access_log_file_name()),
+        })
+    }
+}
+
+impl MyApp {
+    pub fn start_local_api(&mut self, ctx: &egui::Context) {
+        if self.settings.api.enabled {
+            self.local_api = LocalApi::start(ctx);
+        }
+    }
+
+    pub fn handle_api_requests(&mut self) {
+        let Some(api) = &mut self.local_api else {
+            return;
+        };
+
+        while let Ok(request) = api.requests.try_recv() {
+            // Read every time, so a client revoked with the CLI is refused right away
+            let mut clients = match ApiClients::load(local_api::get_clients_file_name()) {
+                Ok(clients) => clients,
+                Err(err) => {
+                    request.respond(Err(err.into()));
+                    continue;
+                }
+            };
+
+            if let ApiCall::Register { name, categories } = &request.call {
+                let result = if self.vault.is_unlocked() {
+                    register(&mut clients, name, categories, request.peer.as_ref())
+                } else {
+                    // Nobody may be at the screen to see the prompt
+                    Err(ApiError::new(
+                        VAULT_LOCKED,
+                        "Unlock the vault to register the client",
+                    ))
+                };
+                api.access_log.write(Some(name), &request.call, &result);
+                request.respond(result);
+                continue;
+            }
+
+            let client = request
+                .token
+                .as_deref()
+                .and_then(|token| clients.find(token));
+
+            let result = match client {
+                Some(client) => local_api::execute(&self.vault, client, &request.call),
+                None => Err(ApiError::new(
+                    UNKNOWN_CLIENT,
+                    "Unknown client. Call register first",
+                )),
+            };
+
+            api.access_log.write(
+                client.map(|client| client.name.as_str()),
+                &request.call,
+                &result,
+            );
+            request.respond(result);
+        }
+    }
+}
+
+/// The first time a client connects the user decides which categories it may read
+fn register(
+    clients: &mut ApiClients,
+    name: &str,
+    categories: &[String],
+    peer: Option<&PeerProcess>,
+) -> Result<serde_json::Value, ApiError> {
+    if clients.is_approved(name) {
+        return Err(ApiError::new(
+            INVALID_REQUEST,
+            format!(
+                "Client {} is registered already. Revoke it with my-secrets api revoke {} to register again",
+                name, name
+            ),
+        ));
+    }
+
+    if !ask_to_approve(name, categories, peer) {
+        return Err(ApiError::new(DENIED, "The user denied the access"));
+    }
+
+    let token = clients.approve(name, categories.to_vec())?;
+    Ok(json!({ "token": token }))
+}
+
+fn ask_to_approve(name: &str, categories: &[String], peer: Option<&PeerProcess>) -> bool {
+    let peer = match peer {
+        Some(peer) => peer.to_string(),
+        None => "unknown process".to_string(),
+    };
+
+    let description = format!(
+        "{} ({}) asks to read the secrets of the categories {} through the local API.\n\
+        Allow it? Revoke the access with my-secrets api revoke {}",
+        name,
+        peer,
+        categories.join(", "),
+        name
+    );
+
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Local API")
+            .set_buttons(MessageButtons::YesNo)
+            .set_description(description)
+            .show()
+    });
+
+    matches!(dialog_result, MessageDialogResult::Yes)
+}
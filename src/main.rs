@@ -1,14 +1,34 @@
+mod auto_lock;
+mod cli;
 mod components;
-mod file;
-mod password_utils;
+mod export_bundle;
+mod export_category;
+mod file_changes;
+mod file_lock;
+mod git_sync;
+mod history;
+mod import_entries;
+mod local_api;
+mod merge_copy;
+mod native_dialogs;
 mod render_bottom_panel;
+mod sealed_categories;
+mod server_sync;
 mod settings;
+mod share_entries;
 mod states;
+mod team_members;
 mod text_buffer;
+mod undo_redo;
+mod unlock_agent;
+mod unsaved_changes;
 
 use components::*;
 use egui::{FontData, FontDefinitions};
+use my_secrets_core::{Vault, VaultError};
+use zeroize::Zeroize;
 
+use crate::history::*;
 use crate::settings::SettingsModel;
 
 use crate::states::*;
@@ -17,20 +37,24 @@ const FONT_MESLO: &[u8] = std::include_bytes!("../fonts/MesloLGS NF Regular.ttf"
 
 pub struct MyApp {
     settings: SettingsModel,
-    pub authenticated: Option<AuthenticatedState>,
+    pub vault: Vault,
     pub selected_category: Option<String>,
     pub selected_sub_category: Option<SelectedSubCategoryState>,
-    pub has_not_saved_data: bool,
+    pub changes: ChangeTracker,
     pub modal_dialog: ModalDialog,
     pub edit_state: EditingState,
+    pub category_locks: CategoryLocks,
+    pub file_watcher: FileWatcher,
+    pub window_minimized: bool,
+    pub recovery_draft_saved_at: std::time::Instant,
+    pub history: History,
+    pub show_history: bool,
+    pub local_api: Option<local_api::LocalApi>,
     //pub normal_style: Rc<Style>,
     //pub category_style: Rc<Style>,
 }
 
 impl MyApp {
-    fn get_content_ref_mut(&mut self) -> &mut TypeContent {
-        &mut self.authenticated.as_mut().unwrap().content
-    }
     /*
     fn get_content_by_selected_category_mut(&mut self) -> &mut BTreeMap<String, String> {
         match &self.selected_category {
@@ -60,36 +84,35 @@ impl MyApp {
         }
     } */
 
-    fn update_edited_content(&mut self) -> &AuthenticatedState {
-        let selected_category = match &self.selected_category {
-            Some(selected_category) => selected_category,
-            None => {
-                panic!("There is not selected category")
-            }
-        };
-
-        let selected_sub_category = match &self.selected_sub_category {
-            Some(selected_sub_category) => selected_sub_category.clone(),
-            None => {
-                panic!("There is not selected category")
-            }
+    pub fn update_edited_content(&mut self) {
+        let (Some(selected_category), Some(selected_sub_category)) =
+            (&self.selected_category, &self.selected_sub_category)
+        else {
+            return;
         };
 
-        let authenticated_state = self.authenticated.as_mut().unwrap();
+        self.vault
+            .update_content(|content| {
+                if let Some(sub_level_data) = content.get_mut(selected_category) {
+                    let prev_text = sub_level_data.insert(
+                        selected_sub_category.id.clone(),
+                        selected_sub_category.text.clone(),
+                    );
 
-        if let Some(sub_level_data) = authenticated_state.content.get_mut(selected_category) {
-            sub_level_data.insert(selected_sub_category.id, selected_sub_category.text);
-        }
-
-        authenticated_state
+                    if let Some(mut prev_text) = prev_text {
+                        prev_text.zeroize();
+                    }
+                }
+            })
+            .unwrap();
     }
 
     fn get_selected_content(&self, sub_category_id: &str) -> Option<&str> {
-        let auth_data = self.authenticated.as_ref()?;
+        let content = self.vault.content().ok()?;
 
         let selected_category = self.selected_category.as_ref()?;
 
-        let first_level = auth_data.content.get(selected_category)?;
+        let first_level = content.get(selected_category)?;
 
         let result = match first_level.get(sub_category_id) {
             Some(value) => value.as_str(),
@@ -112,21 +135,82 @@ impl MyApp {
     }
      */
 
+    pub fn has_not_saved_data(&self) -> bool {
+        self.changes.is_dirty()
+    }
+
+    /// Saves all dirty entries at once
     pub fn save_to_file(&mut self) {
-        let state = self.update_edited_content();
-        crate::file::save_to_file(&state.aes_key, &state.content);
-        self.edit_state.finish_editing();
-        self.has_not_saved_data = false;
+        self.write_to_file(false);
+    }
+
+    /// Overwrite writes even if another program changed the file since it was loaded.
+    /// Otherwise the user decides what to do with such file.
+    /// Returns whether the file has the content now
+    pub fn write_to_file(&mut self, overwrite: bool) -> bool {
+        self.update_edited_content();
+
+        let result = if overwrite {
+            self.vault.overwrite()
+        } else {
+            self.vault.save()
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(VaultError::FileChanged) => {
+                return self.resolve_file_changes();
+            }
+            Err(err) => {
+                crate::native_dialogs::show_error(&format!("Can not save the vault. Err: {}", err));
+                return false;
+            }
+        }
+
+        self.delete_draft();
+        self.edit_state.wipe();
+        self.changes.clear();
+        true
     }
 
+    /// Discards changes of all dirty entries by going back to the saved file
     pub fn cancel_not_saved_data(&mut self) {
-        let prev_content = self.edit_state.finish_editing();
+        self.edit_state.wipe();
 
-        if let Some(sub_category_content) = self.selected_sub_category.as_mut() {
-            sub_category_content.text = prev_content;
+        let selected_category = self.selected_category.clone();
+        let selected_sub_category = self
+            .selected_sub_category
+            .as_ref()
+            .map(|selected_sub_category| selected_sub_category.id.clone());
+
+        self.select_category(None);
+
+        if let Err(err) = self.vault.reload() {
+            crate::native_dialogs::show_error(&format!("Can not reload the vault. Err: {}", err));
         }
 
-        self.has_not_saved_data = false;
+        self.history.clear();
+        self.delete_draft();
+        self.changes.clear();
+
+        if let Some(selected_category) = selected_category {
+            let content = self.vault.content().unwrap();
+            let sub_category_exists =
+                match (content.get(&selected_category), &selected_sub_category) {
+                    (Some(sub_categories), Some(sub_category)) => {
+                        sub_categories.contains_key(sub_category)
+                    }
+                    _ => false,
+                };
+
+            if content.contains_key(&selected_category) {
+                self.select_category(Some(selected_category));
+
+                if sub_category_exists {
+                    self.select_sub_category(selected_sub_category);
+                }
+            }
+        }
     }
 
     pub fn select_category(&mut self, category_id: Option<String>) {
@@ -138,16 +222,51 @@ impl MyApp {
     }
 
     pub fn select_sub_category(&mut self, sub_category_id: Option<String>) {
+        self.flush_text_edit();
+
+        if self.vault.is_unlocked() {
+            // Write the text back before switching, so edits of the previous entry are kept
+            self.update_edited_content();
+        }
+
+        let same_sub_category = match (&self.selected_sub_category, &sub_category_id) {
+            (Some(selected_sub_category), Some(sub_category_id)) => {
+                &selected_sub_category.id == sub_category_id
+            }
+            _ => false,
+        };
+
+        // Edit mode is started for the entry. Another one opens for reading
+        if !same_sub_category {
+            self.edit_state.wipe();
+        }
+
+        self.show_sub_category(sub_category_id);
+    }
+
+    /// Selects the entry with the text it has in the vault
+    fn show_sub_category(&mut self, sub_category_id: Option<String>) {
         match sub_category_id {
             Some(sub_category_id) => {
                 let content = self.get_selected_content(&sub_category_id).unwrap();
-                self.selected_sub_category = Some(SelectedSubCategoryState {
+                let selected_sub_category = SelectedSubCategoryState {
                     id: sub_category_id,
                     text: content.to_string(),
-                });
+                };
+
+                if let Some(category) = &self.selected_category {
+                    self.history.set_text_checkpoint(
+                        category,
+                        &selected_sub_category.id,
+                        &selected_sub_category.text,
+                    );
+                }
+
+                self.selected_sub_category = Some(selected_sub_category);
             }
             None => {
                 self.selected_sub_category = None;
+                self.history.clear_text_checkpoint();
             }
         }
     }
@@ -155,35 +274,116 @@ impl MyApp {
     pub fn handle_dialog_result(&mut self, dialog_result: ShowDialogResult) {
         match dialog_result {
             ShowDialogResult::DialogIsBeingRendered => {}
-            ShowDialogResult::Authenticated { aes_key, data } => {
-                self.authenticated = Some(AuthenticatedState {
-                    aes_key,
-                    content: data,
-                });
+            ShowDialogResult::Authenticated => {
+                self.changes.clear();
+                if let Ok(Some(draft)) = self.vault.load_draft() {
+                    if crate::unsaved_changes::ask_to_restore_draft() {
+                        self.changes
+                            .mark_differences(self.vault.content().unwrap(), &draft);
+
+                        // The draft stays on disk, so it is not lost with the error
+                        if let Err(err) = self.vault.replace_content(draft) {
+                            self.changes.clear();
+                            crate::native_dialogs::show_error(&format!(
+                                "Can not restore the recovery draft. Err: {}",
+                                err
+                            ));
+                        }
+                    } else {
+                        my_secrets_core::wipe_content(draft);
+                        self.delete_draft();
+                    }
+                }
                 self.edit_state.extend_expiration_time();
                 self.modal_dialog.set_none();
             }
             ShowDialogResult::CreatedSubCategory(sub_category) => {
-                self.select_sub_category(Some(sub_category));
+                let category = self.selected_category.as_ref().unwrap().to_string();
+                self.execute(VaultCommand::CreateSubCategory {
+                    category,
+                    name: sub_category,
+                    text: "".to_string(),
+                });
                 self.modal_dialog.set_none();
             }
             ShowDialogResult::CreatedCategory(category) => {
-                self.select_category(Some(category));
+                self.execute(VaultCommand::CreateCategory {
+                    name: category,
+                    sub_categories: Default::default(),
+                });
                 self.modal_dialog.set_none();
             }
             ShowDialogResult::RenameCategory(new_category_name) => {
                 let from = self.selected_category.as_ref().unwrap().to_string();
-                self.select_category(None);
-
-                let content = self.get_content_ref_mut();
-
-                let removed = content.remove(&from).unwrap();
-
-                content.insert(new_category_name.clone(), removed);
-
-                self.select_category(Some(new_category_name));
-                self.has_not_saved_data = true;
+                if from != new_category_name {
+                    self.execute(VaultCommand::RenameCategory {
+                        from,
+                        to: new_category_name,
+                    });
+                }
+                self.modal_dialog.set_none();
+            }
+            ShowDialogResult::RenameSubCategory(new_sub_category_name) => {
+                let category = self.selected_category.as_ref().unwrap().to_string();
+                let from = self.selected_sub_category.as_ref().unwrap().id.to_string();
+                if from != new_sub_category_name {
+                    self.execute(VaultCommand::RenameSubCategory {
+                        category,
+                        from,
+                        to: new_sub_category_name,
+                    });
+                }
+                self.modal_dialog.set_none();
+            }
+            ShowDialogResult::MoveSubCategory(to_category) => {
+                let from_category = self.selected_category.as_ref().unwrap().to_string();
+                let name = self.selected_sub_category.as_ref().unwrap().id.to_string();
+                if from_category != to_category {
+                    self.execute(VaultCommand::MoveSubCategory {
+                        name,
+                        from_category,
+                        to_category,
+                    });
+                }
+                self.modal_dialog.set_none();
+            }
+            ShowDialogResult::Export(state) => {
+                self.modal_dialog.set_none();
+                self.export_category(state);
+            }
+            ShowDialogResult::ExportBundle(state) => {
+                self.modal_dialog.set_none();
+                self.export_bundle(state);
+            }
+            ShowDialogResult::CreateIdentity => {
+                self.modal_dialog.set_none();
+                self.create_identity();
+            }
+            ShowDialogResult::Import(plan) => {
+                self.modal_dialog.set_none();
+                self.import_entries(plan);
+            }
+            ShowDialogResult::Merged(source, content, ids) => {
+                self.modal_dialog.set_none();
+                match source {
+                    MergeSource::Remote => self.finish_merge(content, ids),
+                    MergeSource::Copy => self.apply_merged_copy(content, ids),
+                    MergeSource::FileChange => self.finish_file_merge(content, ids),
+                }
+            }
+            ShowDialogResult::CategoryUnlocked(category) => {
                 self.modal_dialog.set_none();
+                self.select_category(Some(category));
+            }
+            ShowDialogResult::CategorySealed(category) => {
+                self.modal_dialog.set_none();
+
+                // Saved at once. The category stays not sealed if the file is not written
+                if self.write_to_file(false) {
+                    self.select_category(Some(category));
+                } else {
+                    let _ = self.vault.unseal_category(&category);
+                }
             }
             ShowDialogResult::Cancel => {
                 self.modal_dialog.set_none();
@@ -194,11 +394,21 @@ impl MyApp {
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_close_request(ctx);
+        self.handle_auto_lock(ctx);
+        self.handle_file_changes();
+        self.vault.refresh_file_lock();
+        self.handle_commit_error();
+        self.save_recovery_draft_if_needed();
+        self.handle_api_requests();
+
         if let Some(dialog_result) = self.render_dialog(ctx) {
             self.handle_dialog_result(dialog_result);
             return;
         }
 
+        self.handle_history_shortcuts(ctx);
+
         egui::SidePanel::left("my_left_panel").show(ctx, |ui| {
             if let Some(result) = crate::components::side_panel::render(self, ui) {
                 match result {
@@ -210,6 +420,9 @@ impl eframe::App for MyApp {
                         self.select_category(Some(category));
                         self.edit_state.extend_expiration_time();
                     }
+                    side_panel::SizePanelEvent::LockedCategorySelected(category) => {
+                        self.start_unlock_category(category);
+                    }
                 }
             }
         });
@@ -240,16 +453,32 @@ impl eframe::App for MyApp {
         });
 
         self.render_bottom_panel(ctx);
+        self.render_history_window(ctx);
     }
 }
 
 fn main() {
-    let settings = SettingsModel::read();
-
-    if settings.shared_key.as_bytes().len() != 16 {
-        panic!("Shared key must be 16 bytes long");
+    match cli::CliArgs::parse(std::env::args()) {
+        Ok(Some(cli_args)) => {
+            std::process::exit(cli::run(cli_args));
+        }
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     }
 
+    let settings = match SettingsModel::read() {
+        Ok(settings) => settings,
+        Err(err) => {
+            // Started from the Finder there is no terminal to see it
+            eprintln!("{}", err);
+            native_dialogs::show_error(&err);
+            std::process::exit(1);
+        }
+    };
+
     let style = egui::Style {
         visuals: egui::Visuals::light(),
         ..egui::Style::default()
@@ -261,18 +490,46 @@ fn main() {
     category_style.visuals.selection.bg_fill = egui::Color32::from_rgb(0, 0, 255);
     category_style.visuals.selection.stroke.color = egui::Color32::from_rgb(255, 255, 255);
 
-    let app = MyApp {
-        authenticated: None,
+    let mut vault = Vault::open_default(settings.get_iv());
+    vault.set_git_sync(settings.create_git_sync(vault.get_file_name()));
+    vault.set_server_sync(settings.create_server_sync(vault.get_file_name()));
+
+    if let Some(storage) = settings.create_storage(vault.get_file_name()) {
+        vault.set_storage(storage);
+    }
+
+    let file_watcher = FileWatcher::start(vault.get_file_name());
+
+    if !file_lock::lock_vault_file(&mut vault) {
+        return;
+    }
+
+    let unlocked_by_agent = unlock_agent::unlock_from_agent(&mut vault, &settings);
+
+    let mut app = MyApp {
+        vault,
         selected_category: None,
         modal_dialog: Default::default(),
         selected_sub_category: None,
-        has_not_saved_data: false,
-        edit_state: EditingState::new(),
+        changes: ChangeTracker::new(),
+        edit_state: EditingState::new(settings.auto_lock.idle_timeout_minutes),
+        category_locks: CategoryLocks::new(settings.auto_lock.category_idle_timeout_minutes),
+        file_watcher,
+        window_minimized: false,
+        recovery_draft_saved_at: std::time::Instant::now(),
+        history: History::new(),
+        show_history: false,
+        local_api: None,
         //normal_style: Rc::new(style.clone()),
         //category_style: Rc::new(category_style),
         settings,
     };
 
+    // Only at start. After the auto-lock the password is asked again
+    if unlocked_by_agent {
+        app.handle_dialog_result(ShowDialogResult::Authenticated);
+    }
+
     let mut native_options = eframe::NativeOptions::default();
     native_options.centered = true;
     //native_options.viewport.inner_size = Some(egui::vec2(1024.0 * 4.0, 768.0 * 4.0));
@@ -286,6 +543,7 @@ fn main() {
             cc.egui_ctx.set_style(style);
             cc.egui_ctx.set_pixels_per_point(1.0);
             configure_fonts(&cc.egui_ctx);
+            app.start_local_api(&cc.egui_ctx);
             Ok(Box::new(app))
         }),
     );
@@ -0,0 +1,102 @@
+use my_secrets_core::{entry_ids::EntryIds, TypeContent};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::{
+    native_dialogs::show_error,
+    states::{MergeSource, MergeState, ModalWindowState},
+    MyApp,
+};
+
+impl MyApp {
+    /// Merges a conflicted copy of the vault file made by Dropbox or iCloud into the current content
+    pub fn start_copy_merge(&mut self) {
+        let Some(copy_file) = crate::native_dialogs::show(|| {
+            rfd::FileDialog::new()
+                .set_title("Copy of the vault to merge")
+                .pick_file()
+        }) else {
+            return;
+        };
+
+        let base_file = if ask_for_base() {
+            match crate::native_dialogs::show(|| {
+                rfd::FileDialog::new()
+                    .set_title("Version both copies were made from")
+                    .pick_file()
+            }) {
+                Some(base_file) => Some(base_file),
+                None => return,
+            }
+        } else {
+            None
+        };
+
+        let theirs = match std::fs::read(&copy_file) {
+            Ok(theirs) => theirs,
+            Err(err) => {
+                show_error(&format!("Can not read {:?}. Err: {}", copy_file, err));
+                return;
+            }
+        };
+
+        let base = match base_file.map(|base_file| std::fs::read(&base_file)) {
+            Some(Ok(base)) => Some(base),
+            Some(Err(err)) => {
+                show_error(&format!("Can not read the base version. Err: {}", err));
+                return;
+            }
+            None => None,
+        };
+
+        self.flush_text_edit();
+        self.update_edited_content();
+
+        match self.vault.merge_version(base.as_deref(), &theirs) {
+            Ok(mut result) if result.conflicts.is_empty() => {
+                let (content, ids) = result.resolve(&[]);
+                self.apply_merged_copy(content, ids);
+            }
+            Ok(result) => {
+                self.modal_dialog
+                    .set(ModalWindowState::Merge(MergeState::new(
+                        MergeSource::Copy,
+                        result,
+                    )));
+            }
+            Err(err) => {
+                show_error(&format!("Can not merge the copy. Err: {}", err));
+            }
+        }
+    }
+
+    /// The merged content becomes not saved changes to review before saving
+    pub fn apply_merged_copy(&mut self, content: TypeContent, ids: EntryIds) {
+        self.select_category(None);
+
+        if let Ok(current) = self.vault.content() {
+            self.changes.mark_differences(current, &content);
+        }
+
+        if let Err(err) = self.vault.replace_merged_content(content, ids) {
+            show_error(&format!("Can not merge the copy. Err: {}", err));
+        }
+
+        self.history.clear();
+    }
+}
+
+fn ask_for_base() -> bool {
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+            .set_level(MessageLevel::Info)
+            .set_title("Merge copy")
+            .set_buttons(MessageButtons::YesNo)
+            .set_description(
+                "Do you have the version both copies were made from, e.g. a backup? \
+            With it changes made on one side are taken without conflicts.",
+            )
+            .show()
+    });
+
+    matches!(dialog_result, MessageDialogResult::Yes)
+}
@@ -0,0 +1,37 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rfd::{MessageDialog, MessageLevel};
+
+/// Focus events of the window arrive once the dialog is closed and the frames go on
+const FOCUS_SETTLE_TIME: Duration = Duration::from_secs(1);
+
+static LAST_CLOSED: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Shows a blocking rfd dialog. The window loses the focus to it, which must not count as the user leaving
+pub fn show<T>(dialog: impl FnOnce() -> T) -> T {
+    let result = dialog();
+    *LAST_CLOSED.lock().unwrap() = Some(Instant::now());
+    result
+}
+
+/// A native dialog was open just now, so focus changes are its own
+pub fn has_taken_focus() -> bool {
+    match *LAST_CLOSED.lock().unwrap() {
+        Some(closed) => closed.elapsed() < FOCUS_SETTLE_TIME,
+        None => false,
+    }
+}
+
+pub fn show_error(message: &str) {
+    show(|| {
+        MessageDialog::new()
+            .set_level(MessageLevel::Error)
+            .set_title("Error")
+            .set_buttons(rfd::MessageButtons::Ok)
+            .set_description(message)
+            .show()
+    });
+}
@@ -1,13 +1,13 @@
 use rfd::{MessageDialog, MessageDialogResult, MessageLevel};
 
-use crate::{states::*, MyApp};
+use crate::{history::VaultCommand, states::*, MyApp};
 
 impl MyApp {
     pub fn render_bottom_panel(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::bottom("bottom panel").show(ctx, |ui| {
             ui.horizontal_centered(|ui| {
                 if self.edit_state.is_editing() {
-                    if !self.has_not_saved_data {
+                    if !self.has_not_saved_data() {
                         if ui.small_button("Stop editing").clicked() {
                             self.edit_state.finish_editing();
                         };
@@ -18,34 +18,189 @@ impl MyApp {
                             .set(ModalWindowState::CreateCategory("".to_string()));
                     };
 
-                    if let Some(selected_category) = &self.selected_category {
+                    if ui.small_button("Import").clicked() {
+                        self.start_import();
+                    };
+
+                    if ui.small_button("Export bundle").clicked() {
+                        self.start_bundle_export();
+                    };
+
+                    if ui.small_button("Import shared").clicked() {
+                        self.start_shared_import();
+                    };
+
+                    if ui.small_button("Merge copy").clicked() {
+                        self.start_copy_merge();
+                    };
+
+                    if ui.small_button("Contacts").clicked() {
+                        self.open_contacts();
+                    };
+
+                    if ui.small_button("Members").clicked() {
+                        self.open_members();
+                    };
+
+                    if let Some(selected_category) = self.selected_category.clone() {
                         if ui.small_button("Rename category").clicked() {
                             self.modal_dialog
                                 .set(ModalWindowState::RenameCategory(selected_category.clone()));
                         };
 
+                        if ui.small_button("Delete category").clicked()
+                            && confirm(&format!(
+                                "Please confirm that you want to delete category {}.",
+                                selected_category
+                            ))
+                        {
+                            let sub_categories = self
+                                .vault
+                                .content()
+                                .unwrap()
+                                .get(&selected_category)
+                                .cloned()
+                                .unwrap_or_default();
+
+                            self.execute(VaultCommand::DeleteCategory {
+                                name: selected_category.clone(),
+                                sub_categories,
+                            });
+                        };
+
+                        if ui.small_button("Export category").clicked() {
+                            self.start_export(&selected_category);
+                        };
+
+                        if self.vault.is_category_sealed(&selected_category) {
+                            if ui.small_button("Lock category").clicked() {
+                                self.lock_category(&selected_category);
+                                return;
+                            };
+
+                            if ui.small_button("Unseal category").clicked()
+                                && confirm(&format!(
+                                    "Please confirm that category {} should not require its own password anymore.",
+                                    selected_category
+                                ))
+                            {
+                                self.unseal_category(&selected_category);
+                            };
+                        } else if ui.small_button("Seal category").clicked() {
+                            self.start_seal_category(selected_category.clone());
+                        };
+
                         if ui.small_button("Add subcategory").clicked() {
                             self.modal_dialog
                                 .set(ModalWindowState::CreateSubCategory("".to_string()));
                         };
 
-                        if let Some(selected_sub_category) = self.selected_sub_category.as_ref() {
+                        if let Some(selected_sub_category) = self.selected_sub_category.clone() {
                             if ui.small_button("Edit").clicked() {
                                 self.edit_state
                                     .start_editing(selected_sub_category.text.to_string());
                             };
+
+                            if ui.small_button("Rename subcategory").clicked() {
+                                self.modal_dialog.set(ModalWindowState::RenameSubCategory(
+                                    selected_sub_category.id.clone(),
+                                ));
+                            };
+
+                            if ui.small_button("Share").clicked() {
+                                self.start_share(vec![format!(
+                                    "{}/{}",
+                                    selected_category, selected_sub_category.id
+                                )]);
+                            };
+
+                            if ui.small_button("Move subcategory").clicked() {
+                                self.modal_dialog.set(ModalWindowState::MoveSubCategory(
+                                    selected_category.clone(),
+                                ));
+                            };
+
+                            if ui.small_button("Delete subcategory").clicked()
+                                && confirm(&format!(
+                                    "Please confirm that you want to delete subcategory {}/{}.",
+                                    selected_category, selected_sub_category.id
+                                ))
+                            {
+                                self.execute(VaultCommand::DeleteSubCategory {
+                                    category: selected_category.clone(),
+                                    name: selected_sub_category.id.clone(),
+                                    text: selected_sub_category.text.clone(),
+                                });
+                            };
                         }
                     }
                 }
 
-                if self.has_not_saved_data {
-                    if ui.small_button("Save").clicked() {
-                        let dialog_result = MessageDialog::new()
-                            .set_level(rfd::MessageLevel::Warning)
-                            .set_title("Confirmation")
-                            .set_buttons(rfd::MessageButtons::YesNo)
-                            .set_description("Please confirm that you want to save the changes.")
-                            .show();
+                let has_remote = self
+                    .vault
+                    .get_git_sync()
+                    .map(|git_sync| git_sync.remote.is_some())
+                    .unwrap_or(false);
+
+                if has_remote {
+                    if ui.small_button("Pull").clicked() {
+                        self.pull_from_git();
+                    };
+
+                    if ui.small_button("Push").clicked() {
+                        self.push_to_git();
+                    };
+                } else if self.vault.get_server_sync().is_some() {
+                    if ui.small_button("Pull").clicked() {
+                        self.pull_from_server();
+                    };
+
+                    if ui.small_button("Push").clicked() {
+                        self.push_to_server();
+                    };
+                }
+
+                if ui.small_button("Lock").clicked() {
+                    self.lock();
+                    return;
+                };
+
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo").small())
+                    .clicked()
+                {
+                    self.undo();
+                };
+
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo").small())
+                    .clicked()
+                {
+                    self.redo();
+                };
+
+                if ui.small_button("History").clicked() {
+                    self.show_history = !self.show_history;
+                };
+
+                if self.vault.is_read_only() {
+                    ui.label("Read only: the vault is open in another process");
+                }
+
+                if self.has_not_saved_data() {
+                    let save_button_text =
+                        format!("Save ({})", self.changes.dirty_entries_amount());
+                    if ui.small_button(save_button_text).clicked() {
+                        let dialog_result = crate::native_dialogs::show(|| {
+                            MessageDialog::new()
+                                .set_level(rfd::MessageLevel::Warning)
+                                .set_title("Confirmation")
+                                .set_buttons(rfd::MessageButtons::YesNo)
+                                .set_description(
+                                    "Please confirm that you want to save the changes.",
+                                )
+                                .show()
+                        });
 
                         if let MessageDialogResult::Yes = dialog_result {
                             self.save_to_file();
@@ -53,12 +208,16 @@ impl MyApp {
                     };
 
                     if ui.small_button("Cancel").clicked() {
-                        let dialog_result = MessageDialog::new()
-                            .set_level(MessageLevel::Warning)
-                            .set_title("Confirmation")
-                            .set_buttons(rfd::MessageButtons::YesNo)
-                            .set_description("Please confirm that you want to cancel the changes.")
-                            .show();
+                        let dialog_result = crate::native_dialogs::show(|| {
+                            MessageDialog::new()
+                                .set_level(MessageLevel::Warning)
+                                .set_title("Confirmation")
+                                .set_buttons(rfd::MessageButtons::YesNo)
+                                .set_description(
+                                    "Please confirm that you want to cancel the changes.",
+                                )
+                                .show()
+                        });
 
                         if let MessageDialogResult::Yes = dialog_result {
                             self.cancel_not_saved_data();
@@ -69,3 +228,16 @@ impl MyApp {
         });
     }
 }
+
+fn confirm(description: &str) -> bool {
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Confirmation")
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .set_description(description)
+            .show()
+    });
+
+    matches!(dialog_result, MessageDialogResult::Yes)
+}
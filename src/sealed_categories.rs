@@ -0,0 +1,82 @@
+use crate::{
+    native_dialogs::show_error,
+    states::{CategoryPasswordState, ModalWindowState},
+    MyApp,
+};
+
+impl MyApp {
+    pub fn start_unlock_category(&mut self, category: String) {
+        self.modal_dialog.set(ModalWindowState::CategoryPassword(
+            CategoryPasswordState::new(category, false),
+        ));
+    }
+
+    /// Sealing writes the vault file at once, so other changes have to be saved first
+    pub fn start_seal_category(&mut self, category: String) {
+        if self.has_not_saved_data() {
+            show_error("Please save or cancel the changes before sealing the category");
+            return;
+        }
+
+        self.modal_dialog.set(ModalWindowState::CategoryPassword(
+            CategoryPasswordState::new(category, true),
+        ));
+    }
+
+    pub fn unseal_category(&mut self, category: &str) {
+        if self.has_not_saved_data() {
+            show_error("Please save or cancel the changes before unsealing the category");
+            return;
+        }
+
+        if let Err(err) = self
+            .vault
+            .unseal_category(category)
+            .and_then(|_| self.vault.save())
+        {
+            show_error(&format!(
+                "Can not unseal category {}. Err: {}",
+                category, err
+            ));
+        }
+    }
+
+    /// Encrypts the category in memory again. Not saved changes of it are kept
+    pub fn lock_category(&mut self, category: &str) {
+        if self.selected_category.as_deref() == Some(category) {
+            self.select_category(None);
+            self.edit_state.wipe();
+        }
+
+        if let Err(err) = self.vault.lock_category(category) {
+            show_error(&format!("Can not lock category {}. Err: {}", category, err));
+            return;
+        }
+
+        self.history.forget_category(category);
+    }
+
+    /// Sealed categories have their own idle timer which is extended while they are selected
+    pub fn handle_category_locks(&mut self, has_activity: bool) {
+        self.category_locks
+            .sync(&self.vault.list_unlocked_sealed_categories());
+
+        if has_activity {
+            if let Some(selected_category) = &self.selected_category {
+                self.category_locks
+                    .extend_expiration_time(selected_category);
+            }
+        }
+
+        for category in self.category_locks.get_expired() {
+            self.lock_category(&category);
+        }
+
+        // Reload or a restored draft brings sealed categories back locked
+        if let Some(selected_category) = self.selected_category.clone() {
+            if self.vault.is_category_locked(&selected_category) {
+                self.select_category(None);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use my_secrets_core::git_sync::PullResult;
+
+use crate::{native_dialogs::show_error, MyApp};
+
+impl MyApp {
+    pub fn pull_from_server(&mut self) {
+        if self.has_not_saved_data() {
+            show_error("Please save or cancel the changes before pulling");
+            return;
+        }
+
+        match self.vault.pull_from_server() {
+            Ok(PullResult::UpToDate) => {}
+            Ok(PullResult::FastForwarded) => {
+                // Nothing is changed locally, so this only reloads the file keeping the selection
+                self.cancel_not_saved_data();
+            }
+            Ok(PullResult::Diverged { base, theirs }) => {
+                self.merge_remote(base.as_deref(), &theirs);
+            }
+            Err(err) => {
+                show_error(&format!("Can not pull from the sync server. Err: {}", err));
+            }
+        }
+    }
+
+    pub fn push_to_server(&mut self) {
+        if self.has_not_saved_data() {
+            show_error("Please save the changes before pushing");
+            return;
+        }
+
+        if let Some(server_sync) = self.vault.get_server_sync() {
+            if let Err(err) = server_sync.push() {
+                show_error(&format!("Can not push to the sync server. Err: {}", err));
+            }
+        }
+    }
+}
@@ -1,43 +1,294 @@
+use std::collections::BTreeMap;
+
+use my_secrets_core::{
+    export::KeyNamingRules,
+    git_sync::GitSync,
+    remote_storage::{RemoteAuth, S3Storage, WebDavStorage},
+    server_sync::ServerSync,
+    storage::{CachedStorage, Storage},
+};
 use serde::{Deserialize, Serialize};
 
+const SETTINGS_FILE_NAME: &str = ".my-secrets.yaml";
+/// Keychain service of the storage passwords. The account is the user or the access key
+const STORAGE_KEYCHAIN_SERVICE: &str = "my-secrets-storage";
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SettingsModel {
     pub shared_key: String,
+    #[serde(default)]
+    pub auto_lock: AutoLockSettings,
+    #[serde(default)]
+    pub export: BTreeMap<String, KeyNamingRules>,
+    #[serde(default)]
+    pub git: Option<GitSettings>,
+    #[serde(default)]
+    pub sync_server: Option<SyncServerSettings>,
+    #[serde(default)]
+    pub storage: StorageSettings,
+    #[serde(default)]
+    pub agent: AgentSettings,
+    #[serde(default)]
+    pub api: ApiSettings,
 }
 
-impl SettingsModel {
-    pub fn read() -> Self {
-        let key_chain = security_framework::os::macos::keychain::SecKeychain::default().unwrap();
-        let shared_key = key_chain.find_generic_password("my-secrets", "my-secrets");
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SettingsFileModel {
+    #[serde(default)]
+    pub auto_lock: AutoLockSettings,
+    /// Key naming rules for exports by category name
+    #[serde(default)]
+    pub export: BTreeMap<String, KeyNamingRules>,
+    /// Keep the vault directory as a git repository committing every save
+    #[serde(default)]
+    pub git: Option<GitSettings>,
+    /// Pull and push the vault from a `my-secrets serve` server. Git is used if both are configured
+    #[serde(default)]
+    pub sync_server: Option<SyncServerSettings>,
+    /// Keep the vault file on a WebDAV server or in an S3 compatible bucket.
+    /// The local vault file becomes its offline cache
+    #[serde(default)]
+    pub storage: StorageSettings,
+    /// `my-secrets agent` keeping the unlocked key for the CLI and the GUI
+    #[serde(default)]
+    pub agent: AgentSettings,
+    /// Local API for other tools on ~/.my-secrets/api/api.sock while the GUI runs
+    #[serde(default)]
+    pub api: ApiSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GitSettings {
+    /// URL or path of the repository to pull from and push to
+    #[serde(default)]
+    pub remote: Option<String>,
+    #[serde(default = "default_git_branch")]
+    pub branch: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncServerSettings {
+    /// `http://host:port` of the server. Not needed on the machine running it
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Shared by the server and its clients. The server accepts any request without it
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StorageSettings {
+    #[serde(default)]
+    pub webdav: Option<WebDavSettings>,
+    #[serde(default)]
+    pub s3: Option<S3Settings>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebDavSettings {
+    /// URL of the vault file
+    pub url: String,
+    /// URL of the collection to copy backups to
+    #[serde(default)]
+    pub backups_url: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Read from the keychain if not set here
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct S3Settings {
+    /// e.g. http://localhost:9000 for MinIO
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_s3_key")]
+    pub key: String,
+    /// Backups are copied to keys starting with it, e.g. backups/
+    #[serde(default)]
+    pub backups_prefix: Option<String>,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub access_key: String,
+    /// Read from the keychain if not set here
+    #[serde(default)]
+    pub secret_key: Option<String>,
+}
+
+fn default_s3_key() -> String {
+    my_secrets_core::file::DEFAULT_FILE_NAME.to_string()
+}
 
-        if let Err(err) = &shared_key {
-            panic!("Can not read settings from keychain. Err: {}", err);
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentSettings {
+    /// The agent forgets the key once it was not used for this time
+    #[serde(default = "default_agent_idle_timeout_minutes")]
+    pub idle_timeout_minutes: u64,
+}
+
+impl Default for AgentSettings {
+    fn default() -> Self {
+        Self {
+            idle_timeout_minutes: default_agent_idle_timeout_minutes(),
         }
+    }
+}
+
+fn default_agent_idle_timeout_minutes() -> u64 {
+    15
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ApiSettings {
+    /// Off unless enabled. Every client is approved in the GUI the first time it connects
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+fn default_git_branch() -> String {
+    "main".to_string()
+}
 
-        let shared_key = shared_key.unwrap();
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutoLockSettings {
+    #[serde(default = "default_idle_timeout_minutes")]
+    pub idle_timeout_minutes: i64,
+    #[serde(default)]
+    pub lock_on_focus_lost: bool,
+    #[serde(default)]
+    pub lock_on_minimize: bool,
+    #[serde(default)]
+    pub unsaved_edits: UnsavedEditsPolicy,
+    /// Sealed categories lock again after this idle time even if the vault stays unlocked
+    #[serde(default = "default_category_idle_timeout_minutes")]
+    pub category_idle_timeout_minutes: i64,
+}
 
+impl Default for AutoLockSettings {
+    fn default() -> Self {
         Self {
-            shared_key: String::from_utf8(shared_key.0.to_vec()).unwrap(),
+            idle_timeout_minutes: default_idle_timeout_minutes(),
+            lock_on_focus_lost: false,
+            lock_on_minimize: false,
+            unsaved_edits: UnsavedEditsPolicy::default(),
+            category_idle_timeout_minutes: default_category_idle_timeout_minutes(),
         }
+    }
+}
 
-        /*
-        use std::io::Read;
+fn default_idle_timeout_minutes() -> i64 {
+    10
+}
+
+fn default_category_idle_timeout_minutes() -> i64 {
+    2
+}
+
+/// What to do with not saved changes when the vault is being locked
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsavedEditsPolicy {
+    #[default]
+    Prompt,
+    SaveDraft,
+}
+
+impl SettingsModel {
+    /// Fails if the shared key can not be read from the keychain or the settings file is not valid
+    pub fn read() -> Result<Self, String> {
+        let key_chain = security_framework::os::macos::keychain::SecKeychain::default()
+            .map_err(|err| format!("Can not open the keychain. Err: {}", err))?;
+
+        let (shared_key, _) = key_chain
+            .find_generic_password("my-secrets", "my-secrets")
+            .map_err(|err| format!("Can not read settings from keychain. Err: {}", err))?;
 
-        let file_name = crate::file::compile_full_filename(".my-secrets.yaml");
+        let shared_key = String::from_utf8(shared_key.to_vec())
+            .map_err(|_| "The shared key in the keychain is not valid UTF-8".to_string())?;
 
-        let mut file_result = std::fs::File::open(file_name.as_str());
-        if file_result.is_err() {
-            panic!("Can not read settings from file: {}", file_name);
+        if shared_key.as_bytes().len() != 16 {
+            return Err("Shared key must be 16 bytes long".to_string());
         }
-        let mut result = Vec::new();
-        match file_result.as_mut().unwrap().read_to_end(&mut result) {
-            Ok(_) => match serde_yaml::from_slice(&result) {
-                Ok(result) => return result,
-                Err(err) => panic!("Invalid yaml format of file: {}. Err: {}", file_name, err),
-            },
-            Err(_) => panic!("Can not read settings from file: {}", file_name),
+
+        let settings_file = read_settings_file()?;
+
+        Ok(Self {
+            shared_key,
+            auto_lock: settings_file.auto_lock,
+            export: settings_file.export,
+            git: settings_file.git,
+            sync_server: settings_file.sync_server,
+            storage: settings_file.storage,
+            agent: settings_file.agent,
+            api: settings_file.api,
+        })
+    }
+
+    pub fn get_export_rules(&self, category: &str) -> KeyNamingRules {
+        match self.export.get(category) {
+            Some(rules) => rules.clone(),
+            None => KeyNamingRules::default(),
         }
-         */
+    }
+
+    pub fn create_git_sync(&self, vault_file_name: &str) -> Option<GitSync> {
+        let git = self.git.as_ref()?;
+        Some(GitSync::new(
+            vault_file_name,
+            git.remote.clone(),
+            git.branch.clone(),
+        ))
+    }
+
+    pub fn create_server_sync(&self, vault_file_name: &str) -> Option<ServerSync> {
+        let sync_server = self.sync_server.as_ref()?;
+        Some(ServerSync::new(
+            vault_file_name,
+            sync_server.url.clone()?,
+            sync_server.token.clone(),
+        ))
+    }
+
+    /// Remote storage with the vault file as its offline cache
+    pub fn create_storage(&self, vault_file_name: &str) -> Option<Box<dyn Storage>> {
+        let remote: Box<dyn Storage> = if let Some(webdav) = &self.storage.webdav {
+            let auth = match &webdav.user {
+                Some(user) => RemoteAuth::Basic {
+                    user: user.clone(),
+                    password: get_storage_secret(webdav.password.as_ref(), user),
+                },
+                None => RemoteAuth::None,
+            };
+
+            Box::new(WebDavStorage::new(
+                webdav.url.clone(),
+                webdav.backups_url.clone(),
+                auth,
+            ))
+        } else if let Some(s3) = &self.storage.s3 {
+            let auth = RemoteAuth::AwsSigV4 {
+                region: s3.region.clone(),
+                access_key: s3.access_key.clone(),
+                secret_key: get_storage_secret(s3.secret_key.as_ref(), &s3.access_key),
+            };
+
+            Box::new(S3Storage::new(
+                &s3.endpoint,
+                s3.bucket.clone(),
+                s3.key.clone(),
+                s3.backups_prefix.clone(),
+                auth,
+            ))
+        } else {
+            return None;
+        };
+
+        Some(Box::new(CachedStorage::new(remote, vault_file_name)))
     }
 
     pub fn get_iv(&self) -> [u8; 16] {
@@ -49,3 +300,35 @@ impl SettingsModel {
         result
     }
 }
+
+/// Password set in the settings or the one stored in the keychain for the account
+fn get_storage_secret(secret: Option<&String>, account: &str) -> String {
+    if let Some(secret) = secret {
+        return secret.clone();
+    }
+
+    let key_chain = security_framework::os::macos::keychain::SecKeychain::default().unwrap();
+
+    match key_chain.find_generic_password(STORAGE_KEYCHAIN_SERVICE, account) {
+        Ok((password, _)) => String::from_utf8(password.to_vec()).unwrap_or_default(),
+        Err(err) => {
+            eprintln!(
+                "Can not read the storage password of {} from keychain. Err: {}",
+                account, err
+            );
+            String::new()
+        }
+    }
+}
+
+fn read_settings_file() -> Result<SettingsFileModel, String> {
+    let file_name = my_secrets_core::file::compile_full_filename(SETTINGS_FILE_NAME);
+
+    let content = match std::fs::read(file_name.as_str()) {
+        Ok(content) => content,
+        Err(_) => return Ok(SettingsFileModel::default()),
+    };
+
+    serde_yaml::from_slice(&content)
+        .map_err(|err| format!("Invalid settings file: {}. Err: {}", file_name, err))
+}
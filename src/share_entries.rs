@@ -0,0 +1,70 @@
+use my_secrets_core::{address_book::AddressBook, sharing, VaultCommand};
+
+use crate::{
+    native_dialogs::show_error,
+    states::{ContactsState, ImportState, ModalWindowState, ShareState},
+    MyApp,
+};
+
+impl MyApp {
+    pub fn start_share(&mut self, paths: Vec<String>) {
+        self.update_edited_content();
+
+        let address_book = match AddressBook::load() {
+            Ok(address_book) => address_book,
+            Err(err) => {
+                show_error(&err.to_string());
+                return;
+            }
+        };
+
+        self.modal_dialog
+            .set(ModalWindowState::Share(ShareState::new(
+                paths,
+                &address_book,
+            )));
+    }
+
+    pub fn open_contacts(&mut self) {
+        let public_key = self
+            .vault
+            .content()
+            .ok()
+            .and_then(|content| sharing::get_public_key(content).ok());
+
+        self.modal_dialog
+            .set(ModalWindowState::Contacts(ContactsState::new(public_key)));
+    }
+
+    /// The identity is added as a regular entry, so it is written to the vault with the next save
+    pub fn create_identity(&mut self) {
+        let has_category = self
+            .vault
+            .content()
+            .unwrap()
+            .contains_key(sharing::IDENTITY_CATEGORY);
+
+        let text = sharing::generate_identity_text();
+
+        let command = if has_category {
+            VaultCommand::CreateSubCategory {
+                category: sharing::IDENTITY_CATEGORY.to_string(),
+                name: sharing::IDENTITY_SUB_CATEGORY.to_string(),
+                text,
+            }
+        } else {
+            VaultCommand::CreateCategory {
+                name: sharing::IDENTITY_CATEGORY.to_string(),
+                sub_categories: [(sharing::IDENTITY_SUB_CATEGORY.to_string(), text)].into(),
+            }
+        };
+
+        self.execute(command);
+        self.open_contacts();
+    }
+
+    pub fn start_shared_import(&mut self) {
+        self.modal_dialog
+            .set(ModalWindowState::Import(ImportState::new_pasted()));
+    }
+}
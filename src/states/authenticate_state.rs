@@ -1,9 +1,20 @@
-#[derive(Default, Debug)]
+use zeroize::Zeroize;
+
+#[derive(Debug)]
 pub struct AuthenticateState {
     pub error_message: Option<String>,
     pub password: String,
 }
 
+impl Default for AuthenticateState {
+    fn default() -> Self {
+        Self {
+            error_message: None,
+            password: crate::text_buffer::new_secret_buffer(),
+        }
+    }
+}
+
 impl AuthenticateState {
     pub fn render(&mut self, ctx: &egui::Context) -> bool {
         let mut result = false;
@@ -29,4 +40,14 @@ impl AuthenticateState {
 
         result
     }
+
+    pub fn wipe_password(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+impl Drop for AuthenticateState {
+    fn drop(&mut self) {
+        self.wipe_password();
+    }
 }
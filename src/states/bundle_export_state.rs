@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use my_secrets_core::TypeContent;
+use zeroize::Zeroize;
+
+use crate::components::DialogResult;
+
+#[derive(Clone)]
+pub struct BundleExportState {
+    /// Category -> subcategory -> selected
+    pub entries: BTreeMap<String, BTreeMap<String, bool>>,
+    pub password: String,
+    pub password_confirmation: String,
+}
+
+impl BundleExportState {
+    pub fn new(content: &TypeContent, selected_category: Option<&str>) -> Self {
+        Self {
+            entries: content
+                .iter()
+                .map(|(category, sub_categories)| {
+                    let selected = Some(category.as_str()) == selected_category;
+                    let sub_categories = sub_categories
+                        .keys()
+                        .map(|sub_category| (sub_category.to_string(), selected))
+                        .collect();
+
+                    (category.to_string(), sub_categories)
+                })
+                .collect(),
+            password: crate::text_buffer::new_secret_buffer(),
+            password_confirmation: crate::text_buffer::new_secret_buffer(),
+        }
+    }
+
+    /// Fully selected categories as `category`, the rest as `category/subcategory`
+    pub fn get_selected_paths(&self) -> Vec<String> {
+        let mut result = Vec::new();
+
+        for (category, sub_categories) in &self.entries {
+            if sub_categories.is_empty() {
+                continue;
+            }
+
+            if sub_categories.values().all(|selected| *selected) {
+                result.push(category.to_string());
+                continue;
+            }
+
+            for (sub_category, selected) in sub_categories {
+                if *selected {
+                    result.push(format!("{}/{}", category, sub_category));
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<DialogResult> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(400.0);
+
+                ui.heading("Export encrypted bundle");
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for (category, sub_categories) in self.entries.iter_mut() {
+                                let mut all_selected =
+                                    sub_categories.values().all(|selected| *selected);
+
+                                if ui.checkbox(&mut all_selected, category.as_str()).changed() {
+                                    for selected in sub_categories.values_mut() {
+                                        *selected = all_selected;
+                                    }
+                                }
+
+                                ui.indent(category.as_str(), |ui| {
+                                    for (sub_category, selected) in sub_categories.iter_mut() {
+                                        ui.checkbox(selected, sub_category.as_str());
+                                    }
+                                });
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Bundle password:");
+                        ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Repeat password:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.password_confirmation)
+                                .password(true),
+                        );
+                    });
+
+                    let passwords_match = self.password == self.password_confirmation;
+
+                    if !passwords_match {
+                        ui.label("Passwords do not match");
+                    }
+
+                    ui.horizontal(|ui| {
+                        let can_export = passwords_match
+                            && !self.password.is_empty()
+                            && !self.get_selected_paths().is_empty();
+
+                        if ui
+                            .add_enabled(can_export, egui::Button::new("Export"))
+                            .clicked()
+                        {
+                            result = Some(DialogResult::Ok);
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            result = Some(DialogResult::Cancel);
+                        }
+                    });
+                });
+            })
+        });
+
+        result
+    }
+
+    pub fn wipe_password(&mut self) {
+        self.password.zeroize();
+        self.password_confirmation.zeroize();
+    }
+}
+
+impl std::fmt::Debug for BundleExportState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BundleExportState")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl Drop for BundleExportState {
+    fn drop(&mut self) {
+        self.wipe_password();
+    }
+}
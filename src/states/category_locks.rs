@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+/// Idle timers of the unlocked sealed categories
+pub struct CategoryLocks {
+    last_active: BTreeMap<String, DateTimeAsMicroseconds>,
+    idle_timeout_minutes: i64,
+}
+
+impl CategoryLocks {
+    pub fn new(idle_timeout_minutes: i64) -> Self {
+        Self {
+            last_active: BTreeMap::new(),
+            idle_timeout_minutes,
+        }
+    }
+
+    /// Starts timers of the newly unlocked categories and forgets the ones locked or renamed meanwhile
+    pub fn sync(&mut self, unlocked_categories: &[&str]) {
+        self.last_active
+            .retain(|category, _| unlocked_categories.contains(&category.as_str()));
+
+        for category in unlocked_categories {
+            if !self.last_active.contains_key(*category) {
+                self.last_active
+                    .insert(category.to_string(), DateTimeAsMicroseconds::now());
+            }
+        }
+    }
+
+    pub fn extend_expiration_time(&mut self, category: &str) {
+        if let Some(last_active) = self.last_active.get_mut(category) {
+            *last_active = DateTimeAsMicroseconds::now();
+        }
+    }
+
+    pub fn get_expired(&self) -> Vec<String> {
+        if self.idle_timeout_minutes <= 0 {
+            return Vec::new();
+        }
+
+        let now = DateTimeAsMicroseconds::now();
+
+        self.last_active
+            .iter()
+            .filter(|(_, last_active)| {
+                now.duration_since(**last_active).get_full_minutes() >= self.idle_timeout_minutes
+            })
+            .map(|(category, _)| category.to_string())
+            .collect()
+    }
+}
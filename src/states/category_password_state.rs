@@ -0,0 +1,112 @@
+use zeroize::Zeroize;
+
+use crate::components::DialogResult;
+
+/// Password of a sealed category. Sealing asks for it twice
+pub struct CategoryPasswordState {
+    pub category: String,
+    pub sealing: bool,
+    pub password: String,
+    pub password_confirmation: String,
+    pub error_message: Option<String>,
+}
+
+impl CategoryPasswordState {
+    pub fn new(category: String, sealing: bool) -> Self {
+        Self {
+            category,
+            sealing,
+            password: crate::text_buffer::new_secret_buffer(),
+            password_confirmation: crate::text_buffer::new_secret_buffer(),
+            error_message: None,
+        }
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<DialogResult> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(400.0);
+
+                if self.sealing {
+                    ui.heading(format!("Seal category {}", self.category));
+                } else {
+                    ui.heading(format!("Unlock category {}", self.category));
+                }
+
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    if self.sealing {
+                        ui.label(
+                            "The category will require this password in addition to the master one",
+                        );
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Category password:");
+                        ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                    });
+
+                    let mut can_submit = !self.password.is_empty();
+
+                    if self.sealing {
+                        ui.horizontal(|ui| {
+                            ui.label("Repeat password:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.password_confirmation)
+                                    .password(true),
+                            );
+                        });
+
+                        if self.password != self.password_confirmation {
+                            ui.label("Passwords do not match");
+                            can_submit = false;
+                        }
+                    }
+
+                    if let Some(err) = &self.error_message {
+                        ui.add(egui::Label::new(err));
+                    }
+
+                    ui.horizontal(|ui| {
+                        let button = if self.sealing { "Seal" } else { "Unlock" };
+
+                        if ui
+                            .add_enabled(can_submit, egui::Button::new(button))
+                            .clicked()
+                        {
+                            result = Some(DialogResult::Ok);
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            result = Some(DialogResult::Cancel);
+                        }
+                    });
+                });
+            })
+        });
+
+        result
+    }
+
+    pub fn wipe_password(&mut self) {
+        self.password.zeroize();
+        self.password_confirmation.zeroize();
+    }
+}
+
+impl std::fmt::Debug for CategoryPasswordState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CategoryPasswordState")
+            .field("category", &self.category)
+            .field("sealing", &self.sealing)
+            .finish()
+    }
+}
+
+impl Drop for CategoryPasswordState {
+    fn drop(&mut self) {
+        self.wipe_password();
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeSet;
+
+use my_secrets_core::{TypeContent, VaultCommand};
+
+/// Keeps track of categories and subcategories which were changed since the last save
+#[derive(Default)]
+pub struct ChangeTracker {
+    dirty_categories: BTreeSet<String>,
+    dirty_sub_categories: BTreeSet<(String, String)>,
+}
+
+impl ChangeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty_categories.is_empty() || !self.dirty_sub_categories.is_empty()
+    }
+
+    pub fn is_category_dirty(&self, category: &str) -> bool {
+        self.dirty_categories.contains(category)
+            || self
+                .dirty_sub_categories
+                .iter()
+                .any(|(dirty_category, _)| dirty_category == category)
+    }
+
+    pub fn is_sub_category_dirty(&self, category: &str, sub_category: &str) -> bool {
+        self.dirty_sub_categories
+            .contains(&(category.to_string(), sub_category.to_string()))
+    }
+
+    pub fn dirty_entries_amount(&self) -> usize {
+        self.dirty_categories.len() + self.dirty_sub_categories.len()
+    }
+
+    pub fn mark_category(&mut self, category: &str) {
+        if !self.dirty_categories.contains(category) {
+            self.dirty_categories.insert(category.to_string());
+        }
+    }
+
+    pub fn mark_sub_category(&mut self, category: &str, sub_category: &str) {
+        let key = (category.to_string(), sub_category.to_string());
+        if !self.dirty_sub_categories.contains(&key) {
+            self.dirty_sub_categories.insert(key);
+        }
+    }
+
+    pub fn mark_command(&mut self, command: &VaultCommand) {
+        match command {
+            VaultCommand::EditText {
+                category,
+                sub_category,
+                ..
+            } => {
+                self.mark_sub_category(category, sub_category);
+            }
+            VaultCommand::CreateCategory { name, .. }
+            | VaultCommand::DeleteCategory { name, .. } => {
+                self.mark_category(name);
+            }
+            VaultCommand::RenameCategory { from, to } => {
+                self.mark_category(from);
+                self.mark_category(to);
+            }
+            VaultCommand::CreateSubCategory { category, name, .. }
+            | VaultCommand::DeleteSubCategory { category, name, .. } => {
+                self.mark_sub_category(category, name);
+            }
+            VaultCommand::RenameSubCategory { category, from, to } => {
+                self.mark_sub_category(category, from);
+                self.mark_sub_category(category, to);
+            }
+            VaultCommand::MoveSubCategory {
+                name,
+                from_category,
+                to_category,
+            } => {
+                self.mark_sub_category(from_category, name);
+                self.mark_sub_category(to_category, name);
+            }
+        }
+    }
+
+    /// Marks everything which differs between the saved content and the current one
+    pub fn mark_differences(&mut self, saved: &TypeContent, current: &TypeContent) {
+        for (category, sub_categories) in current {
+            match saved.get(category) {
+                Some(saved_sub_categories) => {
+                    for (sub_category, text) in sub_categories {
+                        if saved_sub_categories.get(sub_category) != Some(text) {
+                            self.mark_sub_category(category, sub_category);
+                        }
+                    }
+
+                    for sub_category in saved_sub_categories.keys() {
+                        if !sub_categories.contains_key(sub_category) {
+                            self.mark_sub_category(category, sub_category);
+                        }
+                    }
+                }
+                None => {
+                    self.mark_category(category);
+                }
+            }
+        }
+
+        for category in saved.keys() {
+            if !current.contains_key(category) {
+                self.mark_category(category);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.dirty_categories.clear();
+        self.dirty_sub_categories.clear();
+    }
+}
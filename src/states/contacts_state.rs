@@ -0,0 +1,128 @@
+use my_secrets_core::address_book::AddressBook;
+
+pub enum ContactsAction {
+    CreateIdentity,
+    Close,
+}
+
+#[derive(Debug)]
+pub struct ContactsState {
+    /// None if the vault has no identity yet
+    pub public_key: Option<String>,
+    pub address_book: AddressBook,
+    pub new_name: String,
+    pub new_public_key: String,
+    pub error_message: Option<String>,
+}
+
+impl ContactsState {
+    pub fn new(public_key: Option<String>) -> Self {
+        let (address_book, error_message) = match AddressBook::load() {
+            Ok(address_book) => (address_book, None),
+            Err(err) => (AddressBook::default(), Some(err.to_string())),
+        };
+
+        Self {
+            public_key,
+            address_book,
+            new_name: String::new(),
+            new_public_key: String::new(),
+            error_message,
+        }
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<ContactsAction> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(500.0);
+
+                ui.heading("Identity and contacts");
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    match &self.public_key {
+                        Some(public_key) => {
+                            ui.label("Your public key:");
+                            ui.horizontal(|ui| {
+                                ui.monospace(public_key.as_str());
+                                if ui.small_button("Copy").clicked() {
+                                    ui.ctx().copy_text(public_key.to_string());
+                                }
+                            });
+                        }
+                        None => {
+                            ui.label("The vault has no identity to receive shared entries");
+                            if ui.button("Create identity").clicked() {
+                                result = Some(ContactsAction::CreateIdentity);
+                            }
+                        }
+                    }
+
+                    ui.separator();
+                    ui.label("Contacts:");
+
+                    let mut removed = None;
+                    for (name, public_key) in &self.address_book.contacts {
+                        ui.horizontal(|ui| {
+                            ui.label(name.as_str());
+                            ui.monospace(public_key.as_str());
+                            if ui.small_button("Remove").clicked() {
+                                removed = Some(name.to_string());
+                            }
+                        });
+                    }
+
+                    if let Some(name) = removed {
+                        self.address_book.remove(&name);
+                        self.save();
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("Name"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.new_public_key)
+                                .hint_text("age1..."),
+                        );
+
+                        if ui.button("Add").clicked() {
+                            self.add_contact();
+                        }
+                    });
+
+                    if let Some(err) = &self.error_message {
+                        ui.add(egui::Label::new(err));
+                    }
+
+                    if ui.button("Close").clicked() {
+                        result = Some(ContactsAction::Close);
+                    }
+                });
+            })
+        });
+
+        result
+    }
+
+    fn add_contact(&mut self) {
+        let name = self.new_name.trim().to_string();
+
+        if name.is_empty() {
+            self.error_message = Some("Name can not be empty".to_string());
+            return;
+        }
+
+        if let Err(err) = self.address_book.add(&name, &self.new_public_key) {
+            self.error_message = Some(err.to_string());
+            return;
+        }
+
+        self.new_name.clear();
+        self.new_public_key.clear();
+        self.save();
+    }
+
+    fn save(&mut self) {
+        self.error_message = self.address_book.save().err().map(|err| err.to_string());
+    }
+}
@@ -6,6 +6,16 @@ pub enum ModalWindowState {
     CreateCategory(String),
     RenameCategory(String),
     CreateSubCategory(String),
+    RenameSubCategory(String),
+    MoveSubCategory(String),
+    Export(ExportState),
+    Import(ImportState),
+    ExportBundle(BundleExportState),
+    Share(ShareState),
+    Contacts(ContactsState),
+    Members(MembersState),
+    CategoryPassword(CategoryPasswordState),
+    Merge(MergeState),
     None,
 }
 
@@ -25,6 +35,10 @@ impl ModalDialog {
     pub fn set_none(&mut self) {
         self.inner = ModalWindowState::None;
     }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self.inner, ModalWindowState::None)
+    }
 }
 
 impl Default for ModalDialog {
@@ -1,15 +1,18 @@
 use rust_extensions::date_time::DateTimeAsMicroseconds;
+use zeroize::Zeroize;
 
 pub struct EditingState {
     editing: Option<String>,
     last_active: DateTimeAsMicroseconds,
+    idle_timeout_minutes: i64,
 }
 
 impl EditingState {
-    pub fn new() -> Self {
+    pub fn new(idle_timeout_minutes: i64) -> Self {
         Self {
             editing: None,
             last_active: DateTimeAsMicroseconds::now(),
+            idle_timeout_minutes,
         }
     }
 
@@ -17,8 +20,15 @@ impl EditingState {
         self.editing.is_some()
     }
 
-    pub fn finish_editing(&mut self) -> String {
-        self.editing.take().unwrap()
+    pub fn finish_editing(&mut self) -> Option<String> {
+        self.editing.take()
+    }
+
+    /// Forgets the copy of the text taken when editing started and wipes it from memory
+    pub fn wipe(&mut self) {
+        if let Some(mut editing) = self.editing.take() {
+            editing.zeroize();
+        }
     }
 
     pub fn extend_expiration_time(&mut self) {
@@ -30,13 +40,13 @@ impl EditingState {
         self.last_active = DateTimeAsMicroseconds::now();
     }
 
-    pub fn activity_expired(&mut self) -> bool {
-        if self.editing.is_some() {
+    pub fn activity_expired(&self) -> bool {
+        if self.idle_timeout_minutes <= 0 {
             return false;
         }
 
         let now = DateTimeAsMicroseconds::now();
-        now.duration_since(self.last_active).get_full_minutes() >= 10
+        now.duration_since(self.last_active).get_full_minutes() >= self.idle_timeout_minutes
     }
 
     pub fn editing_value(&self) -> &str {
@@ -50,3 +60,9 @@ impl EditingState {
         self.editing.as_ref().unwrap()
     }
 }
+
+impl Drop for EditingState {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use my_secrets_core::export::ExportFormat;
+
+use crate::components::DialogResult;
+
+#[derive(Debug, Clone)]
+pub struct ExportState {
+    pub category: String,
+    pub entries: BTreeMap<String, bool>,
+    pub format: ExportFormat,
+    pub name: String,
+    pub namespace: String,
+}
+
+impl ExportState {
+    pub fn new<'s>(category: &str, sub_categories: impl Iterator<Item = &'s String>) -> Self {
+        Self {
+            category: category.to_string(),
+            entries: sub_categories
+                .map(|sub_category| (sub_category.to_string(), true))
+                .collect(),
+            format: ExportFormat::Kubernetes,
+            name: category.to_string(),
+            namespace: String::new(),
+        }
+    }
+
+    /// Entries to export. None means the whole category
+    pub fn get_selected_entries(&self) -> Option<Vec<String>> {
+        if self.entries.values().all(|selected| *selected) {
+            return None;
+        }
+
+        Some(
+            self.entries
+                .iter()
+                .filter(|(_, selected)| **selected)
+                .map(|(entry, _)| entry.to_string())
+                .collect(),
+        )
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<DialogResult> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(400.0);
+
+                ui.heading(format!("Export category {}", self.category));
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    ui.horizontal(|ui| {
+                        for format in [
+                            ExportFormat::Kubernetes,
+                            ExportFormat::Env,
+                            ExportFormat::DockerCompose,
+                        ] {
+                            ui.radio_value(&mut self.format, format, format.get_name());
+                        }
+                    });
+
+                    if self.format == ExportFormat::Kubernetes {
+                        ui.horizontal(|ui| {
+                            ui.label("Secret name:");
+                            ui.add(egui::TextEdit::singleline(&mut self.name));
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Namespace:");
+                            ui.add(egui::TextEdit::singleline(&mut self.namespace));
+                        });
+                    }
+
+                    ui.label("Entries:");
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for (entry, selected) in self.entries.iter_mut() {
+                                ui.checkbox(selected, entry.as_str());
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        let has_selected = self.entries.values().any(|selected| *selected);
+                        if ui
+                            .add_enabled(has_selected, egui::Button::new("Export"))
+                            .clicked()
+                        {
+                            result = Some(DialogResult::Ok);
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            result = Some(DialogResult::Cancel);
+                        }
+                    });
+                });
+            })
+        });
+
+        result
+    }
+}
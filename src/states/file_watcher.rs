@@ -0,0 +1,73 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Notices writes of other programs to the vault file. The directory is watched,
+/// since sync services replace the file instead of writing into it
+pub struct FileWatcher {
+    changed: Arc<AtomicBool>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl FileWatcher {
+    /// Without the watcher changes are still noticed on save
+    pub fn start(file_name: &str) -> Self {
+        let changed = Arc::new(AtomicBool::new(false));
+
+        let watcher = match create_watcher(file_name, changed.clone()) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                eprintln!("Can not watch the file: {}. Err: {}", file_name, err);
+                None
+            }
+        };
+
+        Self {
+            changed,
+            _watcher: watcher,
+        }
+    }
+
+    /// True once after each change of the file
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+fn create_watcher(file_name: &str, changed: Arc<AtomicBool>) -> notify::Result<RecommendedWatcher> {
+    let path = Path::new(file_name);
+    let watched_name = path.file_name().map(|name| name.to_os_string());
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+
+        // Our own reads of the file
+        if event.kind.is_access() {
+            return;
+        }
+
+        if event
+            .paths
+            .iter()
+            .any(|path| path.file_name() == watched_name.as_deref())
+        {
+            changed.store(true, Ordering::Relaxed);
+        }
+    })?;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
@@ -0,0 +1,268 @@
+use my_secrets_core::{
+    import::{
+        self, ConflictResolution, GroupMapping, ImportAction, ImportFormat, ImportOptions,
+        ImportPlan, ImportedEntry,
+    },
+    sharing, TypeContent, VaultError,
+};
+use zeroize::Zeroize;
+
+use crate::components::DialogResult;
+
+pub struct ImportState {
+    /// Empty when the shared entries are pasted as text
+    pub file_name: String,
+    pub pasted: String,
+    pub format: ImportFormat,
+    pub password: String,
+    pub key_file: Option<String>,
+    pub mapping: GroupMapping,
+    pub resolution: ConflictResolution,
+    pub error_message: Option<String>,
+    pub plan: Option<ImportPlan>,
+}
+
+impl ImportState {
+    pub fn new(file_name: String) -> Self {
+        Self {
+            format: ImportFormat::from_file_name(&file_name).unwrap_or(ImportFormat::Chrome),
+            file_name,
+            pasted: String::new(),
+            password: crate::text_buffer::new_secret_buffer(),
+            key_file: None,
+            mapping: GroupMapping::Flatten,
+            resolution: ConflictResolution::Skip,
+            error_message: None,
+            plan: None,
+        }
+    }
+
+    /// Shared entries pasted from a message instead of a file
+    pub fn new_pasted() -> Self {
+        let mut result = Self::new(String::new());
+        result.format = ImportFormat::Shared;
+        result
+    }
+
+    /// Opens the database and builds the preview of the import
+    pub fn read_database(&mut self, content: &TypeContent) {
+        let result = if self.file_name.is_empty() {
+            sharing::read_shared(content, self.pasted.as_bytes())
+        } else {
+            self.read_file(content)
+        };
+        self.wipe_password();
+
+        match result {
+            Ok(entries) => {
+                self.error_message = None;
+                self.plan = Some(ImportPlan::new(content, entries, self.resolution));
+            }
+            Err(err) => {
+                self.error_message = Some(err.to_string());
+            }
+        }
+    }
+
+    fn read_file(&self, content: &TypeContent) -> Result<Vec<ImportedEntry>, VaultError> {
+        import::read_entries(
+            self.format,
+            &self.file_name,
+            &ImportOptions {
+                password: Some(self.password.as_str()),
+                key_file: self.key_file.as_deref(),
+                mapping: self.mapping,
+                vault_content: Some(content),
+            },
+        )
+    }
+
+    /// Returns Ok when the database should be read or, once the preview is shown, imported
+    pub fn render(&mut self, ctx: &egui::Context, content: &TypeContent) -> Option<DialogResult> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(500.0);
+
+                if self.file_name.is_empty() {
+                    ui.heading("Import shared entries");
+                } else {
+                    ui.heading(format!("Import {}", self.file_name));
+                }
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    if let Some(plan) = &mut self.plan {
+                        render_preview(ui, plan, content, &mut self.resolution, &mut result);
+                    } else {
+                        self.render_open_database(ui, &mut result);
+                    }
+
+                    if let Some(err) = &self.error_message {
+                        ui.add(egui::Label::new(err));
+                    }
+                });
+            })
+        });
+
+        result
+    }
+
+    fn render_open_database(&mut self, ui: &mut egui::Ui, result: &mut Option<DialogResult>) {
+        if self.file_name.is_empty() {
+            ui.label("Paste the shared text:");
+            ui.add(
+                egui::TextEdit::multiline(&mut self.pasted)
+                    .code_editor()
+                    .desired_rows(10),
+            );
+        } else {
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                for format in ImportFormat::ALL {
+                    ui.radio_value(&mut self.format, format, format.get_name());
+                }
+            });
+        }
+
+        if self.format.requires_password() {
+            ui.horizontal(|ui| {
+                ui.label("Password:");
+                ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+            });
+        }
+
+        if self.format == ImportFormat::KeePass {
+            self.render_keepass_options(ui);
+        }
+
+        ui.horizontal(|ui| {
+            let can_open = (!self.file_name.is_empty() || !self.pasted.trim().is_empty())
+                && (!self.format.requires_password()
+                    || !self.password.is_empty()
+                    || (self.format == ImportFormat::KeePass && self.key_file.is_some()));
+            if ui
+                .add_enabled(can_open, egui::Button::new("Open"))
+                .clicked()
+            {
+                *result = Some(DialogResult::Ok);
+            }
+
+            if ui.button("Cancel").clicked() {
+                *result = Some(DialogResult::Cancel);
+            }
+        });
+    }
+
+    fn render_keepass_options(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Key file:");
+            ui.label(self.key_file.as_deref().unwrap_or("none"));
+
+            if ui.small_button("Choose").clicked() {
+                if let Some(key_file) =
+                    crate::native_dialogs::show(|| rfd::FileDialog::new().pick_file())
+                {
+                    self.key_file = Some(key_file.to_string_lossy().to_string());
+                }
+            }
+
+            if self.key_file.is_some() && ui.small_button("Clear").clicked() {
+                self.key_file = None;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Nested groups:");
+            ui.radio_value(&mut self.mapping, GroupMapping::Flatten, "flatten");
+            ui.radio_value(
+                &mut self.mapping,
+                GroupMapping::Nested,
+                "separate categories",
+            );
+        });
+    }
+
+    pub fn wipe_password(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+fn render_preview(
+    ui: &mut egui::Ui,
+    plan: &mut ImportPlan,
+    content: &TypeContent,
+    resolution: &mut ConflictResolution,
+    result: &mut Option<DialogResult>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Existing subcategories:");
+        for value in [
+            ConflictResolution::Skip,
+            ConflictResolution::Overwrite,
+            ConflictResolution::KeepBoth,
+        ] {
+            ui.radio_value(resolution, value, value.get_name());
+        }
+    });
+
+    let mut changed = plan.resolution != *resolution;
+
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            for item in plan.items.iter_mut() {
+                let action = match item.action {
+                    ImportAction::Create => "new".to_string(),
+                    ImportAction::Unchanged => "unchanged".to_string(),
+                    ImportAction::Skip => "exists, skip".to_string(),
+                    ImportAction::Overwrite => "exists, overwrite".to_string(),
+                    ImportAction::KeepBoth => format!("as {}", item.target_sub_category),
+                };
+
+                let label = format!(
+                    "{}/{} ({})",
+                    item.entry.category, item.entry.sub_category, action
+                );
+
+                if ui.checkbox(&mut item.include, label).changed() {
+                    changed = true;
+                }
+            }
+        });
+
+    if changed {
+        plan.rebuild(content, *resolution);
+    }
+
+    ui.label(plan.summary().to_string());
+
+    ui.horizontal(|ui| {
+        if ui.button("Import").clicked() {
+            *result = Some(DialogResult::Ok);
+        }
+
+        if ui.button("Cancel").clicked() {
+            *result = Some(DialogResult::Cancel);
+        }
+    });
+}
+
+impl std::fmt::Debug for ImportState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportState")
+            .field("file_name", &self.file_name)
+            .field("format", &self.format)
+            .field("key_file", &self.key_file)
+            .field("mapping", &self.mapping)
+            .field("resolution", &self.resolution)
+            .field("error_message", &self.error_message)
+            .finish()
+    }
+}
+
+impl Drop for ImportState {
+    fn drop(&mut self) {
+        self.wipe_password();
+    }
+}
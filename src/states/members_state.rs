@@ -0,0 +1,221 @@
+use my_secrets_core::{
+    team::{MemberKey, TeamMember},
+    Vault, VaultError,
+};
+use zeroize::Zeroize;
+
+pub enum MembersAction {
+    ConvertToTeam,
+    AddMember,
+    RemoveMember(String),
+    Close,
+}
+
+pub struct MembersState {
+    pub members: Vec<TeamMember>,
+    /// None if the vault is not a team vault yet
+    pub member_name: Option<String>,
+    pub name: String,
+    pub use_public_key: bool,
+    pub public_key: String,
+    pub password: String,
+    pub password_confirmation: String,
+    pub error_message: Option<String>,
+}
+
+impl MembersState {
+    pub fn new(vault: &Vault) -> Self {
+        let mut result = Self {
+            members: Vec::new(),
+            member_name: None,
+            name: String::new(),
+            use_public_key: false,
+            public_key: String::new(),
+            password: crate::text_buffer::new_secret_buffer(),
+            password_confirmation: crate::text_buffer::new_secret_buffer(),
+            error_message: None,
+        };
+
+        result.refresh(vault);
+        result
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<MembersAction> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(500.0);
+
+                ui.heading("Team members");
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    match &self.member_name {
+                        Some(member_name) => {
+                            for member in &self.members {
+                                ui.horizontal(|ui| {
+                                    ui.label(member.name.as_str());
+
+                                    if member.is_password_member() {
+                                        ui.label("password");
+                                    } else {
+                                        ui.monospace(member.public_key.as_str());
+                                    }
+
+                                    if &member.name == member_name {
+                                        ui.label("(you)");
+                                    } else if ui.small_button("Remove").clicked() {
+                                        result =
+                                            Some(MembersAction::RemoveMember(member.name.clone()));
+                                    }
+                                });
+                            }
+
+                            ui.separator();
+                            ui.label("Add member:");
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut self.use_public_key, false, "Password");
+                                ui.radio_value(&mut self.use_public_key, true, "Public key");
+                            });
+                        }
+                        None => {
+                            ui.label(
+                                "The vault is encrypted with the master password. \
+                                 Convert it to a team vault to share it with other members. \
+                                 You become the first member with the name and the password below",
+                            );
+                        }
+                    }
+
+                    ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("Name"));
+
+                    let can_submit = if self.use_public_key && self.member_name.is_some() {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.public_key).hint_text("age1..."),
+                        );
+
+                        !self.public_key.trim().is_empty()
+                    } else {
+                        ui.horizontal(|ui| {
+                            ui.label("Password:");
+                            ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Repeat password:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.password_confirmation)
+                                    .password(true),
+                            );
+                        });
+
+                        let passwords_match = self.password == self.password_confirmation;
+
+                        if !passwords_match {
+                            ui.label("Passwords do not match");
+                        }
+
+                        passwords_match && !self.password.is_empty()
+                    };
+
+                    let can_submit = can_submit && !self.name.trim().is_empty();
+
+                    ui.horizontal(|ui| {
+                        let (button, action) = match self.member_name {
+                            Some(_) => ("Add", MembersAction::AddMember),
+                            None => ("Convert to team vault", MembersAction::ConvertToTeam),
+                        };
+
+                        if ui
+                            .add_enabled(can_submit, egui::Button::new(button))
+                            .clicked()
+                        {
+                            result = Some(action);
+                        }
+
+                        if ui.button("Close").clicked() {
+                            result = Some(MembersAction::Close);
+                        }
+                    });
+
+                    if let Some(err) = &self.error_message {
+                        ui.add(egui::Label::new(err));
+                    }
+                });
+            })
+        });
+
+        result
+    }
+
+    /// Applies the action to the vault and saves it right away,
+    /// a removed member must not be able to read the file written with the old key
+    pub fn apply(&mut self, vault: &mut Vault, action: MembersAction) {
+        let name = self.name.trim().to_string();
+
+        let result = match action {
+            MembersAction::ConvertToTeam => vault.convert_to_team(&name, &self.password),
+            MembersAction::AddMember => {
+                let key = if self.use_public_key {
+                    MemberKey::PublicKey(&self.public_key)
+                } else {
+                    MemberKey::Password(&self.password)
+                };
+
+                vault.add_team_member(&name, key)
+            }
+            MembersAction::RemoveMember(name) => vault.remove_team_member(&name),
+            MembersAction::Close => return,
+        };
+
+        match result.and_then(|_| vault.save()) {
+            Ok(()) => {
+                self.name.clear();
+                self.public_key.clear();
+                self.wipe_password();
+                self.error_message = None;
+            }
+            Err(err) => {
+                self.error_message = Some(err.to_string());
+            }
+        }
+
+        self.refresh(vault);
+    }
+
+    fn refresh(&mut self, vault: &Vault) {
+        self.members = vault
+            .get_team_members()
+            .map(|members| members.to_vec())
+            .unwrap_or_default();
+
+        self.member_name = match vault.get_team_member_name() {
+            Ok(member_name) => Some(member_name.to_string()),
+            Err(VaultError::Team(_)) => None,
+            Err(err) => {
+                self.error_message = Some(err.to_string());
+                None
+            }
+        };
+    }
+
+    pub fn wipe_password(&mut self) {
+        self.password.zeroize();
+        self.password_confirmation.zeroize();
+    }
+}
+
+impl std::fmt::Debug for MembersState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MembersState")
+            .field("members", &self.members)
+            .field("member_name", &self.member_name)
+            .finish()
+    }
+}
+
+impl Drop for MembersState {
+    fn drop(&mut self) {
+        self.wipe_password();
+    }
+}
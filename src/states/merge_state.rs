@@ -0,0 +1,115 @@
+use my_secrets_core::{
+    entry_ids::EntryIds,
+    merge::{MergeChoice, MergeEntry, MergeResult},
+    TypeContent,
+};
+
+use crate::components::DialogResult;
+
+const DELETED: &str = "(deleted)";
+
+#[derive(Debug, Clone, Copy)]
+pub enum MergeSource {
+    /// Pulled from the git remote. The result is saved as the merge commit
+    Remote,
+    /// Copy of the vault file picked by the user. The result becomes not saved changes
+    Copy,
+    /// The vault file written by another program. The result is saved over it
+    FileChange,
+}
+
+/// Entries changed on both sides. Each conflict takes our or their version
+pub struct MergeState {
+    pub source: MergeSource,
+    pub result: MergeResult,
+    pub choices: Vec<MergeChoice>,
+}
+
+impl MergeState {
+    pub fn new(source: MergeSource, result: MergeResult) -> Self {
+        Self {
+            source,
+            choices: vec![MergeChoice::Ours; result.conflicts.len()],
+            result,
+        }
+    }
+
+    pub fn resolve(&mut self) -> (TypeContent, EntryIds) {
+        self.result.resolve(&self.choices)
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<DialogResult> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(700.0);
+
+                ui.heading("Resolve conflicts");
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    ui.label("These entries were changed both here and on the other side");
+
+                    egui::ScrollArea::vertical()
+                        .max_height(500.0)
+                        .show(ui, |ui| {
+                            for (index, conflict) in self.result.conflicts.iter().enumerate() {
+                                ui.separator();
+                                ui.strong(conflict.get_path());
+
+                                ui.columns(2, |columns| {
+                                    columns[0].radio_value(
+                                        &mut self.choices[index],
+                                        MergeChoice::Ours,
+                                        "Keep mine",
+                                    );
+                                    render_entry(&mut columns[0], conflict.ours.as_ref());
+
+                                    columns[1].radio_value(
+                                        &mut self.choices[index],
+                                        MergeChoice::Theirs,
+                                        "Take theirs",
+                                    );
+                                    render_entry(&mut columns[1], conflict.theirs.as_ref());
+                                });
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Merge").clicked() {
+                            result = Some(DialogResult::Ok);
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            result = Some(DialogResult::Cancel);
+                        }
+                    });
+                });
+            })
+        });
+
+        result
+    }
+}
+
+/// Name of the entry on that side and its text
+fn render_entry(ui: &mut egui::Ui, entry: Option<&MergeEntry>) {
+    match entry {
+        Some(entry) => {
+            ui.weak(format!("{}/{}", entry.category, entry.sub_category));
+            ui.monospace(&entry.text);
+        }
+        None => {
+            ui.monospace(DELETED);
+        }
+    }
+}
+
+impl std::fmt::Debug for MergeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MergeState")
+            .field("source", &self.source)
+            .field("conflicts", &self.result.conflicts.len())
+            .finish()
+    }
+}
@@ -4,7 +4,27 @@ mod authenticate_state;
 pub use authenticate_state::*;
 mod editing_state;
 pub use editing_state::*;
-mod authenticated_state;
-pub use authenticated_state::*;
 mod selected_sub_category;
 pub use selected_sub_category::*;
+mod change_tracker;
+pub use change_tracker::*;
+mod export_state;
+pub use export_state::*;
+mod import_state;
+pub use import_state::*;
+mod bundle_export_state;
+pub use bundle_export_state::*;
+mod share_state;
+pub use share_state::*;
+mod contacts_state;
+pub use contacts_state::*;
+mod members_state;
+pub use members_state::*;
+mod category_locks;
+pub use category_locks::*;
+mod category_password_state;
+pub use category_password_state::*;
+mod merge_state;
+pub use merge_state::*;
+mod file_watcher;
+pub use file_watcher::*;
@@ -1,4 +1,6 @@
-#[derive(Default, Clone)]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[derive(Default, Clone, Zeroize, ZeroizeOnDrop)]
 pub struct SelectedSubCategoryState {
     pub id: String,
     pub text: String,
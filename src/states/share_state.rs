@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use my_secrets_core::{address_book::AddressBook, sharing::SHARED_EXTENSION};
+
+use crate::components::DialogResult;
+
+pub struct ShareState {
+    pub paths: Vec<String>,
+    /// Contact name -> selected
+    pub contacts: BTreeMap<String, bool>,
+    pub public_key: String,
+    pub error_message: Option<String>,
+    /// Armored age file once the entries are encrypted
+    pub shared: Option<String>,
+}
+
+impl ShareState {
+    pub fn new(paths: Vec<String>, address_book: &AddressBook) -> Self {
+        Self {
+            paths,
+            contacts: address_book
+                .contacts
+                .keys()
+                .map(|name| (name.to_string(), false))
+                .collect(),
+            public_key: String::new(),
+            error_message: None,
+            shared: None,
+        }
+    }
+
+    /// Selected contact names and the public key typed in
+    pub fn get_recipients(&self) -> Vec<String> {
+        let mut result: Vec<String> = self
+            .contacts
+            .iter()
+            .filter(|(_, selected)| **selected)
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        if !self.public_key.trim().is_empty() {
+            result.push(self.public_key.trim().to_string());
+        }
+
+        result
+    }
+
+    /// Returns Ok when the entries should be encrypted
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<DialogResult> {
+        let mut result = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_width(500.0);
+
+                ui.heading(format!("Share {}", self.paths.join(", ")));
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::new(2.0, 10.0);
+
+                    match &self.shared {
+                        None => self.render_recipients(ui, &mut result),
+                        Some(shared) => render_shared(ui, shared, &mut result),
+                    }
+
+                    if let Some(err) = &self.error_message {
+                        ui.add(egui::Label::new(err));
+                    }
+                });
+            })
+        });
+
+        result
+    }
+
+    fn render_recipients(&mut self, ui: &mut egui::Ui, result: &mut Option<DialogResult>) {
+        ui.label("Recipients:");
+
+        if self.contacts.is_empty() {
+            ui.label("The address book is empty");
+        }
+
+        for (name, selected) in self.contacts.iter_mut() {
+            ui.checkbox(selected, name.as_str());
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Public key:");
+            ui.add(egui::TextEdit::singleline(&mut self.public_key).hint_text("age1..."));
+        });
+
+        ui.horizontal(|ui| {
+            let has_recipients = !self.get_recipients().is_empty();
+            if ui
+                .add_enabled(has_recipients, egui::Button::new("Share"))
+                .clicked()
+            {
+                *result = Some(DialogResult::Ok);
+            }
+
+            if ui.button("Cancel").clicked() {
+                *result = Some(DialogResult::Cancel);
+            }
+        });
+    }
+}
+
+fn render_shared(ui: &mut egui::Ui, shared: &str, result: &mut Option<DialogResult>) {
+    ui.label("Send this text or file to the recipients:");
+
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            let mut text = shared;
+            ui.add(egui::TextEdit::multiline(&mut text).code_editor());
+        });
+
+    ui.horizontal(|ui| {
+        if ui.button("Copy").clicked() {
+            ui.ctx().copy_text(shared.to_string());
+        }
+
+        if ui.button("Save to file").clicked() {
+            if let Some(file_name) = crate::native_dialogs::show(|| {
+                rfd::FileDialog::new()
+                    .set_file_name(format!("shared.{}", SHARED_EXTENSION))
+                    .save_file()
+            }) {
+                if let Err(err) = my_secrets_core::file::write_private_file(
+                    &file_name.to_string_lossy(),
+                    shared.as_bytes(),
+                ) {
+                    crate::native_dialogs::show_error(&format!(
+                        "Can not write file {}. Err: {}",
+                        file_name.display(),
+                        err
+                    ));
+                }
+            }
+        }
+
+        if ui.button("Close").clicked() {
+            *result = Some(DialogResult::Cancel);
+        }
+    });
+}
+
+impl std::fmt::Debug for ShareState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShareState")
+            .field("paths", &self.paths)
+            .field("contacts", &self.contacts)
+            .finish()
+    }
+}
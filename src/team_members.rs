@@ -0,0 +1,18 @@
+use crate::{
+    native_dialogs::show_error,
+    states::{MembersState, ModalWindowState},
+    MyApp,
+};
+
+impl MyApp {
+    /// Member changes write the vault file at once, so other changes have to be saved first
+    pub fn open_members(&mut self) {
+        if self.has_not_saved_data() {
+            show_error("Please save or cancel the changes before managing team members");
+            return;
+        }
+
+        self.modal_dialog
+            .set(ModalWindowState::Members(MembersState::new(&self.vault)));
+    }
+}
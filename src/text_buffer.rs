@@ -1,4 +1,5 @@
 use egui::TextBuffer;
+use zeroize::Zeroize;
 
 use crate::MyApp;
 
@@ -12,16 +13,15 @@ impl TextBuffer for MyApp {
     }
 
     fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
-        self.has_not_saved_data = true;
-        self.selected_sub_category
-            .as_mut()
-            .unwrap()
-            .text
-            .insert_text(text, char_index)
+        self.mark_selected_sub_category_dirty();
+
+        let selected_text = &mut self.selected_sub_category.as_mut().unwrap().text;
+        reserve_wiping(selected_text, text.len());
+        selected_text.insert_text(text, char_index)
     }
 
     fn delete_char_range(&mut self, char_range: std::ops::Range<usize>) {
-        self.has_not_saved_data = true;
+        self.mark_selected_sub_category_dirty();
         self.selected_sub_category
             .as_mut()
             .unwrap()
@@ -29,3 +29,34 @@ impl TextBuffer for MyApp {
             .delete_char_range(char_range)
     }
 }
+
+impl MyApp {
+    fn mark_selected_sub_category_dirty(&mut self) {
+        if let (Some(category), Some(selected_sub_category)) =
+            (&self.selected_category, &self.selected_sub_category)
+        {
+            self.changes
+                .mark_sub_category(category, &selected_sub_category.id);
+        }
+    }
+}
+
+/// Password fields are sized up front, so typing does not leave copies in reallocated buffers
+const SECRET_BUFFER_CAPACITY: usize = 256;
+
+pub fn new_secret_buffer() -> String {
+    String::with_capacity(SECRET_BUFFER_CAPACITY)
+}
+
+/// Grows the text into a new buffer wiping the old one, which a growing String would leave behind
+fn reserve_wiping(text: &mut String, additional: usize) {
+    let required = text.len() + additional;
+
+    if required <= text.capacity() {
+        return;
+    }
+
+    let mut grown = String::with_capacity(required.max(text.capacity() * 2));
+    grown.push_str(text);
+    std::mem::replace(text, grown).zeroize();
+}
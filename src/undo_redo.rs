@@ -0,0 +1,209 @@
+use crate::{history::*, native_dialogs::show_error, MyApp};
+
+enum HistoryJump {
+    Undo(usize),
+    Redo(usize),
+}
+
+impl MyApp {
+    /// Applies the command to the content and puts it into the undo history
+    pub fn execute(&mut self, command: VaultCommand) -> bool {
+        self.flush_text_edit();
+        self.update_edited_content();
+
+        if let Err(err) = self.vault.apply(&command) {
+            show_error(&err.to_string());
+            return false;
+        }
+
+        let (category, sub_category) = command.focus();
+        self.focus_on(category, sub_category);
+        self.changes.mark_command(&command);
+        self.history.push(command);
+        true
+    }
+
+    /// The command stays in the history if it can not be undone. Returns false then
+    pub fn undo(&mut self) -> bool {
+        self.flush_text_edit();
+
+        let inverse = match self.history.last_undo() {
+            Some(command) => command.inverse(),
+            None => return false,
+        };
+
+        self.update_edited_content();
+
+        if let Err(err) = self.vault.apply(&inverse) {
+            show_error(&err.to_string());
+            return false;
+        }
+
+        let (category, sub_category) = inverse.focus();
+        self.focus_on(category, sub_category);
+        self.changes.mark_command(&inverse);
+
+        if let Some(command) = self.history.pop_undo() {
+            self.history.undone(command);
+        }
+
+        true
+    }
+
+    /// The command stays in the history if it can not be redone. Returns false then
+    pub fn redo(&mut self) -> bool {
+        self.flush_text_edit();
+
+        let command = match self.history.last_redo() {
+            Some(command) => command.clone(),
+            None => return false,
+        };
+
+        self.update_edited_content();
+
+        if let Err(err) = self.vault.apply(&command) {
+            show_error(&err.to_string());
+            return false;
+        }
+
+        let (category, sub_category) = command.focus();
+        self.focus_on(category, sub_category);
+        self.changes.mark_command(&command);
+
+        if let Some(command) = self.history.pop_redo() {
+            self.history.redone(command);
+        }
+
+        true
+    }
+
+    /// Records text typed since the last checkpoint as a single undoable change
+    pub fn flush_text_edit(&mut self) {
+        let (category, selected_sub_category) =
+            match (&self.selected_category, &self.selected_sub_category) {
+                (Some(category), Some(selected_sub_category)) => (category, selected_sub_category),
+                _ => return,
+            };
+
+        let checkpoint = match self.history.get_text_checkpoint() {
+            Some(checkpoint) => checkpoint,
+            None => return,
+        };
+
+        if &checkpoint.category != category
+            || checkpoint.sub_category != selected_sub_category.id
+            || checkpoint.text == selected_sub_category.text
+        {
+            return;
+        }
+
+        let command = VaultCommand::EditText {
+            category: category.clone(),
+            sub_category: selected_sub_category.id.clone(),
+            before: checkpoint.text.clone(),
+            after: selected_sub_category.text.clone(),
+        };
+
+        self.history.set_text_checkpoint(
+            category,
+            &selected_sub_category.id,
+            &selected_sub_category.text,
+        );
+        self.history.push(command);
+    }
+
+    fn focus_on(&mut self, category: Option<&str>, sub_category: Option<&str>) {
+        let same_sub_category = match (&self.selected_category, &self.selected_sub_category) {
+            (Some(selected_category), Some(selected_sub_category)) => {
+                Some(selected_category.as_str()) == category
+                    && Some(selected_sub_category.id.as_str()) == sub_category
+            }
+            _ => false,
+        };
+
+        if !same_sub_category {
+            self.edit_state.wipe();
+        }
+
+        self.selected_category = category.map(|category| category.to_string());
+        self.selected_sub_category = None;
+        self.history.clear_text_checkpoint();
+
+        if let Some(sub_category) = sub_category {
+            self.show_sub_category(Some(sub_category.to_string()));
+        }
+    }
+
+    pub fn handle_history_shortcuts(&mut self, ctx: &egui::Context) {
+        let (redo, undo) = ctx.input_mut(|i| {
+            let redo = i.consume_key(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            );
+            let undo = i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+            (redo, undo)
+        });
+
+        if redo {
+            self.redo();
+        } else if undo {
+            self.undo();
+        }
+    }
+
+    pub fn render_history_window(&mut self, ctx: &egui::Context) {
+        if !self.show_history {
+            return;
+        }
+
+        let mut show_history = true;
+        let mut jump = None;
+
+        egui::Window::new("History")
+            .open(&mut show_history)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (index, command) in self.history.undo_items().iter().enumerate() {
+                        if ui
+                            .selectable_label(false, command.description())
+                            .on_hover_text("Undo up to this change")
+                            .clicked()
+                        {
+                            jump = Some(HistoryJump::Undo(index));
+                        }
+                    }
+
+                    for (index, command) in self.history.redo_items().iter().enumerate().rev() {
+                        let text = egui::RichText::new(command.description()).weak();
+                        if ui
+                            .selectable_label(false, text)
+                            .on_hover_text("Redo up to this change")
+                            .clicked()
+                        {
+                            jump = Some(HistoryJump::Redo(index));
+                        }
+                    }
+                });
+            });
+
+        self.show_history = show_history;
+
+        match jump {
+            Some(HistoryJump::Undo(index)) => {
+                while self.history.undo_items().len() > index {
+                    if !self.undo() {
+                        break;
+                    }
+                }
+            }
+            Some(HistoryJump::Redo(index)) => {
+                while self.history.redo_items().len() > index {
+                    if !self.redo() {
+                        break;
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+}
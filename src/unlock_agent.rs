@@ -0,0 +1,30 @@
+use my_secrets_core::{unlock_agent::AgentClient, Vault, VaultError};
+
+use crate::settings::SettingsModel;
+
+/// Unlocks the vault with the key the unlock agent has, so the password is not asked.
+/// False if the agent is not running, has no key or the key is not the one of this vault
+pub fn unlock_from_agent(vault: &mut Vault, settings: &SettingsModel) -> bool {
+    let aes_key = match AgentClient::default().get_key(settings.get_iv()) {
+        Ok(Some(aes_key)) => aes_key,
+        Ok(None) | Err(VaultError::Agent(_)) => return false,
+        Err(err) => {
+            eprintln!("Can not get the key from the unlock agent. Err: {}", err);
+            return false;
+        }
+    };
+
+    vault.unlock_with_key(aes_key).is_ok()
+}
+
+/// Gives the key of the vault unlocked with the password to the agent if it is running
+pub fn add_key_to_agent(vault: &Vault) {
+    let Ok(aes_key) = vault.get_aes_key() else {
+        return;
+    };
+
+    match AgentClient::default().add_key(aes_key) {
+        Ok(()) | Err(VaultError::Agent(_)) => {}
+        Err(err) => eprintln!("Can not give the key to the unlock agent. Err: {}", err),
+    }
+}
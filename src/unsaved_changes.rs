@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::MyApp;
+
+const RECOVERY_DRAFT_INTERVAL: Duration = Duration::from_secs(30);
+
+pub enum UnsavedChangesDecision {
+    Save,
+    Discard,
+    Cancel,
+}
+
+pub fn ask_about_unsaved_changes(description: &str) -> UnsavedChangesDecision {
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Not saved changes")
+            .set_buttons(MessageButtons::YesNoCancelCustom(
+                "Save".to_string(),
+                "Discard".to_string(),
+                "Cancel".to_string(),
+            ))
+            .set_description(description)
+            .show()
+    });
+
+    match dialog_result {
+        MessageDialogResult::Yes => UnsavedChangesDecision::Save,
+        MessageDialogResult::No => UnsavedChangesDecision::Discard,
+        MessageDialogResult::Custom(button) if button == "Save" => UnsavedChangesDecision::Save,
+        MessageDialogResult::Custom(button) if button == "Discard" => {
+            UnsavedChangesDecision::Discard
+        }
+        _ => UnsavedChangesDecision::Cancel,
+    }
+}
+
+pub fn ask_to_restore_draft() -> bool {
+    let dialog_result = crate::native_dialogs::show(|| {
+        MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("Recovery")
+        .set_buttons(MessageButtons::YesNo)
+        .set_description(
+            "Not saved changes from the previous session were found. Do you want to restore them?",
+        )
+        .show()
+    });
+
+    matches!(dialog_result, MessageDialogResult::Yes)
+}
+
+impl MyApp {
+    pub fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|i| i.viewport().close_requested()) {
+            return;
+        }
+
+        if !self.has_not_saved_data() {
+            return;
+        }
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+
+        match ask_about_unsaved_changes(
+            "There are not saved changes. Do you want to save them before closing?",
+        ) {
+            UnsavedChangesDecision::Save => {
+                self.save_to_file();
+
+                if !self.has_not_saved_data() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+            UnsavedChangesDecision::Discard => {
+                self.delete_draft();
+                self.changes.clear();
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            UnsavedChangesDecision::Cancel => {}
+        }
+    }
+
+    /// Keeps an encrypted copy of not saved changes on disk, so they survive a crash
+    pub fn save_recovery_draft_if_needed(&mut self) {
+        if !self.has_not_saved_data() || !self.vault.is_unlocked() {
+            return;
+        }
+
+        if self.recovery_draft_saved_at.elapsed() < RECOVERY_DRAFT_INTERVAL {
+            return;
+        }
+
+        self.save_draft();
+    }
+
+    pub fn save_draft(&mut self) {
+        self.update_edited_content();
+
+        if let Err(err) = self.vault.save_draft() {
+            eprintln!("Can not save draft. Err: {}", err);
+        }
+
+        self.recovery_draft_saved_at = Instant::now();
+    }
+
+    pub fn delete_draft(&self) {
+        if let Err(err) = self.vault.delete_draft() {
+            eprintln!("Can not delete draft. Err: {}", err);
+        }
+    }
+}